@@ -0,0 +1,63 @@
+//! Measures the throughput of reading large contiguous floating-point datasets.
+//!
+//! Run with `cargo bench --bench read`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use hdf5file::writer::Hdf5Writer;
+use hdf5file::Hdf5File;
+
+const ELEMENT_COUNT: u64 = 1 << 20;
+
+/// Builds a file containing a dataset of `f32` (`/f32`) and one of `f64` (`/f64`).
+fn fixture() -> Vec<u8> {
+    let f32_values = (0..ELEMENT_COUNT)
+        .map(|i| i as f32 * 0.5)
+        .collect::<Vec<_>>();
+    let f64_values = (0..ELEMENT_COUNT)
+        .map(|i| i as f64 * 0.5)
+        .collect::<Vec<_>>();
+
+    let mut writer = Hdf5Writer::new(Vec::new());
+    writer
+        .add_dataset("f32", &[ELEMENT_COUNT], &f32_values)
+        .expect("cannot add the f32 dataset");
+    writer
+        .add_dataset("f64", &[ELEMENT_COUNT], &f64_values)
+        .expect("cannot add the f64 dataset");
+    writer.finish().expect("cannot write the fixture")
+}
+
+fn read(c: &mut Criterion) {
+    let mut file = Hdf5File::from_bytes(fixture()).expect("cannot open the fixture");
+
+    let mut group = c.benchmark_group("read");
+    group.sample_size(20);
+    for &(name, element_size) in &[("f32", 4), ("f64", 8)] {
+        let mut dataset = file
+            .dataset(format!("/{}", name))
+            .expect("cannot open the dataset")
+            .expect("no such dataset");
+        group.throughput(Throughput::Bytes(ELEMENT_COUNT * element_size));
+        group.bench_function(name, |b| {
+            b.iter(|| black_box(dataset.read().expect("cannot read the dataset")))
+        });
+    }
+
+    let mut dataset = file
+        .dataset("/f64")
+        .expect("cannot open the dataset")
+        .expect("no such dataset");
+    group.throughput(Throughput::Bytes(ELEMENT_COUNT * 8));
+    group.bench_function("f64 iter", |b| {
+        b.iter(|| {
+            let mut sum = 0.0;
+            for v in dataset.iter_f64().expect("cannot iterate the dataset") {
+                sum += v.expect("cannot read an element");
+            }
+            black_box(sum)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, read);
+criterion_main!(benches);
@@ -1,10 +1,15 @@
 #[macro_use]
 extern crate trackable;
 
-use hdf5file;
+use hdf5file::DataObject;
+use ndarray::{ArrayViewD, Axis};
+use std::fmt::Display;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
+/// The number of leading and trailing elements printed per dimension by `dump`.
+const EDGE_ITEMS: usize = 3;
+
 #[derive(Debug, StructOpt)]
 struct Opt {
     hdf5_file: PathBuf,
@@ -18,6 +23,8 @@ struct Opt {
 enum Op {
     Get { object_path: PathBuf },
     Ls,
+    Dump { object_path: PathBuf },
+    Tree,
 }
 
 fn main() -> trackable::result::TopLevelResult {
@@ -36,6 +43,105 @@ fn main() -> trackable::result::TopLevelResult {
                 );
             }
         }
+        Op::Tree => {
+            let mut object_paths =
+                track!(track!(file.object_paths())?.collect::<hdf5file::Result<Vec<_>>>())?;
+            object_paths.sort();
+
+            println!("/");
+            for object_path in object_paths {
+                let depth = object_path.components().count() - 1;
+                let indent = "  ".repeat(depth);
+                let name = track_assert_some!(
+                    object_path.file_name().and_then(|n| n.to_str()),
+                    trackable::error::Failed
+                );
+                if let Some(dataset) = track!(file.dataset(&object_path))? {
+                    println!(
+                        "{}{} {:?} {:?}",
+                        indent,
+                        name,
+                        track!(dataset.shape())?,
+                        track!(dataset.dtype())?
+                    );
+                } else {
+                    println!("{}{}/", indent, name);
+                }
+            }
+        }
+        Op::Dump { object_path } => {
+            let object = track!(file.get_object(&object_path))?;
+            let object = track_assert_some!(
+                object,
+                trackable::error::Failed,
+                "No such object: {:?}",
+                object_path
+            );
+            dump_object(&object);
+        }
     }
     Ok(())
 }
+
+fn dump_object(object: &DataObject) {
+    match object {
+        DataObject::Float(a) => dump("f64", a.view()),
+        DataObject::Float32(a) => dump("f32", a.view()),
+        DataObject::U8(a) => dump("u8", a.view()),
+        DataObject::U16(a) => dump("u16", a.view()),
+        DataObject::I64(a) => dump("i64", a.view()),
+        DataObject::U64(a) => dump("u64", a.view()),
+        DataObject::Bool(a) => dump("bool", a.view()),
+        DataObject::Compound(t) => {
+            for (name, column) in t.columns() {
+                println!("column: {}", name);
+                dump_object(column);
+            }
+        }
+        DataObject::Enum { codes, mapping } => {
+            let label = |v: &i64| match mapping.iter().find(|(_, x)| x == v) {
+                Some((name, _)) => name.clone(),
+                None => v.to_string(),
+            };
+            dump("enum", codes.map(label).view())
+        }
+        DataObject::Values(a) => dump("value", a.map(|v| format!("{:?}", v)).view()),
+    }
+}
+
+fn dump<T: Display>(dtype: &str, array: ArrayViewD<T>) {
+    println!("shape: {:?}", array.shape());
+    println!("dtype: {}", dtype);
+    println!("{}", format_array(array, 0));
+}
+
+fn format_array<T: Display>(array: ArrayViewD<T>, depth: usize) -> String {
+    if array.ndim() == 0 {
+        return array.iter().map(|x| x.to_string()).collect();
+    }
+
+    let len = array.len_of(Axis(0));
+    let indices = if len > EDGE_ITEMS * 2 {
+        (0..EDGE_ITEMS)
+            .map(Some)
+            .chain(std::iter::once(None))
+            .chain((len - EDGE_ITEMS..len).map(Some))
+            .collect::<Vec<_>>()
+    } else {
+        (0..len).map(Some).collect()
+    };
+    let items = indices
+        .into_iter()
+        .map(|i| match i {
+            Some(i) => format_array(array.index_axis(Axis(0), i), depth + 1),
+            None => "...".to_owned(),
+        })
+        .collect::<Vec<_>>();
+
+    if array.ndim() == 1 {
+        format!("[{}]", items.join(", "))
+    } else {
+        let separator = format!(",\n{}", " ".repeat(depth + 1));
+        format!("[{}]", items.join(&separator))
+    }
+}
@@ -0,0 +1,160 @@
+//! Multi-dimensional arrays returned by reading operations.
+//!
+//! If the `ndarray` feature is enabled (default), `Array<T>` is `ndarray::ArrayD<T>`.
+//! Otherwise, it is a minimal row-major array consisting of a flat `Vec<T>` and a shape.
+#[cfg(feature = "ndarray")]
+use crate::Error;
+use crate::{ErrorKind, Result};
+
+/// Multi-dimensional array.
+#[cfg(feature = "ndarray")]
+pub type Array<T> = ndarray::ArrayD<T>;
+
+/// Makes a new array from the given shape and row-major items.
+pub(crate) fn from_shape_vec<T>(shape: Vec<usize>, items: Vec<T>) -> Result<Array<T>> {
+    #[cfg(feature = "ndarray")]
+    {
+        track!(Array::from_shape_vec(shape, items).map_err(Error::from))
+    }
+    #[cfg(not(feature = "ndarray"))]
+    {
+        track!(Array::from_shape_vec(shape, items))
+    }
+}
+
+/// Checks that `order` is a permutation of the axes of an array of the given rank.
+pub(crate) fn check_permutation(order: &[usize], rank: usize) -> Result<()> {
+    track_assert_eq!(order.len(), rank, ErrorKind::InvalidInput; order);
+    let mut seen = vec![false; rank];
+    for &axis in order {
+        track_assert!(
+            axis < rank && !seen[axis],
+            ErrorKind::InvalidInput,
+            "Not a permutation of {} axes: {:?}",
+            rank,
+            order
+        );
+        seen[axis] = true;
+    }
+    Ok(())
+}
+
+/// Makes a new row-major array whose `i`-th axis is the `order[i]`-th axis of `array`.
+///
+/// If `order` isn't a permutation of the axes, an `ErrorKind::InvalidInput` error will be returned.
+#[cfg(feature = "ndarray")]
+pub(crate) fn permute_axes<T: Clone>(array: Array<T>, order: &[usize]) -> Result<Array<T>> {
+    track!(check_permutation(order, array.ndim()))?;
+
+    // `permuted_axes` only swaps the strides, so the items are copied into the standard layout.
+    let permuted = array.permuted_axes(order.to_vec());
+    let shape = permuted.shape().to_vec();
+    let items = permuted.iter().cloned().collect();
+    track!(from_shape_vec(shape, items))
+}
+
+/// Makes a new row-major array whose `i`-th axis is the `order[i]`-th axis of `array`.
+///
+/// If `order` isn't a permutation of the axes, an `ErrorKind::InvalidInput` error will be returned.
+#[cfg(not(feature = "ndarray"))]
+pub(crate) fn permute_axes<T: Clone>(array: Array<T>, order: &[usize]) -> Result<Array<T>> {
+    let shape = array.shape().to_vec();
+    track!(check_permutation(order, shape.len()))?;
+    let items = array.into_raw_vec();
+
+    let mut strides = vec![1; shape.len()];
+    for i in (1..shape.len()).rev() {
+        strides[i - 1] = strides[i] * shape[i];
+    }
+    let new_shape = order.iter().map(|&axis| shape[axis]).collect::<Vec<_>>();
+    let mut index = vec![0; new_shape.len()];
+    let mut permuted = Vec::with_capacity(items.len());
+    for _ in 0..items.len() {
+        let offset = index
+            .iter()
+            .zip(order)
+            .map(|(&i, &axis)| i * strides[axis])
+            .sum::<usize>();
+        permuted.push(items[offset].clone());
+        for d in (0..index.len()).rev() {
+            index[d] += 1;
+            if index[d] < new_shape[d] {
+                break;
+            }
+            index[d] = 0;
+        }
+    }
+    track!(from_shape_vec(new_shape, permuted))
+}
+
+/// Multi-dimensional array (in row-major order).
+#[cfg(not(feature = "ndarray"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Array<T> {
+    shape: Vec<usize>,
+    items: Vec<T>,
+}
+#[cfg(not(feature = "ndarray"))]
+impl<T> Array<T> {
+    /// Makes a new `Array` instance from the given shape and row-major items.
+    ///
+    /// If the number of items doesn't match the shape, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn from_shape_vec(shape: Vec<usize>, items: Vec<T>) -> Result<Self> {
+        track_assert_eq!(
+            shape.iter().product::<usize>(),
+            items.len(),
+            ErrorKind::InvalidInput;
+            shape
+        );
+        Ok(Self { shape, items })
+    }
+
+    /// Returns the shape of the array.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Returns the number of dimensions of the array.
+    pub fn ndim(&self) -> usize {
+        self.shape.len()
+    }
+
+    /// Returns the number of elements in the array.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the array has no elements, otherwise `false`.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns an iterator over the elements in row-major order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Returns the elements in row-major order.
+    ///
+    /// This always returns `Some(_)` (the return type mirrors the `ndarray` one).
+    pub fn as_slice(&self) -> Option<&[T]> {
+        Some(&self.items)
+    }
+
+    /// Converts the array into the elements in row-major order.
+    pub fn into_raw_vec(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Makes a new array by applying `f` to each element.
+    pub fn mapv<U, F>(&self, f: F) -> Array<U>
+    where
+        T: Clone,
+        F: FnMut(T) -> U,
+    {
+        Array {
+            shape: self.shape.clone(),
+            items: self.items.iter().cloned().map(f).collect(),
+        }
+    }
+}
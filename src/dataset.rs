@@ -0,0 +1,655 @@
+use crate::array;
+use crate::io::{FileContext, ReadExt as _, SeekExt as _};
+use crate::lowlevel::level0::UNDEFINED_ADDRESS;
+use crate::lowlevel::level1::GlobalHeapCollection;
+use crate::lowlevel::level2::{
+    DatatypeClass, DatatypeMessage, FixedPointDatatype, FloatingPointDatatype, Layout,
+    ObjectHeader, RegionReference, VirtualMapping,
+};
+use crate::{
+    Array, ChunkInfo, DataObject, Error, ErrorKind, FromDataObject, ReaderOptions, Result,
+    ScalarValue,
+};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+
+/// Storage layout of a dataset.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StorageLayout {
+    /// The data is stored in the object header of the dataset.
+    Compact,
+
+    /// The data is stored in a single contiguous block of the file.
+    Contiguous,
+
+    /// The data is split into equally-shaped chunks indexed by a B-tree.
+    Chunked {
+        /// Shape of each chunk.
+        chunk_dimensions: Vec<usize>,
+    },
+
+    /// The data is mapped from other datasets (see `Dataset::virtual_mappings`).
+    Virtual,
+}
+
+/// Dataset.
+#[derive(Debug)]
+pub struct Dataset<'a, T: 'a = File> {
+    io: &'a mut FileContext<T>,
+    header: ObjectHeader,
+    options: &'a ReaderOptions,
+}
+impl<'a, T> Dataset<'a, T>
+where
+    T: Read + Seek,
+{
+    pub(crate) fn new(
+        io: &'a mut FileContext<T>,
+        header: ObjectHeader,
+        options: &'a ReaderOptions,
+    ) -> Self {
+        Self {
+            io,
+            header,
+            options,
+        }
+    }
+
+    /// Returns the shape of the dataset.
+    pub fn shape(&self) -> Result<Vec<usize>> {
+        let dimensions = track!(self.header.dimensions())?;
+        Ok(dimensions.iter().map(|&d| d as usize).collect())
+    }
+
+    /// Returns the maximum shape of the dataset.
+    ///
+    /// `None` elements are unlimited dimensions (i.e., the dataset is extendible along them).
+    pub fn max_shape(&self) -> Result<Vec<Option<usize>>> {
+        let dimensions = track!(self.header.max_dimensions())?;
+        Ok(dimensions
+            .into_iter()
+            .map(|d| d.map(|d| d as usize))
+            .collect())
+    }
+
+    /// Returns the number of elements in the dataset.
+    ///
+    /// This doesn't read the data of the dataset.
+    pub fn element_count(&self) -> Result<usize> {
+        let shape = track!(self.shape())?;
+        let count = shape.iter().try_fold(1usize, |n, &d| n.checked_mul(d));
+        Ok(track_assert_some!(count, ErrorKind::InvalidFile; shape))
+    }
+
+    /// Returns how the data of the dataset is stored in the file.
+    ///
+    /// This only looks at the data layout message and doesn't read the data.
+    pub fn storage_layout(&self) -> Result<StorageLayout> {
+        match *track!(self.header.layout())? {
+            Layout::Compact { .. } => Ok(StorageLayout::Compact),
+            Layout::Contiguous { .. } => Ok(StorageLayout::Contiguous),
+            Layout::Chunked {
+                ref chunk_dimensions,
+                ..
+            } => Ok(StorageLayout::Chunked {
+                chunk_dimensions: chunk_dimensions.iter().map(|&d| d as usize).collect(),
+            }),
+            Layout::Virtual { .. } => Ok(StorageLayout::Virtual),
+        }
+    }
+
+    /// Returns the total size of the data of the dataset stored in the file in bytes.
+    ///
+    /// For chunked datasets, this is the sum of the (filtered) sizes of the allocated chunks,
+    /// and only the chunk index is read.
+    /// Virtual datasets store no data of their own, so their size is zero.
+    pub fn byte_size(&mut self) -> Result<u64> {
+        match *track!(self.header.layout())? {
+            Layout::Compact { ref data } => Ok(data.len() as u64),
+            Layout::Contiguous { size, .. } => Ok(size),
+            Layout::Chunked { .. } => {
+                let chunks = track!(self.chunk_map())?;
+                Ok(chunks.iter().map(|c| c.size).sum())
+            }
+            Layout::Virtual { .. } => Ok(0),
+        }
+    }
+
+    /// Returns the class of the datatype of the dataset.
+    pub fn dtype(&self) -> Result<DatatypeClass> {
+        track!(self.header.datatype()).map(|t| t.class())
+    }
+
+    /// Returns the full datatype of the dataset.
+    ///
+    /// Unlike `dtype`, this exposes the element size, the byte order and the layout of compound members.
+    pub fn dtype_message(&self) -> Result<DatatypeMessage> {
+        track!(self.header.datatype())
+    }
+
+    /// Returns the fill value of the dataset decoded with the datatype of the dataset.
+    ///
+    /// If no fill value is defined, `Ok(None)` will be returned.
+    pub fn fill_value(&self) -> Result<Option<ScalarValue>> {
+        track!(self.header.fill_value_scalar())
+    }
+
+    /// Returns the datatype and the shape of the attribute named `name` without decoding its value.
+    ///
+    /// If there is no such attribute, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn attribute_info(&self, name: &str) -> Result<(DatatypeMessage, Vec<usize>)> {
+        track!(self.header.attribute_info(name))
+    }
+
+    /// Reads the value of the attribute named `name` (see `ObjectHeader::read_attribute`).
+    ///
+    /// If there is no such attribute, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn read_attribute(&self, name: &str) -> Result<ScalarValue> {
+        track!(self.header.read_attribute(name))
+    }
+
+    /// Returns `true` if the dataset is a dimension scale (i.e., its `CLASS` attribute is `"DIMENSION_SCALE"`).
+    pub fn is_dimension_scale(&self) -> Result<bool> {
+        let attribute = match self.header.attribute("CLASS") {
+            None => return Ok(false),
+            Some(attribute) => attribute,
+        };
+        if let DatatypeMessage::String(t) = attribute.datatype() {
+            Ok(track!(t.decode(attribute.data()))? == "DIMENSION_SCALE")
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Returns the addresses of the dimension scales attached to each dimension of the dataset.
+    ///
+    /// The scales are resolved via the `DIMENSION_LIST` attribute.
+    /// `None` means that no scale is attached to the dimension,
+    /// and if several scales are attached to a dimension, the first one is returned.
+    /// The scale datasets can be opened with `Hdf5File::dataset_at`.
+    pub fn dimension_scales(&mut self) -> Result<Vec<Option<u64>>> {
+        let rank = track!(self.header.dimensions())?.len();
+        let attribute = match self.header.attribute("DIMENSION_LIST") {
+            None => return Ok(vec![None; rank]),
+            Some(attribute) => attribute,
+        };
+        let is_reference_list = match attribute.datatype() {
+            DatatypeMessage::VariableLength(t) => {
+                !t.is_string()
+                    && matches!(t.base(), DatatypeMessage::Reference(r) if r.is_object_reference())
+            }
+            _ => false,
+        };
+        track_assert!(
+            is_reference_list,
+            ErrorKind::InvalidFile,
+            "DIMENSION_LIST isn't a list of object references: {}",
+            attribute.datatype()
+        );
+        track_assert_eq!(
+            attribute.dimensions(),
+            [rank as u64],
+            ErrorKind::InvalidFile
+        );
+
+        // Each element is a sequence length followed by a global heap ID (collection address and object index).
+        let mut data = attribute.data();
+        let mut reader = BufReader::new(&mut *self.io);
+        let mut scales = Vec::with_capacity(rank);
+        for _ in 0..rank {
+            let len = track!(data.read_u32())?;
+            let collection_address = track!(data.read_u64())?;
+            let index = track!(data.read_u32())?;
+            if len == 0 {
+                scales.push(None);
+                continue;
+            }
+
+            track!(reader.seek_to(collection_address))?;
+            let collection = track!(reader.with_offset("global heap collection", |r| {
+                track!(GlobalHeapCollection::from_reader(r))
+            }))?;
+            let mut object = track_assert_some!(
+                collection.object(index),
+                ErrorKind::InvalidFile;
+                collection_address,
+                index
+            );
+            scales.push(Some(track!(object.read_u64())?));
+        }
+        Ok(scales)
+    }
+
+    /// Returns `true` if the creation order of the attributes of the dataset is tracked.
+    ///
+    /// If it isn't, enumerating the attributes by creation order isn't meaningful.
+    pub fn tracks_attribute_creation_order(&self) -> bool {
+        self.header.is_attribute_creation_order_tracked()
+    }
+
+    /// Returns an iterator over the type codes of the messages in the object header of the dataset.
+    ///
+    /// See: https://support.hdfgroup.org/HDF5/doc/H5.format.html#ObjectHeaderMessages
+    pub fn message_types(&self) -> impl Iterator<Item = u16> + '_ {
+        self.header.messages().map(|(kind, _)| kind)
+    }
+
+    /// Reads the mappings from source datasets if the dataset is virtual.
+    ///
+    /// Only the mappings are available; reading the data of a virtual dataset results in an `ErrorKind::Unsupported` error.
+    /// If the dataset isn't virtual, an empty vector will be returned.
+    pub fn virtual_mappings(&mut self) -> Result<Vec<VirtualMapping>> {
+        track!(self
+            .header
+            .virtual_mappings(BufReader::new(&mut *self.io), self.options))
+    }
+
+    /// Reads the dataset region references stored in the dataset (in row-major order).
+    ///
+    /// `None` is returned for null references.
+    /// If the dataset doesn't consist of region references, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn read_region_references(&mut self) -> Result<Vec<Option<RegionReference>>> {
+        track!(self
+            .header
+            .region_references(BufReader::new(&mut *self.io), self.options))
+    }
+
+    /// Returns the locations of the chunks of the dataset.
+    ///
+    /// This walks the chunk index, but doesn't read the chunks themselves.
+    /// If the dataset isn't chunked, an empty vector will be returned.
+    pub fn chunk_map(&mut self) -> Result<Vec<ChunkInfo>> {
+        track!(self
+            .header
+            .chunks(BufReader::new(&mut *self.io), self.options))
+    }
+
+    /// Reads the given chunk and reverts the filters applied to it.
+    ///
+    /// The filters are decoded by using the `FilterRegistry` specified via `ReaderOptions`.
+    pub fn read_chunk(&mut self, chunk: &ChunkInfo) -> Result<Vec<u8>> {
+        track!(self
+            .header
+            .read_chunk(BufReader::new(&mut *self.io), chunk, self.options))
+    }
+
+    /// Reads the data of the dataset.
+    pub fn read(&mut self) -> Result<DataObject> {
+        track!(self
+            .header
+            .get_data_object(BufReader::new(&mut *self.io), self.options))
+    }
+
+    /// Reads the data of the dataset with its axes reordered (see `DataObject::permuted_axes`).
+    ///
+    /// For example, `&[1, 0]` reads a two-dimensional dataset transposed.
+    /// If `order` isn't a permutation of the axes of the dataset,
+    /// an `ErrorKind::InvalidInput` error will be returned without reading the data.
+    pub fn read_with_axes(&mut self, order: &[usize]) -> Result<DataObject> {
+        let rank = track!(self.shape())?.len();
+        track!(array::check_permutation(order, rank))?;
+        let object = track!(self.read())?;
+        track!(object.permuted_axes(order))
+    }
+
+    /// Reads the data of the dataset and converts every element to `f64`.
+    ///
+    /// Unlike `into_array::<f64>`, this never fails due to a loss of precision:
+    /// 64-bit integers beyond 2^53 are rounded to the nearest `f64`.
+    /// Booleans and enumeration codes are converted as integers.
+    /// If the data is compound, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn read_as_f64_lossy(&mut self) -> Result<Array<f64>> {
+        Ok(match track!(self.read())? {
+            DataObject::Float(a) => a,
+            DataObject::Float32(a) => a.mapv(f64::from),
+            DataObject::U8(a) => a.mapv(f64::from),
+            DataObject::U16(a) => a.mapv(f64::from),
+            DataObject::I64(a) => a.mapv(|v| v as f64),
+            DataObject::U64(a) => a.mapv(|v| v as f64),
+            DataObject::Bool(a) => a.mapv(|v| f64::from(u8::from(v))),
+            DataObject::Enum { codes, .. } => codes.mapv(|v| v as f64),
+            DataObject::Compound(_) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Cannot convert compound values to `f64`"
+            ),
+            DataObject::Values(_) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Cannot convert untyped values to `f64`"
+            ),
+        })
+    }
+
+    /// Reads a dataset of 1-byte integers (e.g., `uint8` arrays holding text) as strings.
+    ///
+    /// Each row along the last dimension becomes a string, so a one-dimensional dataset results in a single string
+    /// (and a scalar one in a single one-character string).
+    /// If `trim` is `true`, trailing null bytes and spaces are removed from each string.
+    ///
+    /// If the datatype isn't a 1-byte fixed-point one, an `ErrorKind::InvalidInput` error will be returned,
+    /// and if a row isn't valid UTF-8, an `ErrorKind::InvalidFile` error will be returned.
+    pub fn read_char_array_as_strings(&mut self, trim: bool) -> Result<Vec<String>> {
+        let datatype = track!(self.header.datatype())?;
+        track_assert!(
+            datatype.class() == DatatypeClass::FixedPoint && datatype.size() == 1,
+            ErrorKind::InvalidInput,
+            "Not a 1-byte integer datatype: {}",
+            datatype
+        );
+        let shape = track!(self.shape())?;
+        let row_len = shape.last().cloned().unwrap_or(1);
+        if row_len == 0 {
+            let rows = shape
+                .iter()
+                .rev()
+                .skip(1)
+                .try_fold(1usize, |n, &d| n.checked_mul(d));
+            let rows = track_assert_some!(rows, ErrorKind::InvalidFile; shape);
+            return Ok(vec![String::new(); rows]);
+        }
+        let bytes = track!(self
+            .header
+            .get_data_bytes(BufReader::new(&mut *self.io), self.options))?;
+        bytes
+            .chunks(row_len)
+            .map(|row| {
+                let len = if trim {
+                    row.iter()
+                        .rposition(|&b| b != 0 && b != b' ')
+                        .map_or(0, |i| i + 1)
+                } else {
+                    row.len()
+                };
+                track!(String::from_utf8(row[..len].to_vec()).map_err(Error::from))
+            })
+            .collect()
+    }
+
+    /// Reads the element at the given flat (row-major) index without reading the whole dataset.
+    ///
+    /// If the index is out of range, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn read_element(&mut self, flat_index: usize) -> Result<ScalarValue> {
+        track!(self
+            .header
+            .read_element(BufReader::new(&mut *self.io), self.options, flat_index))
+    }
+
+    /// Reads the data of the dataset together with the offsets of the chunks that couldn't be decoded.
+    ///
+    /// If `ReaderOptions::skip_corrupt_chunks` is enabled, such chunks are filled with the fill value
+    /// (or zeros) and their offsets are returned, otherwise the first failure is returned as an error.
+    pub fn read_with_corrupt_chunks(&mut self) -> Result<(DataObject, Vec<Vec<u64>>)> {
+        let mut corrupt_chunks = Vec::new();
+        let object = track!(self.header.get_data_object_with(
+            BufReader::new(&mut *self.io),
+            self.options,
+            &mut corrupt_chunks
+        ))?;
+        Ok((object, corrupt_chunks))
+    }
+
+    /// Reads the data of the dataset into `buf` as values of type `U` (in row-major order).
+    ///
+    /// If the length of `buf` differs from the number of elements of the dataset,
+    /// an `ErrorKind::InvalidInput` error will be returned without reading the data.
+    /// This allows a buffer to be reused across many datasets of the same shape.
+    pub fn read_into_slice<U: FromDataObject>(&mut self, buf: &mut [U]) -> Result<()> {
+        let count = track!(self.element_count())?;
+        track_assert_eq!(buf.len(), count, ErrorKind::InvalidInput);
+
+        let datatype = track!(self.header.datatype())?;
+        let element_size = datatype.size() as usize;
+        track_assert_ne!(element_size, 0, ErrorKind::InvalidFile);
+        let data_size = track_assert_some!(
+            count.checked_mul(element_size),
+            ErrorKind::InvalidFile;
+            count
+        );
+
+        // Unfiltered contiguous data is read from the file block by block,
+        // and the others are decoded block by block after their chunks are assembled.
+        match *track!(self.header.layout())? {
+            Layout::Contiguous { address, size }
+                if address != UNDEFINED_ADDRESS && self.header.filter_pipeline().is_none() =>
+            {
+                track_assert_eq!(size, data_size as u64, ErrorKind::InvalidFile);
+                track!(self.io.seek_to(address))?;
+                let mut reader = BufReader::new(&mut *self.io);
+                for block in buf.chunks_mut(READ_BLOCK_SIZE) {
+                    let bytes = track!(reader.read_vec(block.len() * element_size))?;
+                    track!(decode_into(&datatype, bytes, block))?;
+                }
+            }
+            _ => {
+                let bytes = track!(self
+                    .header
+                    .get_data_bytes(BufReader::new(&mut *self.io), self.options))?;
+                track_assert_eq!(bytes.len(), data_size, ErrorKind::InvalidFile);
+                let blocks = bytes.chunks(READ_BLOCK_SIZE * element_size);
+                for (block, bytes) in buf.chunks_mut(READ_BLOCK_SIZE).zip(blocks) {
+                    track!(decode_into(&datatype, bytes.to_vec(), block))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator that decodes the elements of the dataset one at a time as `f64` values.
+    ///
+    /// The elements are yielded in row-major order and are read from the file lazily,
+    /// so the memory usage doesn't depend on the size of the dataset.
+    ///
+    /// Only compact and (unfiltered) contiguous datasets of numeric datatypes are supported.
+    /// The elements of a contiguous dataset whose storage has never been allocated are its fill value.
+    pub fn iter_f64(&mut self) -> Result<ElementsF64<'_>> {
+        let datatype = track!(self.header.datatype())?;
+        let decoder = track!(ElementDecoder::new(&datatype))?;
+        track_assert!(
+            self.header.filter_pipeline().is_none(),
+            ErrorKind::Unsupported,
+            "Filtered data can't be read lazily"
+        );
+
+        let remaining = track!(self.element_count())?;
+        let size = (remaining as u64).checked_mul(u64::from(datatype.size()));
+        let size = track_assert_some!(size, ErrorKind::InvalidFile; remaining);
+        let reader: Box<dyn Read + '_> = match *track!(self.header.layout())? {
+            Layout::Compact { ref data } => {
+                track_assert_eq!(data.len() as u64, size, ErrorKind::InvalidFile);
+                Box::new(std::io::Cursor::new(data.clone()))
+            }
+            Layout::Contiguous { size: 0, .. } if remaining == 0 => Box::new(std::io::empty()),
+            Layout::Contiguous {
+                address: UNDEFINED_ADDRESS,
+                ..
+            } => {
+                // The data has never been written, so every element holds the fill value.
+                let fill_value = track!(self.header.resolved_fill_value())?;
+                Box::new(RepeatedBytes::new(fill_value).take(size))
+            }
+            Layout::Contiguous { address, size: n } => {
+                track_assert_eq!(n, size, ErrorKind::InvalidFile);
+                track!(self.io.seek_to(address))?;
+                Box::new(BufReader::new(&mut *self.io).take(size))
+            }
+            Layout::Chunked { .. } => {
+                track_panic!(ErrorKind::Unsupported, "Chunked data can't be read lazily");
+            }
+            Layout::Virtual { .. } => {
+                track_panic!(ErrorKind::Unsupported, "Virtual data can't be read");
+            }
+        };
+        Ok(ElementsF64 {
+            reader,
+            decoder,
+            remaining,
+        })
+    }
+
+    /// Reads the row at the given index of a two-dimensional dataset as `f64` values.
+    ///
+    /// Only the bytes of the row are read from the file.
+    /// If the dataset isn't two-dimensional or the index is out of range,
+    /// an `ErrorKind::InvalidInput` error will be returned.
+    ///
+    /// Only compact and (unfiltered) contiguous datasets of numeric datatypes are supported.
+    pub fn read_row(&mut self, index: usize) -> Result<Vec<f64>> {
+        let shape = track!(self.shape())?;
+        track_assert_eq!(
+            shape.len(),
+            2,
+            ErrorKind::InvalidInput,
+            "Not a two-dimensional dataset"
+        );
+        track_assert!(index < shape[0], ErrorKind::InvalidInput; index, shape);
+
+        let datatype = track!(self.header.datatype())?;
+        let decoder = track!(ElementDecoder::new(&datatype))?;
+        track_assert!(
+            self.header.filter_pipeline().is_none(),
+            ErrorKind::Unsupported,
+            "Rows of filtered data can't be read directly"
+        );
+
+        let row_size = (shape[1] as u64).checked_mul(u64::from(datatype.size()));
+        let row_size = track_assert_some!(row_size, ErrorKind::InvalidFile; shape);
+        let offset = (index as u64).checked_mul(row_size);
+        let offset = track_assert_some!(offset, ErrorKind::InvalidFile; index, row_size);
+        let end = track_assert_some!(offset.checked_add(row_size), ErrorKind::InvalidFile);
+        let bytes = match *track!(self.header.layout())? {
+            Layout::Compact { ref data } => {
+                let bytes = data.get(offset as usize..end as usize);
+                track_assert_some!(bytes, ErrorKind::InvalidFile; index, data.len()).to_vec()
+            }
+            Layout::Contiguous {
+                address: UNDEFINED_ADDRESS,
+                ..
+            } => {
+                // The data has never been written, so the row holds the fill value.
+                let fill_value = track!(self.header.resolved_fill_value())?;
+                fill_value.repeat(shape[1])
+            }
+            Layout::Contiguous { address, size } => {
+                track_assert!(end <= size, ErrorKind::InvalidFile; index, size);
+                let start = track_assert_some!(address.checked_add(offset), ErrorKind::InvalidFile);
+                track!(self.io.seek_to(start))?;
+                let mut reader = BufReader::new(&mut *self.io);
+                track!(reader.with_offset("raw data", |r| track!(r.read_vec(row_size as usize))))?
+            }
+            Layout::Chunked { .. } => {
+                track_panic!(
+                    ErrorKind::Unsupported,
+                    "Rows of chunked data can't be read directly"
+                );
+            }
+            Layout::Virtual { .. } => {
+                track_panic!(ErrorKind::Unsupported, "Virtual data can't be read");
+            }
+        };
+        let mut reader = &bytes[..];
+        (0..shape[1])
+            .map(|_| track!(decoder.decode(&mut reader)))
+            .collect()
+    }
+}
+
+/// An iterator over the elements of a dataset decoded as `f64` values.
+///
+/// This is created by `Dataset::iter_f64`.
+pub struct ElementsF64<'a> {
+    reader: Box<dyn Read + 'a>,
+    decoder: ElementDecoder,
+    remaining: usize,
+}
+impl<'a> Iterator for ElementsF64<'a> {
+    type Item = Result<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let item = track!(self.decoder.decode(&mut self.reader));
+        // Stops after the first failure, since the position of the following elements is unknown.
+        self.remaining = if item.is_ok() { self.remaining - 1 } else { 0 };
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+impl<'a> std::fmt::Debug for ElementsF64<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ElementsF64")
+            .field("decoder", &self.decoder)
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
+
+/// Reader that yields the given bytes over and over again.
+#[derive(Debug)]
+struct RepeatedBytes {
+    bytes: Vec<u8>,
+    position: usize,
+}
+impl RepeatedBytes {
+    fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes, position: 0 }
+    }
+}
+impl Read for RepeatedBytes {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.bytes.is_empty() {
+            return Ok(0);
+        }
+        for b in buf.iter_mut() {
+            *b = self.bytes[self.position];
+            self.position = (self.position + 1) % self.bytes.len();
+        }
+        Ok(buf.len())
+    }
+}
+
+/// Number of elements decoded at once by `Dataset::read_into_slice`.
+const READ_BLOCK_SIZE: usize = 4096;
+
+/// Decodes the elements in `bytes` into `dst`.
+fn decode_into<U: FromDataObject>(
+    datatype: &DatatypeMessage,
+    bytes: Vec<u8>,
+    dst: &mut [U],
+) -> Result<()> {
+    let object = track!(datatype.decode_array(bytes, vec![dst.len()]))?;
+    let (items, _) = track!(object.into_vec::<U>())?;
+    for (dst, src) in dst.iter_mut().zip(items) {
+        *dst = src;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+enum ElementDecoder {
+    FixedPoint(FixedPointDatatype),
+    FloatingPoint(FloatingPointDatatype),
+}
+impl ElementDecoder {
+    fn new(datatype: &DatatypeMessage) -> Result<Self> {
+        Ok(match datatype {
+            DatatypeMessage::FixedPoint(ref t) => ElementDecoder::FixedPoint(t.clone()),
+            DatatypeMessage::FloatingPoint(ref t) => ElementDecoder::FloatingPoint(t.clone()),
+            _ => track_panic!(
+                ErrorKind::Unsupported,
+                "Not a numeric datatype: {}",
+                datatype
+            ),
+        })
+    }
+
+    fn decode<R: Read>(&self, reader: R) -> Result<f64> {
+        match self {
+            ElementDecoder::FixedPoint(t) => track!(t.decode(reader)).map(|v| v as f64),
+            ElementDecoder::FloatingPoint(t) => track!(t.decode(reader)),
+        }
+    }
+}
@@ -1,4 +1,3 @@
-use ndarray;
 use trackable::error::{
     ErrorKind as TrackableErrorKind, ErrorKindExt, Failed, Failure, TrackableError,
 };
@@ -21,6 +20,7 @@ impl From<std::string::FromUtf8Error> for Error {
         ErrorKind::InvalidFile.cause(f).into()
     }
 }
+#[cfg(feature = "ndarray")]
 impl From<ndarray::ShapeError> for Error {
     fn from(f: ndarray::ShapeError) -> Self {
         ErrorKind::InvalidInput.cause(f).into()
@@ -47,6 +47,9 @@ pub enum ErrorKind {
     /// I/O error.
     IoError,
 
+    /// A file referred to by the target file (e.g., by an external link) is not found.
+    NotFound,
+
     /// Other erorr.
     Other,
 }
@@ -1,22 +1,150 @@
-use crate::lowlevel::level0::Superblock;
-use crate::lowlevel::level1::{BTreeNode, BTreeNodeChild, LocalHeap, SymbolTableEntry};
-use crate::lowlevel::level2::DataObject;
-use crate::{Error, ErrorKind, Result};
+use crate::io::{FileContext, SeekExt as _};
+use crate::lowlevel::level0::{DriverInfo, Superblock};
+use crate::lowlevel::level1::{
+    lookup3_hash, BTreeNode, BTreeNodeChild, BTreeV2, FractalHeap, LocalHeap, SymbolTableEntry,
+};
+use crate::lowlevel::level2::{DataObject, LinkInfoMessage, LinkMessage, LinkTarget, ObjectHeader};
+use crate::{Dataset, Error, ErrorKind, FamilyMembers, PositionedReader, ReaderOptions, Result};
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::{BufReader, Read, Seek};
+use std::io::{self, BufReader, Cursor, Read, Seek};
 use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
 
 /// HDF5 file.
-#[derive(Debug)]
+///
+/// Reading operations take `&mut self` because they move the cursor of the underlying I/O stream.
+/// To read datasets concurrently, give each thread its own clone of an instance
+/// whose stream is cheap to clone (e.g., one made by `Hdf5File::open_file_shared` or `Hdf5File::from_bytes`).
+#[derive(Debug, Clone)]
 pub struct Hdf5File<T = File> {
-    io: T,
+    io: FileContext<T>,
     superblock: Superblock,
+    options: ReaderOptions,
+
+    // Directory of the file (used to resolve the file names of external links).
+    dir: Option<PathBuf>,
+
+    // Whether external links are followed (they aren't for files made by `from_reader`).
+    follow_external_links: bool,
+
+    driver_info: Option<DriverInfo>,
 }
 impl Hdf5File<File> {
     /// Makes a new `Hdf5File` instance by opening the specified file.
     pub fn open_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        track!(Self::open_file_with(path, ReaderOptions::default()))
+    }
+
+    /// Makes a new `Hdf5File` instance by opening the specified file with the given options.
+    pub fn open_file_with<P: AsRef<Path>>(path: P, options: ReaderOptions) -> Result<Self> {
+        let path = path.as_ref();
+        let file = track!(File::open(path).map_err(Error::from))?;
+        let mut this = track!(Self::open_with(file, options))?;
+        this.dir = path.parent().map(Path::to_path_buf);
+        Ok(this)
+    }
+}
+impl Hdf5File<PositionedReader<Arc<File>>> {
+    /// Makes a new `Hdf5File` instance by opening the specified file for positioned reads.
+    ///
+    /// The file is read with `pread`-style calls instead of `seek` and `read` pairs.
+    /// The clones of the resulting instance share the same file handle and have their own cursors,
+    /// so they can be used to read different datasets from multiple threads in parallel.
+    pub fn open_file_shared<P: AsRef<Path>>(path: P) -> Result<Self> {
+        track!(Self::open_file_shared_with(path, ReaderOptions::default()))
+    }
+
+    /// Same as `open_file_shared` except that this takes the options.
+    pub fn open_file_shared_with<P: AsRef<Path>>(path: P, options: ReaderOptions) -> Result<Self> {
+        let path = path.as_ref();
         let file = track!(File::open(path).map_err(Error::from))?;
-        track!(Self::open(file))
+        let mut this = track!(Self::open_with(
+            PositionedReader::new(Arc::new(file)),
+            options
+        ))?;
+        this.dir = path.parent().map(Path::to_path_buf);
+        Ok(this)
+    }
+}
+impl Hdf5File<PositionedReader<Arc<FamilyMembers>>> {
+    /// Makes a new `Hdf5File` instance by opening the member files of a file split by the family driver.
+    ///
+    /// `pattern` is the name of the members with a `printf`-style integer conversion (e.g., `"file-%06d.h5"`)
+    /// which is replaced by the indices of the members starting from zero.
+    /// The members are opened until one doesn't exist, and each of them (except the last one)
+    /// must hold `member_size` bytes.
+    ///
+    /// If `pattern` has no conversion or `member_size` differs from the one recorded in the driver information block,
+    /// an `ErrorKind::InvalidInput` error will be returned.
+    /// If the first member doesn't exist, an `ErrorKind::NotFound` error will be returned,
+    /// and if the size of a member is wrong, an `ErrorKind::InvalidFile` error will be returned.
+    pub fn open_family(pattern: &str, member_size: u64) -> Result<Self> {
+        track!(Self::open_family_with(
+            pattern,
+            member_size,
+            ReaderOptions::default()
+        ))
+    }
+
+    /// Same as `open_family` except that this takes the options.
+    pub fn open_family_with(
+        pattern: &str,
+        member_size: u64,
+        options: ReaderOptions,
+    ) -> Result<Self> {
+        let mut members = Vec::new();
+        loop {
+            let path = track!(family_member_path(pattern, members.len()))?;
+            match File::open(&path) {
+                Ok(file) => members.push(file),
+                Err(e) if e.kind() == io::ErrorKind::NotFound && !members.is_empty() => break,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    track_panic!(ErrorKind::NotFound, "Family member: {:?}", path)
+                }
+                Err(e) => return Err(track!(Error::from(e); path)),
+            }
+        }
+        let last = members.len() - 1;
+        for (i, member) in members.iter().enumerate() {
+            let len = track!(member.metadata().map_err(Error::from))?.len();
+            track_assert!(
+                len == member_size || (i == last && len <= member_size),
+                ErrorKind::InvalidFile,
+                "The family member #{} holds {} bytes (member size: {})",
+                i,
+                len,
+                member_size
+            );
+        }
+
+        let family = track!(FamilyMembers::new(members, member_size))?;
+        let mut this = track!(Self::open_with(
+            PositionedReader::new(Arc::new(family)),
+            options
+        ))?;
+        if let Some(&DriverInfo::Family {
+            member_size: recorded,
+        }) = this.driver_info()
+        {
+            track_assert_eq!(recorded, member_size, ErrorKind::InvalidInput);
+        }
+        this.dir = Path::new(pattern).parent().map(Path::to_path_buf);
+        Ok(this)
+    }
+}
+impl Hdf5File<Cursor<Arc<[u8]>>> {
+    /// Makes a new `Hdf5File` instance from the contents of an HDF5 file loaded in memory.
+    ///
+    /// The clones of the resulting instance share the same buffer and have their own cursors,
+    /// so they can be used to read different datasets from multiple threads in parallel.
+    pub fn from_bytes<B: Into<Arc<[u8]>>>(bytes: B) -> Result<Self> {
+        track!(Self::open(Cursor::new(bytes.into())))
+    }
+
+    /// Makes a new `Hdf5File` instance from the contents of an HDF5 file loaded in memory with the given options.
+    pub fn from_bytes_with<B: Into<Arc<[u8]>>>(bytes: B, options: ReaderOptions) -> Result<Self> {
+        track!(Self::open_with(Cursor::new(bytes.into()), options))
     }
 }
 impl<T> Hdf5File<T>
@@ -24,56 +152,844 @@ where
     T: Read + Seek,
 {
     /// Makes a new `Hdf5File` instance by reading data from the given I/O stream.
-    pub fn open(mut io: T) -> Result<Self> {
-        let superblock = track!(Superblock::from_reader(&mut io))?;
-        Ok(Self { io, superblock })
+    pub fn open(io: T) -> Result<Self> {
+        track!(Self::open_with(io, ReaderOptions::default()))
+    }
+
+    /// Makes a new `Hdf5File` instance by reading data from the given I/O stream with the given options.
+    pub fn open_with(mut io: T, options: ReaderOptions) -> Result<Self> {
+        let superblock = io.with_offset("superblock", |io| track!(Superblock::from_reader(io)))?;
+        if options.is_rejecting_inconsistent_files() {
+            track_assert!(
+                superblock.is_consistent(),
+                ErrorKind::InvalidFile,
+                "The file may be open for writing: consistency_flags={:#x}",
+                superblock.file_consistency_flags
+            );
+        }
+        let mut io = FileContext::new(io, superblock.base_address);
+        let driver_info = track!(superblock.driver_info(&mut io))?;
+        if let Some(DriverInfo::Other { driver_id, .. }) = &driver_info {
+            track_panic!(ErrorKind::Unsupported, "File driver: {:?}", driver_id);
+        }
+        Ok(Self {
+            io,
+            superblock,
+            options: options.finalize(),
+            dir: None,
+            follow_external_links: true,
+            driver_info,
+        })
+    }
+
+    /// Returns the driver information block of the file if it has one.
+    ///
+    /// The family driver is the only one whose files can be read (see `Hdf5File::open_family`).
+    pub fn driver_info(&self) -> Option<&DriverInfo> {
+        self.driver_info.as_ref()
+    }
+
+    /// Makes a new `Hdf5File` instance from a reader that isn't associated with a filesystem path
+    /// (e.g., a `Cursor<Vec<u8>>` holding a downloaded file).
+    ///
+    /// Unlike `open`, external links aren't resolved against the current directory:
+    /// following one results in an `ErrorKind::Unsupported` error.
+    pub fn from_reader(reader: T) -> Result<Self> {
+        track!(Self::from_reader_with(reader, ReaderOptions::default()))
+    }
+
+    /// Same as `from_reader` except that this takes the options.
+    pub fn from_reader_with(reader: T, options: ReaderOptions) -> Result<Self> {
+        let mut this = track!(Self::open_with(reader, options))?;
+        this.follow_external_links = false;
+        Ok(this)
+    }
+
+    /// Returns the superblock of the file.
+    pub fn superblock(&self) -> &Superblock {
+        &self.superblock
+    }
+
+    /// Reads the object header of the root group.
+    ///
+    /// This allows for dropping down to the low-level structures without reopening the file.
+    pub fn root(&mut self) -> Result<ObjectHeader> {
+        track!(self
+            .superblock
+            .root_object_header(BufReader::new(&mut self.io), &self.options))
     }
 
     /// Returns an iterator that iterates over the paths of all objects stored in the file.
+    ///
+    /// Both old-style groups (symbol tables) and new-style groups (link messages or dense links) are walked.
+    /// The paths of soft links are included, but their targets aren't walked through them,
+    /// and external links are skipped because their targets are stored in other files.
     pub fn object_paths<'a>(&'a mut self) -> Result<impl 'a + Iterator<Item = Result<PathBuf>>> {
-        let mut io = BufReader::new(&mut self.io);
-        let root = track!(Node::new(
-            &mut io,
-            &self.superblock.root_group_symbol_table_entry,
-        ))?;
-        Ok(Objects::new(io, root))
+        let io = BufReader::new(&mut self.io);
+        let root_entry = self.superblock.root_group_symbol_table_entry.clone();
+        Ok(Objects::new(io, self.options.clone(), root_entry))
+    }
+
+    /// Returns `true` if an object associated with the given path exists, otherwise `false`.
+    ///
+    /// Unlike `get_object` or `dataset`, this doesn't parse the object header of the target object.
+    /// External links are followed (see `external_file`).
+    pub fn contains<P: AsRef<Path>>(&mut self, path: P) -> Result<bool> {
+        match track!(self.resolve(path))? {
+            None => Ok(false),
+            Some(Resolved::Entry(_)) => Ok(true),
+            Some(Resolved::External {
+                file_name,
+                object_path,
+            }) => {
+                let (mut file, path) = track!(self.follow_external(&file_name, object_path))?;
+                track!(file.contains(path))
+            }
+        }
     }
 
     /// Returns a data object associated with the given path.
+    ///
+    /// External links are followed (see `external_file`).
     pub fn get_object<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<DataObject>> {
+        let entry = match track!(self.resolve(path))? {
+            None => return Ok(None),
+            Some(Resolved::Entry(entry)) => entry,
+            Some(Resolved::External {
+                file_name,
+                object_path,
+            }) => {
+                let (mut file, path) = track!(self.follow_external(&file_name, object_path))?;
+                return track!(file.get_object(path));
+            }
+        };
+        track!(entry.get_data_object(BufReader::new(&mut self.io), &self.options)).map(Some)
+    }
+
+    /// Returns the dataset associated with the given path.
+    ///
+    /// If the path doesn't exist or refers to a non-dataset object (e.g., a group), `Ok(None)` will be returned.
+    ///
+    /// Datasets behind external links can't be returned by this method
+    /// because they are read from other files. Use `external_file` to access them.
+    pub fn dataset<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<Dataset<'_, T>>> {
+        let entry = if let Some(entry) = track!(self.entry(path))? {
+            entry
+        } else {
+            return Ok(None);
+        };
+        let header = track!(entry.object_header(BufReader::new(&mut self.io), &self.options))?;
+        if header.is_dataset() {
+            Ok(Some(Dataset::new(&mut self.io, header, &self.options)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Reads all the datasets in the root group.
+    ///
+    /// The datasets are returned with their names in name order, and the other members (e.g., groups) are ignored.
+    /// Datasets that can't be read because they use unsupported features (i.e., `ErrorKind::Unsupported` errors)
+    /// are skipped, and their names are returned with the errors in `TopLevelData::skipped`;
+    /// other errors make the whole call fail.
+    pub fn read_all_top_level(&mut self) -> Result<TopLevelData> {
+        let names = track!(self.member_names("/", LinkOrder::Name))?;
+        let names = track_assert_some!(names, ErrorKind::InvalidFile);
+
+        let mut objects = Vec::new();
+        let mut skipped = Vec::new();
+        for name in names {
+            let result = match self.dataset(format!("/{}", name)) {
+                Ok(None) => continue,
+                Ok(Some(mut dataset)) => dataset.read(),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(object) => objects.push((name, object)),
+                Err(e) if *e.kind() == ErrorKind::Unsupported => skipped.push((name, e)),
+                Err(e) => return Err(track!(e; name)),
+            }
+        }
+        Ok(TopLevelData { objects, skipped })
+    }
+
+    /// Returns the names of the members of the group associated with the given path.
+    ///
+    /// `LinkOrder::Creation` sorts the members by creation order if the group tracks it,
+    /// otherwise the members are sorted by name.
+    /// If the path doesn't exist or refers to a non-group object, `Ok(None)` will be returned.
+    pub fn member_names<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        order: LinkOrder,
+    ) -> Result<Option<Vec<String>>> {
+        let entry = if let Some(entry) = track!(self.entry(path))? {
+            entry
+        } else {
+            return Ok(None);
+        };
         let mut io = BufReader::new(&mut self.io);
-        let mut node = track!(Node::new(
+        if let Some(node) = track!(Node::try_new(&mut io, &entry))? {
+            let mut names = Vec::new();
+            track!(node.collect_names(&mut io, &mut names))?;
+            return Ok(Some(names));
+        }
+        if !entry.has_empty_scratch_pad() {
+            return Ok(None);
+        }
+
+        let header = track!(read_group_header(&mut io, &entry, &self.options))?;
+        let link_info = if let Some(link_info) = header.link_info() {
+            link_info
+        } else {
+            return Ok(None);
+        };
+        let mut links = track!(group_links(
             &mut io,
-            &self.superblock.root_group_symbol_table_entry,
+            &header,
+            link_info,
+            order,
+            &self.options
+        ))?;
+        let by_creation_order = order == LinkOrder::Creation
+            && link_info.is_creation_order_tracked()
+            && links.iter().all(|link| link.creation_order().is_some());
+        if by_creation_order {
+            links.sort_by_key(|link| link.creation_order());
+        } else {
+            links.sort_by(|a, b| a.name().cmp(b.name()));
+        }
+        Ok(Some(
+            links.iter().map(|link| link.name().to_owned()).collect(),
+        ))
+    }
+
+    /// Returns the dataset whose object header is located at the given address.
+    ///
+    /// This is the address-based counterpart of `dataset`.
+    /// If the object at the address isn't a dataset, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn dataset_at(&mut self, address: u64) -> Result<Dataset<'_, T>> {
+        let entry = SymbolTableEntry::from_object_header_address(address);
+        let header = track!(entry.object_header(BufReader::new(&mut self.io), &self.options))?;
+        track_assert!(
+            header.is_dataset(),
+            ErrorKind::InvalidInput,
+            "Not a dataset: address={:#x}",
+            address
+        );
+        Ok(Dataset::new(&mut self.io, header, &self.options))
+    }
+
+    /// Walks all objects in the file and sums up the sizes of their data.
+    ///
+    /// Objects reachable via several paths are counted once, and the root group is included in the group count.
+    /// The data size of chunked datasets is the sum of the stored (filtered) sizes of their chunks.
+    pub fn usage_report(&mut self) -> Result<UsageReport> {
+        track!(self.usage_report_with_progress(|_, _, _| {}))
+    }
+
+    /// Same as `usage_report` except that `progress` is called each time an object is found or visited.
+    ///
+    /// The paths of all objects are enumerated first (`UsagePhase::Enumerating`),
+    /// and then their object headers are visited (`UsagePhase::Measuring`).
+    /// The arguments of `progress` are the phase, the path of the object
+    /// and the number of objects found or visited so far in the phase (including that one).
+    pub fn usage_report_with_progress<F>(&mut self, mut progress: F) -> Result<UsageReport>
+    where
+        F: FnMut(UsagePhase, &Path, usize),
+    {
+        let mut paths = Vec::new();
+        for path in track!(self.object_paths())? {
+            let path = track!(path)?;
+            progress(UsagePhase::Enumerating, &path, paths.len() + 1);
+            paths.push(path);
+        }
+        let mut report = UsageReport {
+            dataset_count: 0,
+            group_count: 1,
+            data_bytes: 0,
+            file_bytes: self.superblock.end_of_file_address,
+        };
+        let mut visited = HashSet::new();
+        visited.insert(
+            self.superblock
+                .root_group_symbol_table_entry
+                .object_header_address(),
+        );
+        for path in paths {
+            let entry = track_assert_some!(track!(self.entry(&path))?, ErrorKind::Other; path);
+            if !visited.insert(entry.object_header_address()) {
+                continue;
+            }
+            let header =
+                track!(entry.object_header(BufReader::new(&mut self.io), &self.options); path)?;
+            progress(UsagePhase::Measuring, &path, visited.len() - 1);
+            if header.is_dataset() {
+                let mut dataset = Dataset::new(&mut self.io, header, &self.options);
+                report.dataset_count += 1;
+                let data_bytes = track!(dataset.byte_size(); path)?;
+                report.data_bytes = track_assert_some!(
+                    report.data_bytes.checked_add(data_bytes),
+                    ErrorKind::InvalidFile;
+                    path
+                );
+            } else if header.is_group() {
+                report.group_count += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Returns the local heap of the group associated with the given path.
+    ///
+    /// If the path doesn't refer to a group whose symbol table is cached in its entry, `Ok(None)` will be returned.
+    pub fn local_heap<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<LocalHeap>> {
+        let entry = if let Some(entry) = track!(self.entry(path))? {
+            entry
+        } else {
+            return Ok(None);
+        };
+        track!(entry.local_heap(BufReader::new(&mut self.io)))
+    }
+
+    /// Returns `true` if the group associated with the given path tracks the creation order of its links.
+    ///
+    /// Old-style groups (with symbol tables) never track it.
+    /// If the path doesn't exist or refers to a non-group object, `Ok(None)` will be returned.
+    pub fn tracks_creation_order<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<bool>> {
+        let entry = if let Some(entry) = track!(self.entry(path))? {
+            entry
+        } else {
+            return Ok(None);
+        };
+        let header = track!(read_group_header(
+            BufReader::new(&mut self.io),
+            &entry,
+            &self.options
         ))?;
+        if !header.is_group() {
+            return Ok(None);
+        }
+        Ok(Some(header.link_info().map_or(false, |link_info| {
+            link_info.is_creation_order_tracked()
+        })))
+    }
+
+    /// Returns the root node of the B-tree that indexes the members of the group associated with the given path.
+    ///
+    /// If the path doesn't refer to a group whose symbol table is cached in its entry, `Ok(None)` will be returned.
+    /// The other nodes can be read by `b_tree_node_at` with the addresses of `BTreeNode::child_addresses`.
+    pub fn group_b_tree<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<BTreeNode>> {
+        let entry = if let Some(entry) = track!(self.entry(path))? {
+            entry
+        } else {
+            return Ok(None);
+        };
+        track!(entry.b_tree_node(BufReader::new(&mut self.io)))
+    }
+
+    /// Reads the group B-tree node at the given address.
+    pub fn b_tree_node_at(&mut self, address: u64) -> Result<BTreeNode> {
+        let mut io = BufReader::new(&mut self.io);
+        track!(io.seek_to(address))?;
+        track!(io.with_offset("B-tree node", |r| track!(BTreeNode::from_reader(r))))
+    }
+
+    /// Opens the file that the given path points into through external links.
+    ///
+    /// Returns the opened file and the path of the target object in it.
+    /// If the path doesn't pass through an external link (or doesn't exist), `Ok(None)` will be returned.
+    ///
+    /// Relative file names are resolved against the directory of this file
+    /// if it was opened by path, otherwise against the current directory.
+    /// If the external file doesn't exist, an `ErrorKind::NotFound` error will be returned.
+    pub fn external_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<Option<(Hdf5File, PathBuf)>> {
+        if let Some(Resolved::External {
+            file_name,
+            object_path,
+        }) = track!(self.resolve(path))?
+        {
+            track!(self.follow_external(&file_name, object_path)).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn follow_external(
+        &self,
+        file_name: &str,
+        mut object_path: PathBuf,
+    ) -> Result<(Hdf5File, PathBuf)> {
+        let mut file = track!(self.open_external(file_name))?;
+        for _ in 0..MAX_EXTERNAL_LINKS {
+            match track!(file.resolve(&object_path))? {
+                Some(Resolved::External {
+                    file_name,
+                    object_path: next,
+                }) => {
+                    file = track!(file.open_external(&file_name))?;
+                    object_path = next;
+                }
+                _ => return Ok((file, object_path)),
+            }
+        }
+        track_panic!(
+            ErrorKind::InvalidFile,
+            "Too many external links while resolving {:?} in {:?}",
+            object_path,
+            file_name
+        );
+    }
+
+    fn open_external(&self, file_name: &str) -> Result<Hdf5File> {
+        track_assert!(
+            self.follow_external_links,
+            ErrorKind::Unsupported,
+            "External links can't be followed in a file made by `from_reader`: {:?}",
+            file_name
+        );
+        let path = match &self.dir {
+            Some(dir) => dir.join(file_name),
+            None => PathBuf::from(file_name),
+        };
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                track_panic!(ErrorKind::NotFound, "External file: {:?}", path)
+            }
+            Err(e) => return Err(track!(Error::from(e); path)),
+        };
+        let mut file = track!(Hdf5File::open_with(file, self.options.clone()); path)?;
+        file.dir = path.parent().map(Path::to_path_buf);
+        Ok(file)
+    }
+
+    fn entry<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<SymbolTableEntry>> {
+        match track!(self.resolve(path))? {
+            None => Ok(None),
+            Some(Resolved::Entry(entry)) => Ok(Some(entry)),
+            Some(Resolved::External {
+                file_name,
+                object_path,
+            }) => track_panic!(
+                ErrorKind::Unsupported,
+                "The path points into the external file {:?} ({:?}); use `external_file` instead",
+                file_name,
+                object_path
+            ),
+        }
+    }
+
+    fn resolve<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<Resolved>> {
+        let path = path.as_ref();
+        let mut io = BufReader::new(&mut self.io);
+        let root = &self.superblock.root_group_symbol_table_entry;
 
-        let mut components = path.as_ref().components().peekable();
+        let mut components = path.components();
         track_assert_eq!(
             components.next(),
             Some(Component::RootDir),
             ErrorKind::InvalidInput
         );
+        let mut pending = track!(component_names(components, ErrorKind::InvalidInput))?;
+        pending.reverse();
 
-        while let Some(component) = components.next() {
-            if let Component::Normal(name) = component {
-                let name = track_assert_some!(name.to_str(), ErrorKind::InvalidInput);
-                if components.peek().is_some() {
-                    if let Some(child) = track!(node.get_dir(&mut io, name))? {
-                        node = child;
-                    } else {
-                        return Ok(None);
-                    }
-                } else if let Some(data) = track!(node.get_data(&mut io, name))? {
-                    return Ok(Some(data));
-                } else {
-                    return Ok(None);
+        let mut entry = root.clone();
+        let mut dir = PathBuf::from("/");
+
+        // Pairs of a followed soft link and the components remaining after it.
+        // Encountering the same pair twice means that the resolution never terminates.
+        let mut followed = HashSet::new();
+        while let Some(name) = pending.pop() {
+            let value = match track!(lookup(&mut io, &entry, &name, &self.options))? {
+                Lookup::Missing => return Ok(None),
+                Lookup::Object(child) => {
+                    entry = child;
+                    dir.push(name);
+                    continue;
                 }
-            } else {
-                track_panic!(ErrorKind::InvalidInput);
+                Lookup::SoftLink(value) => value,
+                Lookup::ExternalLink {
+                    file_name,
+                    object_path,
+                } => {
+                    let mut object_path = PathBuf::from(object_path);
+                    object_path.extend(pending.iter().rev());
+                    return Ok(Some(Resolved::External {
+                        file_name,
+                        object_path,
+                    }));
+                }
+            };
+
+            let link = dir.join(&name);
+            track_assert!(
+                followed.len() < MAX_SOFT_LINKS,
+                ErrorKind::InvalidFile,
+                "Too many soft links while resolving {:?}: {:?} -> {:?}",
+                path,
+                link,
+                value
+            );
+            track_assert!(
+                followed.insert((link.clone(), pending.clone())),
+                ErrorKind::InvalidFile,
+                "Circular soft link while resolving {:?}: {:?} -> {:?}",
+                path,
+                link,
+                value
+            );
+
+            let mut components = Path::new(&value).components().peekable();
+            if components.peek() == Some(&Component::RootDir) {
+                components.next();
+                entry = root.clone();
+                dir = PathBuf::from("/");
+            }
+            let names = track!(component_names(components, ErrorKind::Unsupported); value)?;
+            pending.extend(names.into_iter().rev());
+        }
+        Ok(Some(Resolved::Entry(entry)))
+    }
+}
+
+/// Parses the metadata of the HDF5 file stored in `bytes`.
+///
+/// This reads the superblock, walks all reachable groups and parses the object headers,
+/// the fill values and the chunk indices of the datasets, but doesn't read their data.
+/// It is intended to be the entry point of fuzzing harnesses,
+/// so it reports malformed input as an error instead of panicking.
+pub fn parse_all(bytes: &[u8]) -> Result<()> {
+    let mut file = track!(Hdf5File::open(Cursor::new(bytes)))?;
+    let paths = track!(file.object_paths())?.collect::<Result<Vec<_>>>()?;
+    for path in paths {
+        let mut dataset = if let Some(dataset) = track!(file.dataset(&path); path)? {
+            dataset
+        } else {
+            continue;
+        };
+        track!(dataset.shape(); path)?;
+        track!(dataset.dtype_message(); path)?;
+        track!(dataset.storage_layout(); path)?;
+        track!(dataset.fill_value(); path)?;
+        track!(dataset.chunk_map(); path)?;
+    }
+    Ok(())
+}
+
+/// Result of resolving a path.
+#[derive(Debug)]
+enum Resolved {
+    Entry(SymbolTableEntry),
+    External {
+        file_name: String,
+        object_path: PathBuf,
+    },
+}
+
+/// Result of looking up a member of a group.
+#[derive(Debug)]
+enum Lookup {
+    Missing,
+    Object(SymbolTableEntry),
+    SoftLink(String),
+    ExternalLink {
+        file_name: String,
+        object_path: String,
+    },
+}
+
+/// Looks up the member named `name` of the group represented by `entry`.
+fn lookup<T: Read + Seek>(
+    mut io: T,
+    entry: &SymbolTableEntry,
+    name: &str,
+    options: &ReaderOptions,
+) -> Result<Lookup> {
+    if let Some(node) = track!(Node::try_new(&mut io, entry))? {
+        let child = if let Some(child) = track!(node.get_entry(&mut io, name))? {
+            child
+        } else {
+            return Ok(Lookup::Missing);
+        };
+        return match track!(child.soft_link_value(&mut io, &node.local_heap))? {
+            Some(value) => Ok(Lookup::SoftLink(value)),
+            None => Ok(Lookup::Object(child)),
+        };
+    }
+    if !entry.has_empty_scratch_pad() {
+        return Ok(Lookup::Missing);
+    }
+
+    // The members of new-style groups are stored as link messages in their object headers,
+    // or in a fractal heap if there are many of them.
+    let header = track!(read_group_header(&mut io, entry, options))?;
+    let link = match header.link_info() {
+        Some(link_info) if link_info.is_dense() => {
+            track!(find_dense_link(&mut io, link_info, name, options))?
+        }
+        _ => header.links().find(|link| link.name() == name).cloned(),
+    };
+    let link = if let Some(link) = link {
+        link
+    } else {
+        return Ok(Lookup::Missing);
+    };
+    match link.target().clone() {
+        LinkTarget::Hard { address } => Ok(Lookup::Object(
+            SymbolTableEntry::from_object_header_address(address),
+        )),
+        LinkTarget::Soft { path } => Ok(Lookup::SoftLink(path)),
+        LinkTarget::External {
+            file_name,
+            object_path,
+        } => Ok(Lookup::ExternalLink {
+            file_name,
+            object_path,
+        }),
+    }
+}
+
+/// Returns the path of the member at the given index of a family of files.
+///
+/// The first `printf`-style integer conversion (`%d`, optionally with a zero flag and a width) of `pattern`
+/// is replaced by the index, and `%%` by `%`.
+fn family_member_path(pattern: &str, index: usize) -> Result<PathBuf> {
+    let mut path = String::new();
+    let mut converted = false;
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            path.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            path.push('%');
+            continue;
+        }
+        let mut spec = String::new();
+        while let Some(&d) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            spec.push(d);
+            chars.next();
+        }
+        track_assert_eq!(chars.next(), Some('d'), ErrorKind::InvalidInput; pattern);
+        track_assert!(
+            !converted,
+            ErrorKind::InvalidInput,
+            "Too many conversions: {:?}",
+            pattern
+        );
+        let width = spec.parse::<usize>().unwrap_or(0);
+        if spec.starts_with('0') {
+            path.push_str(&format!("{:0width$}", index, width = width));
+        } else {
+            path.push_str(&format!("{:width$}", index, width = width));
+        }
+        converted = true;
+    }
+    track_assert!(
+        converted,
+        ErrorKind::InvalidInput,
+        "No conversion: {:?}",
+        pattern
+    );
+    Ok(PathBuf::from(path))
+}
+
+/// Reads the object header of a new-style group.
+///
+/// The headers of such groups contain messages that this crate doesn't interpret (e.g., Group Info),
+/// so they are always read leniently.
+fn read_group_header<T: Read + Seek>(
+    io: T,
+    entry: &SymbolTableEntry,
+    options: &ReaderOptions,
+) -> Result<ObjectHeader> {
+    let mut options = options.clone();
+    options.lenient(true);
+    track!(entry.object_header(io, &options))
+}
+
+/// Reads the links of a new-style group.
+///
+/// Densely stored links are read from the fractal heap in the order of the index B-tree
+/// (the creation order index is used for `LinkOrder::Creation` if there is one).
+fn group_links<T: Read + Seek>(
+    mut io: T,
+    header: &ObjectHeader,
+    link_info: &LinkInfoMessage,
+    order: LinkOrder,
+    options: &ReaderOptions,
+) -> Result<Vec<LinkMessage>> {
+    let heap_address = if let Some(address) = link_info.fractal_heap_address() {
+        address
+    } else {
+        // The estimate is only a hint, and each compact link takes a message of the header.
+        let estimated_count = header
+            .group_info()
+            .and_then(|m| m.estimated_entry_count())
+            .map_or(0, usize::from)
+            .min(header.header_messages().len());
+        let mut links = Vec::with_capacity(estimated_count);
+        links.extend(header.links().cloned());
+        return Ok(links);
+    };
+    track!(io.seek_to(heap_address))?;
+    let heap = track!(FractalHeap::from_reader(&mut io))?;
+
+    let b_tree_address = match link_info.creation_order_index_b_tree_address() {
+        Some(address) if order == LinkOrder::Creation => address,
+        _ => track_assert_some!(
+            link_info.name_index_b_tree_address(),
+            ErrorKind::InvalidFile
+        ),
+    };
+    track!(io.seek_to(b_tree_address))?;
+    let b_tree = track!(BTreeV2::from_reader(&mut io, options))?;
+
+    // Records of the name index start with a 4-byte hash of the name,
+    // and those of the creation order index start with an 8-byte creation order.
+    let heap_id_offset = match b_tree.record_type() {
+        5 => 4,
+        6 => 8,
+        t => track_panic!(
+            ErrorKind::InvalidFile,
+            "Not a link index B-tree: type={}",
+            t
+        ),
+    };
+
+    let records = track!(b_tree.records(&mut io))?;
+    let mut links = Vec::with_capacity(records.len());
+    for record in records {
+        track_assert!(
+            record.len() >= heap_id_offset + heap.heap_id_len(),
+            ErrorKind::InvalidFile
+        );
+        let heap_id = &record[heap_id_offset..][..heap.heap_id_len()];
+        let bytes = track!(heap.get_object(&mut io, heap_id))?;
+        links.push(track!(LinkMessage::from_reader(&bytes[..]))?);
+    }
+    Ok(links)
+}
+
+/// Looks up the densely stored link named `name` through the name index of a new-style group.
+fn find_dense_link<T: Read + Seek>(
+    mut io: T,
+    link_info: &LinkInfoMessage,
+    name: &str,
+    options: &ReaderOptions,
+) -> Result<Option<LinkMessage>> {
+    let heap_address = track_assert_some!(link_info.fractal_heap_address(), ErrorKind::InvalidFile);
+    track!(io.seek_to(heap_address))?;
+    let heap = track!(FractalHeap::from_reader(&mut io))?;
+
+    let b_tree_address = track_assert_some!(
+        link_info.name_index_b_tree_address(),
+        ErrorKind::InvalidFile
+    );
+    track!(io.seek_to(b_tree_address))?;
+    let b_tree = track!(BTreeV2::from_reader(&mut io, options))?;
+    track_assert_eq!(b_tree.record_type(), 5, ErrorKind::InvalidFile);
+    track_assert!(
+        b_tree.record_size() >= 4 + heap.heap_id_len(),
+        ErrorKind::InvalidFile
+    );
+
+    // The records are ordered by the hash of the names, which may collide.
+    let hash = lookup3_hash(name.as_bytes());
+    let records = track!(b_tree.find_records(&mut io, |record| {
+        u32::from_le_bytes([record[0], record[1], record[2], record[3]]).cmp(&hash)
+    }))?;
+    for record in records {
+        let heap_id = &record[4..][..heap.heap_id_len()];
+        let bytes = track!(heap.get_object(&mut io, heap_id))?;
+        let link = track!(LinkMessage::from_reader(&bytes[..]))?;
+        if link.name() == name {
+            return Ok(Some(link));
+        }
+    }
+    Ok(None)
+}
+
+/// Storage usage of a file (see `Hdf5File::usage_report`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UsageReport {
+    /// Number of datasets.
+    pub dataset_count: usize,
+
+    /// Number of groups (including the root group).
+    pub group_count: usize,
+
+    /// Total size of the data of the datasets in bytes.
+    pub data_bytes: u64,
+
+    /// Size of the file in bytes (according to the end-of-file address of the superblock).
+    pub file_bytes: u64,
+}
+impl UsageReport {
+    /// Returns the size of everything other than the data of the datasets (e.g., object headers, B-trees and heaps).
+    pub fn metadata_bytes(&self) -> u64 {
+        self.file_bytes.saturating_sub(self.data_bytes)
+    }
+}
+
+/// Datasets in the root group (see `Hdf5File::read_all_top_level`).
+#[derive(Debug)]
+pub struct TopLevelData {
+    /// Names and data of the datasets that have been read (in name order).
+    pub objects: Vec<(String, DataObject)>,
+
+    /// Names of the datasets that have been skipped because of unsupported features, and the errors.
+    pub skipped: Vec<(String, Error)>,
+}
+
+/// Phase of `Hdf5File::usage_report_with_progress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UsagePhase {
+    /// The paths of the objects are being enumerated.
+    Enumerating,
+
+    /// The object headers of the objects are being visited.
+    Measuring,
+}
+
+/// Order of the members of a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkOrder {
+    /// Alphabetical order of the names.
+    Name,
+
+    /// Order in which the members were created.
+    Creation,
+}
+
+/// Maximum number of external links followed while resolving a path.
+const MAX_EXTERNAL_LINKS: usize = 16;
+
+/// Maximum number of soft links followed while resolving a path (the same as the HDF5 library default).
+const MAX_SOFT_LINKS: usize = 16;
+
+fn component_names<'a, I>(components: I, error_kind: ErrorKind) -> Result<Vec<String>>
+where
+    I: Iterator<Item = Component<'a>>,
+{
+    let mut names = Vec::new();
+    for component in components {
+        match component {
+            Component::Normal(name) => {
+                let name = track_assert_some!(name.to_str(), error_kind);
+                names.push(name.to_owned());
             }
+            Component::CurDir => {}
+            _ => track_panic!(error_kind, "Unsupported path component: {:?}", component),
         }
-        track_panic!(ErrorKind::InvalidInput);
     }
+    Ok(names)
 }
 
 #[derive(Debug)]
@@ -83,22 +999,6 @@ pub struct Node {
     local_heap: LocalHeap,
 }
 impl Node {
-    pub fn new<T>(mut io: T, symbol_table: &SymbolTableEntry) -> Result<Self>
-    where
-        T: Read + Seek,
-    {
-        let b_tree_node = track!(symbol_table.b_tree_node(&mut io))?;
-        let b_tree_node = track_assert_some!(b_tree_node, ErrorKind::InvalidInput);
-
-        let local_heap = track!(symbol_table.local_heap(&mut io))?;
-        let local_heap = track_assert_some!(local_heap, ErrorKind::InvalidInput);
-        Ok(Self {
-            dir: PathBuf::from("/"),
-            b_tree_node,
-            local_heap,
-        })
-    }
-
     pub fn try_new<T>(mut io: T, symbol_table: &SymbolTableEntry) -> Result<Option<Self>>
     where
         T: Read + Seek,
@@ -146,7 +1046,7 @@ impl Node {
         }
     }
 
-    pub fn get_dir<T>(&self, mut io: T, name: &str) -> Result<Option<Self>>
+    pub fn get_entry<T>(&self, mut io: T, name: &str) -> Result<Option<SymbolTableEntry>>
     where
         T: Read + Seek,
     {
@@ -159,17 +1059,14 @@ impl Node {
                     b_tree_node: child,
                     local_heap: self.local_heap.clone(),
                 };
-                track!(child.get_dir(io, name))
+                track!(child.get_entry(io, name))
             }
             Some(BTreeNodeChild::GroupLeaf(child)) => {
-                for entry in &child.entries {
+                for entry in child.entries {
                     let child_name = track!(entry.link_name(&mut io, Some(&self.local_heap)))?;
                     let child_name = track_assert_some!(child_name, ErrorKind::InvalidFile);
                     if child_name == name {
-                        let mut child = track!(Node::new(&mut io, &entry))?;
-                        child.dir = self.dir.clone();
-                        child.dir.push(name);
-                        return Ok(Some(child));
+                        return Ok(Some(entry));
                     }
                 }
                 Ok(None)
@@ -177,33 +1074,29 @@ impl Node {
         }
     }
 
-    pub fn get_data<T>(&self, mut io: T, name: &str) -> Result<Option<DataObject>>
+    fn collect_names<T>(&self, io: &mut T, names: &mut Vec<String>) -> Result<()>
     where
         T: Read + Seek,
     {
-        let child = track!(self.get_btree_node_child(&mut io, name))?;
-        match child {
-            None => Ok(None),
-            Some(BTreeNodeChild::Intermediate(child)) => {
-                let child = Self {
-                    dir: self.dir.clone(),
-                    b_tree_node: child,
-                    local_heap: self.local_heap.clone(),
-                };
-                track!(child.get_data(io, name))
-            }
-            Some(BTreeNodeChild::GroupLeaf(child)) => {
-                for entry in &child.entries {
-                    let child_name = track!(entry.link_name(&mut io, Some(&self.local_heap)))?;
-                    let child_name = track_assert_some!(child_name, ErrorKind::InvalidFile);
-                    if child_name == name {
-                        let data = track!(entry.get_data_object(&mut io))?;
-                        return Ok(Some(data));
+        for child in self.children(&mut *io).collect::<Vec<_>>() {
+            match track!(child)? {
+                BTreeNodeChild::Intermediate(child) => {
+                    let child = Self {
+                        dir: self.dir.clone(),
+                        b_tree_node: child,
+                        local_heap: self.local_heap.clone(),
+                    };
+                    track!(child.collect_names(io, names))?;
+                }
+                BTreeNodeChild::GroupLeaf(child) => {
+                    for entry in child.entries {
+                        let name = track!(entry.link_name(&mut *io, Some(&self.local_heap)))?;
+                        names.push(track_assert_some!(name, ErrorKind::InvalidFile));
                     }
                 }
-                Ok(None)
             }
         }
+        Ok(())
     }
 
     pub fn children<'a, T>(&'a self, io: T) -> impl 'a + Iterator<Item = Result<BTreeNodeChild>>
@@ -217,64 +1110,116 @@ impl Node {
 #[derive(Debug)]
 struct Objects<T> {
     io: T,
+    options: ReaderOptions,
     nodes: Vec<Node>,
+
+    // Groups whose members haven't been enumerated yet, and the paths of them.
+    groups: Vec<(PathBuf, SymbolTableEntry)>,
     paths: Vec<PathBuf>,
+
+    // Object header addresses of the visited groups (used to avoid following cyclic hard links).
+    visited: HashSet<u64>,
 }
 impl<T> Objects<T>
 where
     T: Read + Seek,
 {
-    fn new(io: T, root: Node) -> Self {
+    fn new(io: T, options: ReaderOptions, root: SymbolTableEntry) -> Self {
+        let mut visited = HashSet::new();
+        visited.insert(root.object_header_address());
         Self {
             io,
-            nodes: vec![root],
+            options,
+            nodes: Vec::new(),
+            groups: vec![(PathBuf::from("/"), root)],
             paths: Vec::new(),
+            visited,
         }
     }
 
     fn next_object_path(&mut self) -> Result<Option<PathBuf>> {
-        if let Some(path) = self.paths.pop() {
-            return Ok(Some(path));
-        }
-
-        while let Some(node) = self.nodes.pop() {
-            for child in node.children(&mut self.io).collect::<Vec<_>>() {
-                let child = track!(child)?;
-                match child {
-                    BTreeNodeChild::Intermediate(child) => {
-                        let child = Node {
-                            dir: node.dir.clone(),
-                            b_tree_node: child,
-                            local_heap: node.local_heap.clone(),
-                        };
-                        self.nodes.push(child);
-                    }
-                    BTreeNodeChild::GroupLeaf(child) => {
-                        for entry in &child.entries {
-                            let name =
-                                track!(entry.link_name(&mut self.io, Some(&node.local_heap)))?;
-                            let name = track_assert_some!(name, ErrorKind::InvalidFile);
-
-                            let mut path = node.dir.clone();
-                            path.push(name);
-                            self.paths.push(path.clone());
-
-                            if let Some(mut grand_child) =
-                                track!(Node::try_new(&mut self.io, &entry))?
-                            {
-                                grand_child.dir = path;
-                                self.nodes.push(grand_child);
-                            }
-                        }
-                    }
-                }
+        loop {
+            if let Some(path) = self.paths.pop() {
+                return Ok(Some(path));
             }
-            if !self.paths.is_empty() {
-                break;
+            if let Some(node) = self.nodes.pop() {
+                track!(self.visit_node(node))?;
+            } else if let Some((dir, entry)) = self.groups.pop() {
+                track!(self.visit_group(dir, &entry); entry.object_header_address())?;
+            } else {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn visit_node(&mut self, node: Node) -> Result<()> {
+        for child in node.children(&mut self.io).collect::<Vec<_>>() {
+            let child = track!(child)?;
+            match child {
+                BTreeNodeChild::Intermediate(child) => {
+                    let child = Node {
+                        dir: node.dir.clone(),
+                        b_tree_node: child,
+                        local_heap: node.local_heap.clone(),
+                    };
+                    self.nodes.push(child);
+                }
+                BTreeNodeChild::GroupLeaf(child) => {
+                    for entry in child.entries {
+                        let name = track!(entry.link_name(&mut self.io, Some(&node.local_heap)))?;
+                        let name = track_assert_some!(name, ErrorKind::InvalidFile);
+
+                        let mut path = node.dir.clone();
+                        path.push(name);
+                        self.paths.push(path.clone());
+                        if self.visited.insert(entry.object_header_address()) {
+                            self.groups.push((path, entry));
+                        }
+                    }
+                }
             }
         }
+        Ok(())
+    }
+
+    fn visit_group(&mut self, dir: PathBuf, entry: &SymbolTableEntry) -> Result<()> {
+        if let Some(mut node) = track!(Node::try_new(&mut self.io, entry))? {
+            node.dir = dir;
+            self.nodes.push(node);
+            return Ok(());
+        }
+        if !entry.has_empty_scratch_pad() {
+            // A soft link.
+            return Ok(());
+        }
+
+        // Objects other than new-style groups (e.g., datasets) have no links.
+        let header = track!(read_group_header(&mut self.io, entry, &self.options))?;
+        let links = match header.link_info() {
+            Some(link_info) => track!(group_links(
+                &mut self.io,
+                &header,
+                link_info,
+                LinkOrder::Name,
+                &self.options
+            ))?,
+            None => header.links().cloned().collect(),
+        };
+        for link in links {
+            let address = match *link.target() {
+                LinkTarget::Hard { address } => Some(address),
+                LinkTarget::Soft { .. } => None,
+                LinkTarget::External { .. } => continue,
+            };
 
-        Ok(self.paths.pop())
+            let path = dir.join(link.name());
+            self.paths.push(path.clone());
+            if let Some(address) = address.filter(|&a| self.visited.insert(a)) {
+                let entry = SymbolTableEntry::from_object_header_address(address);
+                self.groups.push((path, entry));
+            }
+        }
+        Ok(())
     }
 }
 impl<T> Iterator for Objects<T>
@@ -287,3 +1232,1454 @@ where
         track!(self.next_object_path()).transpose()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lowlevel::level0::UNDEFINED_ADDRESS;
+    use crate::lowlevel::Selection;
+    use trackable::result::TopLevelResult;
+
+    const SUPERBLOCK_SIZE: usize = 96;
+    const B_TREE_NODE_SIZE: usize = 48;
+    const LOCAL_HEAP_SIZE: usize = 32;
+
+    fn push_u64(bytes: &mut Vec<u8>, n: u64) {
+        bytes.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn push_padded_string(bytes: &mut Vec<u8>, s: &str) {
+        bytes.extend_from_slice(s.as_bytes());
+        bytes.push(0);
+        while bytes.len() % 8 != 0 {
+            bytes.push(0);
+        }
+    }
+
+    /// Builds a superblock whose root group symbol table entry has the given properties.
+    fn superblock(object_header_address: u64, cache_type: u32, scratch_pad: &[u64]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&[137, 72, 68, 70, 13, 10, 26, 10]);
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 8, 8, 0]);
+        bytes.extend_from_slice(&4u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        push_u64(&mut bytes, 0);
+        push_u64(&mut bytes, UNDEFINED_ADDRESS);
+        push_u64(&mut bytes, UNDEFINED_ADDRESS);
+        push_u64(&mut bytes, UNDEFINED_ADDRESS);
+        push_u64(&mut bytes, 0);
+        push_u64(&mut bytes, object_header_address);
+        bytes.extend_from_slice(&cache_type.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        for &n in scratch_pad {
+            push_u64(&mut bytes, n);
+        }
+        bytes.resize(SUPERBLOCK_SIZE, 0);
+        bytes
+    }
+
+    /// Builds a file whose root group contains the given entries.
+    ///
+    /// An entry with a link value is an old-style soft link, otherwise it is an (empty) object.
+    /// The entries must be sorted by name.
+    fn root_group_file(entries: &[(&str, Option<&str>)]) -> Vec<u8> {
+        let mut heap_data = Vec::new();
+        push_padded_string(&mut heap_data, "");
+        let mut offsets = Vec::new();
+        for (name, value) in entries {
+            let name_offset = heap_data.len() as u64;
+            push_padded_string(&mut heap_data, name);
+            let value_offset = heap_data.len() as u32;
+            if let Some(value) = value {
+                push_padded_string(&mut heap_data, value);
+            }
+            offsets.push((name_offset, value_offset));
+        }
+
+        let b_tree_address = SUPERBLOCK_SIZE as u64;
+        let heap_address = b_tree_address + B_TREE_NODE_SIZE as u64;
+        let heap_data_address = heap_address + LOCAL_HEAP_SIZE as u64;
+        let symbol_table_node_address = heap_data_address + heap_data.len() as u64;
+
+        let mut bytes = superblock(0, 1, &[b_tree_address, heap_address]);
+
+        bytes.extend_from_slice(b"TREE\x00\x00\x01\x00");
+        push_u64(&mut bytes, UNDEFINED_ADDRESS);
+        push_u64(&mut bytes, UNDEFINED_ADDRESS);
+        push_u64(&mut bytes, 0);
+        push_u64(&mut bytes, symbol_table_node_address);
+        push_u64(&mut bytes, offsets.last().map_or(0, |o| o.0));
+
+        bytes.extend_from_slice(b"HEAP\x00\x00\x00\x00");
+        push_u64(&mut bytes, heap_data.len() as u64);
+        push_u64(&mut bytes, UNDEFINED_ADDRESS);
+        push_u64(&mut bytes, heap_data_address);
+        bytes.extend_from_slice(&heap_data);
+
+        bytes.extend_from_slice(b"SNOD\x01\x00");
+        bytes.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for ((_, value), (name_offset, value_offset)) in entries.iter().zip(offsets) {
+            push_u64(&mut bytes, name_offset);
+            if value.is_some() {
+                push_u64(&mut bytes, UNDEFINED_ADDRESS);
+                bytes.extend_from_slice(&2u32.to_le_bytes());
+                bytes.extend_from_slice(&0u32.to_le_bytes());
+                bytes.extend_from_slice(&value_offset.to_le_bytes());
+                bytes.extend_from_slice(&[0; 12]);
+            } else {
+                push_u64(&mut bytes, 0);
+                bytes.extend_from_slice(&[0; 24]);
+            }
+        }
+        bytes
+    }
+
+    fn encode_link(name: &str, target: &LinkTarget, creation_order: Option<u64>) -> Vec<u8> {
+        let flags = if creation_order.is_some() {
+            0b1100
+        } else {
+            0b1000
+        };
+        let mut data = vec![1, flags];
+        match target {
+            LinkTarget::Hard { .. } => data.push(0),
+            LinkTarget::Soft { .. } => data.push(1),
+            LinkTarget::External { .. } => data.push(64),
+        }
+        if let Some(order) = creation_order {
+            push_u64(&mut data, order);
+        }
+        data.push(name.len() as u8);
+        data.extend_from_slice(name.as_bytes());
+        match target {
+            LinkTarget::Hard { address } => push_u64(&mut data, *address),
+            LinkTarget::Soft { path } => {
+                data.extend_from_slice(&(path.len() as u16).to_le_bytes());
+                data.extend_from_slice(path.as_bytes());
+            }
+            LinkTarget::External {
+                file_name,
+                object_path,
+            } => {
+                let len = file_name.len() + object_path.len() + 3;
+                data.extend_from_slice(&(len as u16).to_le_bytes());
+                data.push(0);
+                data.extend_from_slice(file_name.as_bytes());
+                data.push(0);
+                data.extend_from_slice(object_path.as_bytes());
+                data.push(0);
+            }
+        }
+        data
+    }
+
+    /// Builds a version 1 object header containing the given messages (type, flags and data).
+    fn object_header(messages: Vec<(u16, u8, Vec<u8>)>) -> Vec<u8> {
+        let count = messages.len();
+        let mut body = Vec::new();
+        for (kind, flags, mut data) in messages {
+            while data.len() % 8 != 0 {
+                data.push(0);
+            }
+            body.extend_from_slice(&kind.to_le_bytes());
+            body.extend_from_slice(&(data.len() as u16).to_le_bytes());
+            body.extend_from_slice(&[flags, 0, 0, 0]);
+            body.extend_from_slice(&data);
+        }
+
+        let mut bytes = vec![1, 0];
+        bytes.extend_from_slice(&(count as u16).to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    /// Returns the datatype message of little-endian `i32` values.
+    fn i32_datatype() -> Vec<u8> {
+        let mut datatype = vec![0x10, 0b0000_1000, 0, 0];
+        datatype.extend_from_slice(&4u32.to_le_bytes());
+        datatype.extend_from_slice(&[0, 0, 32, 0]);
+        datatype
+    }
+
+    /// Builds the object header of a contiguous dataset of the given datatype message (type, flags and data).
+    fn dataset_header(dimensions: &[u64], datatype: (u16, u8, Vec<u8>), address: u64) -> Vec<u8> {
+        let mut dataspace = vec![1, dimensions.len() as u8, 0, 0, 0, 0, 0, 0];
+        for &d in dimensions {
+            push_u64(&mut dataspace, d);
+        }
+        let mut layout = vec![3, 1];
+        push_u64(&mut layout, address);
+        push_u64(&mut layout, dimensions.iter().product::<u64>() * 4);
+        object_header(vec![(0x01, 0, dataspace), datatype, (0x08, 0, layout)])
+    }
+
+    /// Builds the object header of a contiguous dataset of little-endian `i32` values.
+    fn i32_dataset_header(dimensions: &[u64], address: u64) -> Vec<u8> {
+        dataset_header(dimensions, (0x03, 0, i32_datatype()), address)
+    }
+
+    /// Builds a file whose root group is a new-style group containing the given links.
+    fn link_group_file(links: &[(&str, LinkTarget)]) -> Vec<u8> {
+        let mut bytes = superblock(SUPERBLOCK_SIZE as u64, 0, &[]);
+        let messages = links
+            .iter()
+            .map(|(name, target)| (0x06, 0, encode_link(name, target, None)))
+            .collect();
+        bytes.extend_from_slice(&object_header(messages));
+        bytes
+    }
+
+    /// Builds a file whose root group is a new-style group containing a new-style group `g`
+    /// (which contains the dataset `d` of three `i32` values), a soft link `s` to `/g/d`
+    /// and an external link `e`.
+    fn nested_link_group_file() -> Vec<u8> {
+        let group = |links: &[(&str, LinkTarget)]| {
+            let mut link_info = vec![0, 0];
+            push_u64(&mut link_info, UNDEFINED_ADDRESS);
+            push_u64(&mut link_info, UNDEFINED_ADDRESS);
+            let mut messages = vec![(0x02, 0, link_info)];
+            for (name, target) in links {
+                messages.push((0x06, 0, encode_link(name, target, None)));
+            }
+            object_header(messages)
+        };
+        let root = |group_address: u64| {
+            group(&[
+                (
+                    "e",
+                    LinkTarget::External {
+                        file_name: "other.h5".to_owned(),
+                        object_path: "/x".to_owned(),
+                    },
+                ),
+                (
+                    "g",
+                    LinkTarget::Hard {
+                        address: group_address,
+                    },
+                ),
+                (
+                    "s",
+                    LinkTarget::Soft {
+                        path: "/g/d".to_owned(),
+                    },
+                ),
+            ])
+        };
+        let group_address = (SUPERBLOCK_SIZE + root(0).len()) as u64;
+        let dataset_address =
+            group_address + group(&[("d", LinkTarget::Hard { address: 0 })]).len() as u64;
+        let data_address = dataset_address + i32_dataset_header(&[3], 0).len() as u64;
+
+        let mut bytes = superblock(SUPERBLOCK_SIZE as u64, 0, &[]);
+        bytes.extend_from_slice(&root(group_address));
+        bytes.extend_from_slice(&group(&[(
+            "d",
+            LinkTarget::Hard {
+                address: dataset_address,
+            },
+        )]));
+        bytes.extend_from_slice(&i32_dataset_header(&[3], data_address));
+        for v in &[1i32, 2, 3] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn object_paths_walks_new_style_groups() -> TopLevelResult {
+        let mut file = track!(Hdf5File::from_bytes(nested_link_group_file()))?;
+        let mut paths = track!(file.object_paths())?.collect::<Result<Vec<_>>>()?;
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/g"),
+                PathBuf::from("/g/d"),
+                PathBuf::from("/s")
+            ]
+        );
+        assert!(track!(file.dataset("/s"))?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn read_all_top_level_works() -> TopLevelResult {
+        let root_group = |a: u64, b: u64| {
+            let mut link_info = vec![0, 0];
+            push_u64(&mut link_info, UNDEFINED_ADDRESS);
+            push_u64(&mut link_info, UNDEFINED_ADDRESS);
+            let mut messages = vec![(0x02, 0, link_info)];
+            for (name, address) in &[("a", a), ("b", b)] {
+                let target = LinkTarget::Hard { address: *address };
+                messages.push((0x06, 0, encode_link(name, &target, None)));
+            }
+            let mut bytes = superblock(SUPERBLOCK_SIZE as u64, 0, &[]);
+            bytes.extend_from_slice(&object_header(messages));
+            bytes
+        };
+        let a_address = root_group(0, 0).len() as u64;
+        let a_header_len = i32_dataset_header(&[2], 0).len() as u64;
+        let b_address = a_address + a_header_len + 8;
+        let mut bytes = root_group(a_address, b_address);
+        bytes.extend_from_slice(&i32_dataset_header(&[2], a_address + a_header_len));
+        bytes.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0]);
+
+        // Object references aren't supported.
+        let reference = vec![0x17, 0, 0, 0, 8, 0, 0, 0];
+        bytes.extend_from_slice(&dataset_header(&[0], (0x03, 0, reference), 0));
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let TopLevelData { objects, skipped } = track!(file.read_all_top_level())?;
+        assert_eq!(objects.len(), 1);
+        let (name, object) = track_assert_some!(objects.into_iter().next(), ErrorKind::Other);
+        assert_eq!(name, "a");
+        assert_eq!(track!(object.into_vec::<i32>())?.0, [1, 2]);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0, "b");
+        assert_eq!(*skipped[0].1.kind(), ErrorKind::Unsupported);
+        Ok(())
+    }
+
+    #[test]
+    fn tracks_creation_order_works() -> TopLevelResult {
+        let file = |flags: u8| {
+            let mut link_info = vec![0, flags];
+            if flags & 1 != 0 {
+                push_u64(&mut link_info, 0); // maximum creation index
+            }
+            push_u64(&mut link_info, UNDEFINED_ADDRESS);
+            push_u64(&mut link_info, UNDEFINED_ADDRESS);
+            let mut bytes = superblock(SUPERBLOCK_SIZE as u64, 0, &[]);
+            bytes.extend_from_slice(&object_header(vec![(0x02, 0, link_info)]));
+            bytes
+        };
+        let mut file0 = track!(Hdf5File::from_bytes(file(0)))?;
+        assert_eq!(track!(file0.tracks_creation_order("/"))?, Some(false));
+        assert_eq!(track!(file0.tracks_creation_order("/a"))?, None);
+
+        let mut file1 = track!(Hdf5File::from_bytes(file(1)))?;
+        assert_eq!(track!(file1.tracks_creation_order("/"))?, Some(true));
+        Ok(())
+    }
+
+    #[test]
+    fn root_object_header_works() -> TopLevelResult {
+        let target = LinkTarget::Soft {
+            path: "/b".to_owned(),
+        };
+        let bytes = link_group_file(&[("a", target)]);
+        let superblock = track!(Superblock::from_reader(&bytes[..]))?;
+        let header =
+            track!(superblock
+                .root_object_header(std::io::Cursor::new(&bytes[..]), &ReaderOptions::new()))?;
+        assert!(!header.is_dataset());
+        assert_eq!(header.links().count(), 1);
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        assert_eq!(
+            file.superblock().group_leaf_node_k,
+            superblock.group_leaf_node_k
+        );
+        let root = track!(file.root())?;
+        assert_eq!(root.links().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn read_into_slice_works() -> TopLevelResult {
+        // Spans several blocks of `Dataset::read_into_slice`.
+        let count = 10_000;
+        let mut bytes = root_group_file(&[]);
+        let header_address = bytes.len() as u64;
+        let data_address = header_address + i32_dataset_header(&[count], 0).len() as u64;
+        bytes.extend_from_slice(&i32_dataset_header(&[count], data_address));
+        for v in 0..count as i32 {
+            bytes.extend_from_slice(&(v - 5000).to_le_bytes());
+        }
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        let mut buf = vec![0i32; count as usize];
+        track!(dataset.read_into_slice(&mut buf))?;
+        assert!(buf.iter().enumerate().all(|(i, &v)| v == i as i32 - 5000));
+        let mut buf = vec![0.0f64; count as usize];
+        track!(dataset.read_into_slice(&mut buf))?;
+        assert_eq!((buf[0], buf[9999]), (-5000.0, 4999.0));
+        Ok(())
+    }
+
+    #[test]
+    fn dataset_at_works() -> TopLevelResult {
+        let mut bytes = root_group_file(&[]);
+        let header_address = bytes.len() as u64;
+        let header = i32_dataset_header(&[3], 0);
+        let data_address = header_address + header.len() as u64;
+        bytes.extend_from_slice(&i32_dataset_header(&[3], data_address));
+        for v in &[1i32, -2, 3] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        assert_eq!(track!(dataset.shape())?, [3]);
+        assert_eq!(track!(dataset.max_shape())?, [Some(3)]);
+        assert_eq!(
+            track!(dataset.storage_layout())?,
+            crate::StorageLayout::Contiguous
+        );
+        let (items, _) = track!(track!(dataset.read())?.into_vec::<i32>())?;
+        assert_eq!(items, [1, -2, 3]);
+
+        let mut buf = [0i64; 3];
+        track!(dataset.read_into_slice(&mut buf))?;
+        assert_eq!(buf, [1, -2, 3]);
+        let error = dataset.read_into_slice(&mut [0i64; 2]).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+        let error = dataset.read_into_slice(&mut [0u8; 3]).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+
+        assert_eq!(
+            track!(dataset.read_element(1))?,
+            crate::ScalarValue::I32(-2)
+        );
+        let error = dataset.read_element(3).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+
+        assert!(file.dataset_at(0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn virtual_mappings_work() -> TopLevelResult {
+        let mut object = vec![0];
+        push_u64(&mut object, 2);
+        for (file_name, dataset_name) in &[("a.h5", "/x"), (".", "/y")] {
+            object.extend_from_slice(file_name.as_bytes());
+            object.push(0);
+            object.extend_from_slice(dataset_name.as_bytes());
+            object.push(0);
+            // Source: all (version 1).
+            object.extend_from_slice(&[3, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            // Virtual: regular hyperslab (version 3) with 8-byte values.
+            object.extend_from_slice(&[2, 0, 0, 0, 3, 0, 0, 0, 1, 8, 1, 0, 0, 0]);
+            for &n in &[0u64, 1, 1, 4] {
+                push_u64(&mut object, n);
+            }
+        }
+        let checksum = lookup3_hash(&object);
+        object.extend_from_slice(&checksum.to_le_bytes());
+
+        let mut bytes = root_group_file(&[]);
+        let collection_address = bytes.len() as u64;
+        let checksum_position = bytes.len() + 32 + object.len() - 4;
+        let padded_size = (object.len() + 7) / 8 * 8;
+        bytes.extend_from_slice(b"GCOL\x01\x00\x00\x00");
+        push_u64(&mut bytes, 16 + 16 + padded_size as u64 + 16);
+        bytes.extend_from_slice(&[1, 0, 1, 0, 0, 0, 0, 0]);
+        push_u64(&mut bytes, object.len() as u64);
+        bytes.extend_from_slice(&object);
+        bytes.resize(bytes.len() + padded_size - object.len(), 0);
+        bytes.extend_from_slice(&[0; 16]);
+
+        let header_address = bytes.len() as u64;
+        let mut dataspace = vec![1, 1, 0, 0, 0, 0, 0, 0];
+        push_u64(&mut dataspace, 8);
+        let mut layout = vec![4, 3];
+        push_u64(&mut layout, collection_address);
+        layout.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&object_header(vec![
+            (0x01, 0, dataspace),
+            (0x03, 0, i32_datatype()),
+            (0x08, 0, layout),
+        ]));
+
+        let mut file = track!(Hdf5File::from_bytes(bytes.clone()))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        assert_eq!(
+            track!(dataset.storage_layout())?,
+            crate::StorageLayout::Virtual
+        );
+        let mappings = track!(dataset.virtual_mappings())?;
+        assert_eq!(mappings.len(), 2);
+        assert_eq!(mappings[0].source_file_name(), "a.h5");
+        assert_eq!(mappings[0].source_dataset_name(), "/x");
+        assert_eq!(mappings[0].source_selection().len(), 16);
+        assert_eq!(mappings[1].source_file_name(), ".");
+        assert_eq!(mappings[1].source_dataset_name(), "/y");
+        assert_eq!(mappings[1].virtual_selection().len(), 46);
+
+        let error = dataset.read().unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::Unsupported);
+
+        // Corrupted checksum.
+        bytes[checksum_position] ^= 0xFF;
+        let mut file = track!(Hdf5File::from_bytes(bytes.clone()))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        let error = dataset.virtual_mappings().unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+
+        let mut options = ReaderOptions::default();
+        options.verify_checksums(false);
+        let mut file = track!(Hdf5File::from_bytes_with(bytes, options))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        assert_eq!(track!(dataset.virtual_mappings())?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn region_references_work() -> TopLevelResult {
+        let u32s = |values: &[u32]| {
+            values
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect::<Vec<_>>()
+        };
+        // Two points of a two-dimensional dataset (version 1).
+        let mut points = vec![];
+        push_u64(&mut points, 0x1234);
+        points.extend(u32s(&[1, 1, 0, 24, 2, 2, 0, 1, 2, 3]));
+        // A block from (1, 2) to (3, 4) of a two-dimensional dataset (version 1).
+        let mut blocks = vec![];
+        push_u64(&mut blocks, 0x5678);
+        blocks.extend(u32s(&[2, 1, 0, 24, 2, 1, 1, 2, 3, 4]));
+
+        let mut bytes = root_group_file(&[]);
+        let collection_address = bytes.len() as u64;
+        let mut collection = Vec::new();
+        for (i, object) in [&points, &blocks].iter().enumerate() {
+            collection.extend_from_slice(&[i as u8 + 1, 0, 1, 0, 0, 0, 0, 0]);
+            push_u64(&mut collection, object.len() as u64);
+            collection.extend_from_slice(object);
+            collection.resize((collection.len() + 7) / 8 * 8, 0);
+        }
+        bytes.extend_from_slice(b"GCOL\x01\x00\x00\x00");
+        push_u64(&mut bytes, 16 + collection.len() as u64 + 16);
+        bytes.extend_from_slice(&collection);
+        bytes.extend_from_slice(&[0; 16]);
+
+        let mut data = Vec::new();
+        for &index in &[2u32, 1] {
+            push_u64(&mut data, collection_address);
+            data.extend_from_slice(&index.to_le_bytes());
+        }
+        data.extend_from_slice(&[0; 12]); // null reference
+
+        let header_address = bytes.len() as u64;
+        let mut dataspace = vec![1, 1, 0, 0, 0, 0, 0, 0];
+        push_u64(&mut dataspace, 3);
+        let header_len = object_header(vec![(0x01, 0, dataspace.clone())]).len() as u64 + 16 + 32;
+        let mut layout = vec![3, 1];
+        push_u64(&mut layout, header_address + header_len);
+        push_u64(&mut layout, data.len() as u64);
+        let header = object_header(vec![
+            (0x01, 0, dataspace),
+            (0x03, 0, vec![0x17, 1, 0, 0, 12, 0, 0, 0]),
+            (0x08, 0, layout),
+        ]);
+        assert_eq!(header.len() as u64, header_len);
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&data);
+
+        let mut file = track!(Hdf5File::from_bytes(bytes.clone()))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        let references = track!(dataset.read_region_references())?;
+        assert_eq!(references.len(), 3);
+        let blocks = track_assert_some!(references[0].as_ref(), ErrorKind::Other);
+        assert_eq!(blocks.dataset_address(), 0x5678);
+        assert_eq!(
+            *blocks.selection(),
+            Selection::Blocks(vec![(vec![1, 2], vec![3, 4])])
+        );
+        let points = track_assert_some!(references[1].as_ref(), ErrorKind::Other);
+        assert_eq!(points.dataset_address(), 0x1234);
+        assert_eq!(
+            *points.selection(),
+            Selection::Points(vec![vec![0, 1], vec![2, 3]])
+        );
+        assert!(references[2].is_none());
+
+        // Region references of other sizes are rejected.
+        let datatype = [0x17, 1, 0, 0, 12, 0, 0, 0];
+        let position = track_assert_some!(
+            bytes.windows(datatype.len()).position(|w| w == datatype),
+            ErrorKind::Other
+        );
+        bytes[position + 4] = 8;
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        let error = dataset.read_region_references().unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        Ok(())
+    }
+
+    #[test]
+    fn inconsistent_files_can_be_rejected() -> TopLevelResult {
+        let mut bytes = root_group_file(&[]);
+        let file = track!(Hdf5File::from_bytes(bytes.clone()))?;
+        assert!(file.superblock().is_consistent());
+
+        // File consistency flags: write access.
+        bytes[20..24].copy_from_slice(&1u32.to_le_bytes());
+        let file = track!(Hdf5File::from_bytes(bytes.clone()))?;
+        assert!(!file.superblock().is_consistent());
+        assert_eq!(file.superblock().file_consistency_flags, 1);
+
+        let mut options = ReaderOptions::new();
+        options.reject_inconsistent_files(true);
+        let error = Hdf5File::from_bytes_with(bytes, options).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        Ok(())
+    }
+
+    #[test]
+    fn usage_report_works() -> TopLevelResult {
+        let mut writer = crate::writer::Hdf5Writer::new(Vec::new());
+        track!(writer.add_dataset("a", &[3], &[1.0f64, 2.0, 3.0]))?;
+        track!(writer.add_dataset("b", &[2], &[1i32, 2]))?;
+        let bytes = track!(writer.finish())?;
+        let file_size = bytes.len() as u64;
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let report = track!(file.usage_report())?;
+        assert_eq!(report.dataset_count, 2);
+        assert_eq!(report.group_count, 1);
+        assert_eq!(report.data_bytes, 32);
+        assert_eq!(report.file_bytes, file_size);
+        assert_eq!(report.metadata_bytes(), file_size - 32);
+
+        let mut found = Vec::new();
+        let mut visited = Vec::new();
+        track!(file.usage_report_with_progress(|phase, path, count| {
+            if phase == UsagePhase::Enumerating {
+                assert!(visited.is_empty());
+                found.push((path.to_path_buf(), count));
+            } else {
+                visited.push((path.to_path_buf(), count));
+            }
+        }))?;
+        assert_eq!(found.iter().map(|v| v.1).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(visited.iter().map(|v| v.1).collect::<Vec<_>>(), [1, 2]);
+        visited.sort();
+        assert_eq!(visited[0].0, Path::new("/a"));
+        assert_eq!(visited[1].0, Path::new("/b"));
+        Ok(())
+    }
+
+    #[test]
+    fn usage_report_walks_new_style_groups() -> TopLevelResult {
+        let mut file = track!(Hdf5File::from_bytes(nested_link_group_file()))?;
+        let report = track!(file.usage_report())?;
+        assert_eq!(report.dataset_count, 1);
+        assert_eq!(report.group_count, 2);
+        assert_eq!(report.data_bytes, 12);
+        Ok(())
+    }
+
+    #[test]
+    fn overflowing_dimensions_are_rejected() -> TopLevelResult {
+        let header_address = link_group_file(&[("d", LinkTarget::Hard { address: 0 })]).len();
+        let mut bytes = link_group_file(&[(
+            "d",
+            LinkTarget::Hard {
+                address: header_address as u64,
+            },
+        )]);
+        let mut dataspace = vec![1, 2, 0, 0, 0, 0, 0, 0];
+        push_u64(&mut dataspace, 1 << 40);
+        push_u64(&mut dataspace, 1 << 40);
+        let mut layout = vec![3, 1];
+        push_u64(&mut layout, UNDEFINED_ADDRESS);
+        push_u64(&mut layout, 0);
+        bytes.extend_from_slice(&object_header(vec![
+            (0x01, 0, dataspace),
+            (0x03, 0, i32_datatype()),
+            (0x08, 0, layout),
+        ]));
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset("/d"))?.expect("no dataset");
+        let error = dataset.element_count().unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        let error = dataset.read_element(0).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        let error = dataset.read().unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        Ok(())
+    }
+
+    #[test]
+    fn base_address_works() -> TopLevelResult {
+        // Addresses are relative to the base address, so the structures following
+        // the superblock are moved by that amount.
+        let mut bytes = root_group_file(&[("a", None), ("b", None)]);
+        bytes[24..32].copy_from_slice(&16u64.to_le_bytes());
+        bytes.splice(SUPERBLOCK_SIZE..SUPERBLOCK_SIZE, vec![0; 16]);
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        assert_eq!(file.superblock().base_address, 16);
+        let names = track!(file.member_names("/", LinkOrder::Name))?;
+        assert_eq!(names, Some(vec!["a".to_owned(), "b".to_owned()]));
+        Ok(())
+    }
+
+    #[test]
+    fn group_b_tree_works() -> TopLevelResult {
+        let bytes = root_group_file(&[("a", None), ("b", None)]);
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let local_heap = track_assert_some!(track!(file.local_heap("/"))?, ErrorKind::Other);
+        let symbol_table_node_address =
+            local_heap.data_segment_address() + local_heap.data_segment_size();
+
+        let node = track_assert_some!(track!(file.group_b_tree("/"))?, ErrorKind::Other);
+        assert_eq!(node.node_level(), 0);
+        assert_eq!(node.key_offsets(), [0, 16]);
+        assert_eq!(node.child_addresses(), [symbol_table_node_address]);
+        assert_eq!(node.entries(), [(16, symbol_table_node_address)]);
+        assert_eq!(node.sibling_addresses(), (None, None));
+
+        let same = track!(file.b_tree_node_at(SUPERBLOCK_SIZE as u64))?;
+        assert_eq!(same.entries(), node.entries());
+        assert!(file.b_tree_node_at(0).is_err());
+        assert!(track!(file.group_b_tree("/c"))?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn read_char_array_as_strings_works() -> TopLevelResult {
+        let mut writer = crate::writer::Hdf5Writer::new(Vec::new());
+        track!(writer.add_dataset("a", &[2, 4], &b"ab\0\0cd  "[..]))?;
+        track!(writer.add_dataset("b", &[2], &[1u32, 2]))?;
+        let bytes = track!(writer.finish())?;
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track_assert_some!(track!(file.dataset("/a"))?, ErrorKind::Other);
+        assert_eq!(
+            track!(dataset.read_char_array_as_strings(true))?,
+            ["ab", "cd"]
+        );
+        assert_eq!(
+            track!(dataset.read_char_array_as_strings(false))?,
+            ["ab\0\0", "cd  "]
+        );
+
+        let mut dataset = track_assert_some!(track!(file.dataset("/b"))?, ErrorKind::Other);
+        let error = dataset.read_char_array_as_strings(true).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    #[test]
+    fn read_as_f64_lossy_works() -> TopLevelResult {
+        let mut writer = crate::writer::Hdf5Writer::new(Vec::new());
+        track!(writer.add_dataset("a", &[2], &[-3i32, 4]))?;
+        track!(writer.add_dataset("b", &[1], &[u64::MAX]))?;
+        let bytes = track!(writer.finish())?;
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track_assert_some!(track!(file.dataset("/a"))?, ErrorKind::Other);
+        let array = track!(dataset.read_as_f64_lossy())?;
+        assert_eq!(array.as_slice(), Some(&[-3.0, 4.0][..]));
+
+        let mut dataset = track_assert_some!(track!(file.dataset("/b"))?, ErrorKind::Other);
+        assert!(track!(dataset.read())?.into_array::<f64>().is_err());
+        let array = track!(dataset.read_as_f64_lossy())?;
+        assert_eq!(array.as_slice(), Some(&[u64::MAX as f64][..]));
+        Ok(())
+    }
+
+    /// Builds a version 1 attribute message.
+    fn attribute_message(name: &str, datatype: &[u8], dimensions: &[u64], data: &[u8]) -> Vec<u8> {
+        let mut dataspace = vec![1, dimensions.len() as u8, 0, 0, 0, 0, 0, 0];
+        for &d in dimensions {
+            push_u64(&mut dataspace, d);
+        }
+        let mut bytes = vec![1, 0];
+        bytes.extend_from_slice(&(name.len() as u16 + 1).to_le_bytes());
+        bytes.extend_from_slice(&(datatype.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&(dataspace.len() as u16).to_le_bytes());
+        push_padded_string(&mut bytes, name);
+        bytes.extend_from_slice(datatype);
+        bytes.extend_from_slice(&dataspace);
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn dimension_scales_work() -> TopLevelResult {
+        let mut bytes = root_group_file(&[]);
+        let scale_address = bytes.len() as u64;
+
+        let mut class_datatype = vec![0x13, 0, 0, 0];
+        class_datatype.extend_from_slice(&16u32.to_le_bytes());
+        let class = attribute_message("CLASS", &class_datatype, &[], b"DIMENSION_SCALE\0");
+        let scale_header = |data_address| {
+            let mut dataspace = vec![1, 1, 0, 0, 0, 0, 0, 0];
+            push_u64(&mut dataspace, 2);
+            let mut layout = vec![3, 1];
+            push_u64(&mut layout, data_address);
+            push_u64(&mut layout, 8);
+            object_header(vec![
+                (0x01, 0, dataspace),
+                (0x03, 0, i32_datatype()),
+                (0x08, 0, layout),
+                (0x0C, 0, class.clone()),
+            ])
+        };
+        let scale_data_address = scale_address + scale_header(0).len() as u64;
+        bytes.extend_from_slice(&scale_header(scale_data_address));
+        for v in &[10i32, 20] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let collection_address = bytes.len() as u64;
+        bytes.extend_from_slice(b"GCOL\x01\x00\x00\x00");
+        push_u64(&mut bytes, 56);
+        bytes.extend_from_slice(&[1, 0, 1, 0, 0, 0, 0, 0]);
+        push_u64(&mut bytes, 8);
+        push_u64(&mut bytes, scale_address);
+        bytes.extend_from_slice(&[0; 16]);
+
+        // A variable-length sequence of object references.
+        let mut list_datatype = vec![0x19, 0, 0, 0];
+        list_datatype.extend_from_slice(&16u32.to_le_bytes());
+        list_datatype.extend_from_slice(&[0x17, 0, 0, 0]);
+        list_datatype.extend_from_slice(&8u32.to_le_bytes());
+        let mut list = Vec::new();
+        list.extend_from_slice(&1u32.to_le_bytes());
+        push_u64(&mut list, collection_address);
+        list.extend_from_slice(&1u32.to_le_bytes());
+        list.extend_from_slice(&[0; 16]);
+        let list = attribute_message("DIMENSION_LIST", &list_datatype, &[2], &list);
+
+        let dataset_address = bytes.len() as u64;
+        let mut dataspace = vec![1, 2, 0, 0, 0, 0, 0, 0];
+        push_u64(&mut dataspace, 2);
+        push_u64(&mut dataspace, 3);
+        let mut layout = vec![3, 1];
+        push_u64(&mut layout, UNDEFINED_ADDRESS);
+        push_u64(&mut layout, 24);
+        bytes.extend_from_slice(&object_header(vec![
+            (0x01, 0, dataspace),
+            (0x03, 0, i32_datatype()),
+            (0x08, 0, layout),
+            (0x0C, 0, list),
+        ]));
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset_at(dataset_address))?;
+        assert!(!track!(dataset.is_dimension_scale())?);
+        assert_eq!(
+            track!(dataset.dimension_scales())?,
+            [Some(scale_address), None]
+        );
+
+        let mut scale = track!(file.dataset_at(scale_address))?;
+        assert!(track!(scale.is_dimension_scale())?);
+        assert_eq!(track!(scale.dimension_scales())?, [None]);
+        let (items, _) = track!(track!(scale.read())?.into_vec::<i32>())?;
+        assert_eq!(items, [10, 20]);
+        Ok(())
+    }
+
+    #[test]
+    fn iter_f64_works() -> TopLevelResult {
+        let mut bytes = root_group_file(&[]);
+        let header_address = bytes.len() as u64;
+        let data_address = header_address + i32_dataset_header(&[2, 2], 0).len() as u64;
+        bytes.extend_from_slice(&i32_dataset_header(&[2, 2], data_address));
+        for v in &[1i32, -2, 3, i32::MIN] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let mut file = track!(Hdf5File::from_bytes(bytes.clone()))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        let elements = track!(dataset.iter_f64())?;
+        assert_eq!(elements.size_hint(), (0, Some(4)));
+        let items = elements.collect::<Result<Vec<_>>>()?;
+        assert_eq!(items, [1.0, -2.0, 3.0, f64::from(i32::MIN)]);
+
+        // Truncated data: the iterator stops after the first error.
+        bytes.truncate(bytes.len() - 2);
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        let items = track!(dataset.iter_f64())?.collect::<Vec<_>>();
+        assert_eq!(items.len(), 4);
+        assert!(items[3].is_err());
+
+        // Unallocated storage yields the fill value.
+        let mut dataspace = vec![1, 1, 0, 0, 0, 0, 0, 0];
+        push_u64(&mut dataspace, 3);
+        let mut fill_value = 4u32.to_le_bytes().to_vec();
+        fill_value.extend_from_slice(&7i32.to_le_bytes());
+        let mut layout = vec![3, 1];
+        push_u64(&mut layout, UNDEFINED_ADDRESS);
+        push_u64(&mut layout, 12);
+        let mut bytes = root_group_file(&[]);
+        let header_address = bytes.len() as u64;
+        bytes.extend_from_slice(&object_header(vec![
+            (0x01, 0, dataspace),
+            (0x03, 0, i32_datatype()),
+            (0x04, 0, fill_value),
+            (0x08, 0, layout),
+        ]));
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        let items = track!(dataset.iter_f64())?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(items, [7.0; 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn read_with_axes_works() -> TopLevelResult {
+        let mut bytes = root_group_file(&[]);
+        let header_address = bytes.len() as u64;
+        let data_address = header_address + i32_dataset_header(&[2, 3], 0).len() as u64;
+        bytes.extend_from_slice(&i32_dataset_header(&[2, 3], data_address));
+        for v in 0..6i32 {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        let object = track!(dataset.read_with_axes(&[1, 0]))?;
+        let (items, shape) = track!(object.into_vec::<i32>())?;
+        assert_eq!(shape, [3, 2]);
+        assert_eq!(items, [0, 3, 1, 4, 2, 5]);
+
+        let object = track!(dataset.read_with_axes(&[0, 1]))?;
+        assert_eq!(track!(object.into_vec::<i32>())?.0, [0, 1, 2, 3, 4, 5]);
+        for order in &[&[0][..], &[0, 0], &[1, 2], &[0, 1, 2]] {
+            let error = dataset.read_with_axes(order).unwrap_err();
+            assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn read_row_works() -> TopLevelResult {
+        let mut bytes = root_group_file(&[]);
+        let header_address = bytes.len() as u64;
+        let data_address = header_address + i32_dataset_header(&[3, 2], 0).len() as u64;
+        bytes.extend_from_slice(&i32_dataset_header(&[3, 2], data_address));
+        for v in &[1i32, -2, 3, 4, -5, 6] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        assert_eq!(track!(dataset.read_row(2))?, [-5.0, 6.0]);
+        assert_eq!(track!(dataset.read_row(0))?, [1.0, -2.0]);
+        let error = dataset.read_row(3).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+
+        // Unallocated storage reads as the fill value.
+        let mut bytes = root_group_file(&[]);
+        let header_address = bytes.len() as u64;
+        bytes.extend_from_slice(&i32_dataset_header(&[3, 2], UNDEFINED_ADDRESS));
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        assert_eq!(track!(dataset.read_row(1))?, [0.0, 0.0]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_all_never_panics() -> TopLevelResult {
+        // Makes the last entry of the root group refer to the dataset that follows the group.
+        let mut file = root_group_file(&[("a", Some("/d")), ("d", None)]);
+        let address = file.len() as u64;
+        let entry = file.len() - 40 + 8;
+        file[entry..entry + 8].copy_from_slice(&address.to_le_bytes());
+        file.extend_from_slice(&i32_dataset_header(&[3], address + 112));
+        file.extend_from_slice(&[0; 12]);
+        track!(parse_all(&file))?;
+        let mut hdf5 = track!(Hdf5File::from_bytes(file.clone()))?;
+        assert!(track!(hdf5.dataset("/a"))?.is_some());
+
+        for i in 0..file.len() {
+            let _ = parse_all(&file[..i]);
+            for &mask in &[0x01, 0x80, 0xFF] {
+                let mut corrupted = file.clone();
+                corrupted[i] ^= mask;
+                let _ = parse_all(&corrupted);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_all_walks_new_style_groups() -> TopLevelResult {
+        let file = nested_link_group_file();
+        track!(parse_all(&file))?;
+
+        // The dataset in the new-style subgroup must be parsed too.
+        let datatype = i32_datatype();
+        let position = file
+            .windows(datatype.len())
+            .position(|w| w == &datatype[..])
+            .expect("no datatype message");
+        let mut corrupted = file;
+        corrupted[position] = 0x1F; // Unknown class
+        assert!(parse_all(&corrupted).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn shared_messages_are_resolved() -> TopLevelResult {
+        let mut bytes = root_group_file(&[]);
+
+        // A committed datatype.
+        let datatype_address = bytes.len() as u64;
+        bytes.extend_from_slice(&object_header(vec![(0x03, 0, i32_datatype())]));
+
+        let mut shared = vec![2, 0];
+        push_u64(&mut shared, datatype_address);
+        let header_address = bytes.len() as u64;
+        let header_len = dataset_header(&[2], (0x03, 0b10, shared.clone()), 0).len() as u64;
+        bytes.extend_from_slice(&dataset_header(
+            &[2],
+            (0x03, 0b10, shared),
+            header_address + header_len,
+        ));
+        for v in &[7i32, -8] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let mut dataset = track!(file.dataset_at(header_address))?;
+        assert_eq!(track!(dataset.dtype_message())?.size(), 4);
+        let (items, _) = track!(track!(dataset.read())?.into_vec::<i32>())?;
+        assert_eq!(items, [7, -8]);
+        Ok(())
+    }
+
+    #[test]
+    fn member_names_works() -> TopLevelResult {
+        let mut link_info = vec![0, 1];
+        push_u64(&mut link_info, 2);
+        push_u64(&mut link_info, UNDEFINED_ADDRESS);
+        push_u64(&mut link_info, UNDEFINED_ADDRESS);
+        let mut messages = vec![(0x02, 0, link_info)];
+        for (i, name) in ["b", "a", "c"].iter().enumerate() {
+            let target = LinkTarget::Hard { address: 0 };
+            messages.push((0x06, 0, encode_link(name, &target, Some(i as u64))));
+        }
+        let mut bytes = superblock(SUPERBLOCK_SIZE as u64, 0, &[]);
+        bytes.extend_from_slice(&object_header(messages));
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let names = track!(file.member_names("/", LinkOrder::Creation))?;
+        assert_eq!(
+            names,
+            Some(vec!["b".to_owned(), "a".to_owned(), "c".to_owned()])
+        );
+        let names = track!(file.member_names("/", LinkOrder::Name))?;
+        assert_eq!(
+            names,
+            Some(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()])
+        );
+
+        let bytes = root_group_file(&[("x", None), ("y", Some("/x"))]);
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let names = track!(file.member_names("/", LinkOrder::Creation))?;
+        assert_eq!(names, Some(vec!["x".to_owned(), "y".to_owned()]));
+        Ok(())
+    }
+
+    /// Builds a fractal heap header whose root block is a single direct block at `root_address`.
+    fn fractal_heap(root_address: u64) -> Vec<u8> {
+        let mut bytes = b"FRHP\x00".to_vec();
+        bytes.extend_from_slice(&7u16.to_le_bytes()); // heap ID length
+        bytes.extend_from_slice(&0u16.to_le_bytes()); // I/O filters' encoded length
+        bytes.push(0);
+        bytes.extend_from_slice(&4096u32.to_le_bytes()); // maximum size of managed objects
+        push_u64(&mut bytes, 0);
+        push_u64(&mut bytes, UNDEFINED_ADDRESS);
+        for _ in 0..10 {
+            push_u64(&mut bytes, 0);
+        }
+        bytes.extend_from_slice(&4u16.to_le_bytes()); // table width
+        push_u64(&mut bytes, 512); // starting block size
+        push_u64(&mut bytes, 65536); // maximum direct block size
+        bytes.extend_from_slice(&32u16.to_le_bytes()); // maximum heap size
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        push_u64(&mut bytes, root_address);
+        bytes.extend_from_slice(&0u16.to_le_bytes()); // current number of rows in the root block
+        bytes.extend_from_slice(&[0; 4]);
+        bytes
+    }
+
+    /// Builds a v2 B-tree (header followed by its only leaf node) containing the given records.
+    fn b_tree_v2(address: u64, record_type: u8, records: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = b"BTHD\x00".to_vec();
+        bytes.push(record_type);
+        bytes.extend_from_slice(&512u32.to_le_bytes());
+        bytes.extend_from_slice(&(records[0].len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes()); // depth
+        bytes.extend_from_slice(&[100, 40]);
+        push_u64(&mut bytes, address + 38);
+        bytes.extend_from_slice(&(records.len() as u16).to_le_bytes());
+        push_u64(&mut bytes, records.len() as u64);
+        bytes.extend_from_slice(&lookup3_hash(&bytes).to_le_bytes());
+
+        let mut node = b"BTLF\x00".to_vec();
+        node.push(record_type);
+        for record in records {
+            node.extend_from_slice(record);
+        }
+        node.extend_from_slice(&lookup3_hash(&node).to_le_bytes());
+        bytes.extend_from_slice(&node);
+        bytes
+    }
+
+    #[test]
+    fn dense_links_work() -> TopLevelResult {
+        let root_address = SUPERBLOCK_SIZE as u64;
+        let heap_address = root_address + 64;
+        let block_address = heap_address + fractal_heap(0).len() as u64;
+
+        // Links are created in the order of "b", "a" and "c".
+        let mut block = b"FHDB\x00".to_vec();
+        push_u64(&mut block, heap_address);
+        block.extend_from_slice(&[0; 4]);
+        let mut name_records = Vec::new();
+        let mut creation_records = Vec::new();
+        for (i, name) in ["a", "b", "c"].iter().enumerate() {
+            let target = if *name == "b" {
+                LinkTarget::Hard {
+                    address: root_address,
+                }
+            } else {
+                LinkTarget::Soft {
+                    path: "/b".to_owned(),
+                }
+            };
+            let order = [1u64, 0, 2][i];
+            let link = encode_link(name, &target, Some(order));
+            let mut heap_id = vec![0];
+            heap_id.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            heap_id.extend_from_slice(&(link.len() as u16).to_le_bytes());
+            block.extend_from_slice(&link);
+
+            let mut record = lookup3_hash(name.as_bytes()).to_le_bytes().to_vec();
+            record.extend_from_slice(&heap_id);
+            name_records.push(record);
+            let mut record = order.to_le_bytes().to_vec();
+            record.extend_from_slice(&heap_id);
+            creation_records.push(record);
+        }
+        name_records.sort_by_key(|r| u32::from_le_bytes([r[0], r[1], r[2], r[3]]));
+        creation_records.sort();
+        block.resize(512, 0);
+
+        let name_index_address = block_address + block.len() as u64;
+        let name_index = b_tree_v2(name_index_address, 5, &name_records);
+        let creation_index_address = name_index_address + name_index.len() as u64;
+        let creation_index = b_tree_v2(creation_index_address, 6, &creation_records);
+
+        let mut link_info = vec![0, 0b11];
+        push_u64(&mut link_info, 2);
+        push_u64(&mut link_info, heap_address);
+        push_u64(&mut link_info, name_index_address);
+        push_u64(&mut link_info, creation_index_address);
+        let mut bytes = superblock(root_address, 0, &[]);
+        bytes.extend_from_slice(&object_header(vec![(0x02, 0, link_info)]));
+        assert_eq!(bytes.len() as u64, heap_address);
+        bytes.extend_from_slice(&fractal_heap(block_address));
+        bytes.extend_from_slice(&block);
+        bytes.extend_from_slice(&name_index);
+        bytes.extend_from_slice(&creation_index);
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let names = track!(file.member_names("/", LinkOrder::Creation))?;
+        assert_eq!(
+            names,
+            Some(vec!["b".to_owned(), "a".to_owned(), "c".to_owned()])
+        );
+        let names = track!(file.member_names("/", LinkOrder::Name))?;
+        assert_eq!(
+            names,
+            Some(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()])
+        );
+        assert!(track!(file.contains("/a/c"))?);
+        assert!(!track!(file.contains("/d"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn dense_attributes_work() -> TopLevelResult {
+        let root_address = SUPERBLOCK_SIZE as u64;
+        let heap_address = root_address + 48;
+        let block_address = heap_address + fractal_heap(0).len() as u64;
+
+        let mut block = b"FHDB\x00".to_vec();
+        push_u64(&mut block, heap_address);
+        block.extend_from_slice(&[0; 4]);
+        let mut records = Vec::new();
+        // The datatype is padded to a multiple of eight bytes as version 1 attribute messages require.
+        let mut datatype = i32_datatype();
+        datatype.resize(16, 0);
+        for (name, value) in &[("answer", 42i32), ("offset", -1)] {
+            let attribute = attribute_message(name, &datatype, &[], &value.to_le_bytes());
+            let mut record = vec![0];
+            record.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            record.extend_from_slice(&(attribute.len() as u16).to_le_bytes());
+            record.push(0);
+            block.extend_from_slice(&attribute);
+
+            // Flags, creation order and the hash of the name follow the heap ID.
+            record.extend_from_slice(&[0; 5]);
+            record.extend_from_slice(&lookup3_hash(name.as_bytes()).to_le_bytes());
+            records.push(record);
+        }
+        records.sort_by_key(|r| u32::from_le_bytes([r[13], r[14], r[15], r[16]]));
+        block.resize(512, 0);
+        let name_index_address = block_address + block.len() as u64;
+
+        let mut attribute_info = vec![0, 0];
+        push_u64(&mut attribute_info, heap_address);
+        push_u64(&mut attribute_info, name_index_address);
+        let mut bytes = superblock(root_address, 0, &[]);
+        bytes.extend_from_slice(&object_header(vec![(0x15, 0, attribute_info)]));
+        assert_eq!(bytes.len() as u64, heap_address);
+        let mut heap = fractal_heap(block_address);
+        heap[5..7].copy_from_slice(&8u16.to_le_bytes()); // heap ID length
+        bytes.extend_from_slice(&heap);
+        bytes.extend_from_slice(&block);
+        bytes.extend_from_slice(&b_tree_v2(name_index_address, 8, &records));
+
+        let mut reader = Cursor::new(&bytes);
+        let superblock = track!(Superblock::from_reader(&mut reader))?;
+        let root = track!(superblock.root_object_header(&mut reader, &ReaderOptions::new()))?;
+        assert_eq!(
+            track!(root.read_attribute("answer"))?,
+            crate::ScalarValue::I32(42)
+        );
+        assert_eq!(
+            track!(root.read_attribute("offset"))?,
+            crate::ScalarValue::I32(-1)
+        );
+        assert!(root.read_attribute("scale").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn open_family_works() -> TopLevelResult {
+        let path = track!(family_member_path("a-%06d.h5", 12))?;
+        assert_eq!(path, Path::new("a-000012.h5"));
+        let path = track!(family_member_path("100%%-%d", 3))?;
+        assert_eq!(path, Path::new("100%-3"));
+        assert!(family_member_path("a.h5", 0).is_err());
+        assert!(family_member_path("%d-%d", 0).is_err());
+
+        let mut bytes = root_group_file(&[]);
+        let header_address = bytes.len() as u64;
+        let data_address = header_address + i32_dataset_header(&[3], 0).len() as u64;
+        bytes.extend_from_slice(&i32_dataset_header(&[3], data_address));
+        for v in &[1i32, -2, 3] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        // The driver information block of the family driver (with the member size of 64 bytes).
+        let driver_block = |bytes: &mut Vec<u8>, driver_id: &[u8]| {
+            let address = bytes.len() as u64;
+            bytes[48..56].copy_from_slice(&address.to_le_bytes());
+            bytes.extend_from_slice(&[0, 0, 0, 0, 8, 0, 0, 0]);
+            bytes.extend_from_slice(driver_id);
+            push_u64(bytes, 64);
+        };
+        let mut multi = bytes.clone();
+        driver_block(&mut multi, b"NCSAmult");
+        let e = Hdf5File::from_bytes(multi).expect_err("should fail");
+        assert_eq!(*e.kind(), ErrorKind::Unsupported);
+        driver_block(&mut bytes, b"NCSAfami");
+        let file = track!(Hdf5File::from_bytes(bytes.clone()))?;
+        assert_eq!(
+            file.driver_info(),
+            Some(&DriverInfo::Family { member_size: 64 })
+        );
+
+        let dir = std::env::temp_dir().join(format!("hdf5file-family-{}", std::process::id()));
+        track!(std::fs::create_dir_all(&dir).map_err(Error::from))?;
+        let pattern = dir.join("file-%06d.h5");
+        let pattern = track_assert_some!(pattern.to_str(), ErrorKind::Other);
+        let result = (|| -> Result<()> {
+            for (i, member) in bytes.chunks(64).enumerate() {
+                let path = track!(family_member_path(pattern, i))?;
+                track!(std::fs::write(path, member).map_err(Error::from))?;
+            }
+            let mut file = track!(Hdf5File::open_family(pattern, 64))?;
+            let object = track!(track!(file.dataset_at(header_address))?.read())?;
+            assert_eq!(track!(object.into_vec::<i32>())?.0, [1, -2, 3]);
+
+            let missing = pattern.replace("file-%", "missing-%");
+            let e = Hdf5File::open_family(&missing, 64).expect_err("should fail");
+            assert_eq!(*e.kind(), ErrorKind::NotFound);
+
+            // The member size differs from the one in the driver information block.
+            let other = pattern.replace("file-%", "other-%");
+            for (i, member) in bytes.chunks(32).enumerate() {
+                let path = track!(family_member_path(&other, i))?;
+                track!(std::fs::write(path, member).map_err(Error::from))?;
+            }
+            let e = Hdf5File::open_family(&other, 32).expect_err("should fail");
+            assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+
+            // A short member other than the last one.
+            let path = track!(family_member_path(pattern, 1))?;
+            track!(std::fs::write(path, &bytes[64..100]).map_err(Error::from))?;
+            let e = Hdf5File::open_family(pattern, 64).expect_err("should fail");
+            assert_eq!(*e.kind(), ErrorKind::InvalidFile);
+            Ok(())
+        })();
+        let _ = std::fs::remove_dir_all(&dir);
+        track!(result)?;
+        Ok(())
+    }
+
+    #[test]
+    fn external_links_are_followed() -> TopLevelResult {
+        let dir = std::env::temp_dir().join(format!("hdf5file-external-{}", std::process::id()));
+        track!(std::fs::create_dir_all(&dir).map_err(Error::from))?;
+        let external = |file_name: &str, object_path: &str| LinkTarget::External {
+            file_name: file_name.to_owned(),
+            object_path: object_path.to_owned(),
+        };
+        let files = [
+            ("b.h5", root_group_file(&[("b", None)])),
+            (
+                "a.h5",
+                link_group_file(&[
+                    ("ext", external("b.h5", "/")),
+                    ("missing", external("missing.h5", "/x")),
+                    ("self", external("a.h5", "/self")),
+                ]),
+            ),
+        ];
+        for (name, bytes) in &files {
+            track!(std::fs::write(dir.join(name), bytes).map_err(Error::from))?;
+        }
+
+        let result = (|| -> Result<()> {
+            let mut file = track!(Hdf5File::open_file(dir.join("a.h5")))?;
+            assert!(track!(file.contains("/ext/b"))?);
+            assert!(!track!(file.contains("/ext/c"))?);
+            assert!(!track!(file.contains("/none"))?);
+
+            let (_, path) =
+                track_assert_some!(track!(file.external_file("/ext/b"))?, ErrorKind::Other);
+            assert_eq!(path, Path::new("/b"));
+
+            let e = file.contains("/missing").expect_err("should fail");
+            assert_eq!(*e.kind(), ErrorKind::NotFound);
+
+            let e = file.contains("/self").expect_err("should fail");
+            assert_eq!(*e.kind(), ErrorKind::InvalidFile);
+            Ok(())
+        })();
+        let _ = std::fs::remove_dir_all(&dir);
+        track!(result)?;
+        Ok(())
+    }
+
+    #[test]
+    fn from_reader_works() -> TopLevelResult {
+        let mut bytes = root_group_file(&[]);
+        let header_address = bytes.len() as u64;
+        let data_address = header_address + i32_dataset_header(&[2], 0).len() as u64;
+        bytes.extend_from_slice(&i32_dataset_header(&[2], data_address));
+        bytes.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0]);
+        let mut file = track!(Hdf5File::from_reader(Cursor::new(bytes)))?;
+        let object = track!(track!(file.dataset_at(header_address))?.read())?;
+        assert_eq!(track!(object.into_vec::<i32>())?.0, [1, 2]);
+
+        // External links aren't followed.
+        let target = LinkTarget::External {
+            file_name: "b.h5".to_owned(),
+            object_path: "/".to_owned(),
+        };
+        let bytes = link_group_file(&[("ext", target)]);
+        let mut file = track!(Hdf5File::from_reader(Cursor::new(bytes)))?;
+        let e = file.contains("/ext").expect_err("should fail");
+        assert_eq!(*e.kind(), ErrorKind::Unsupported);
+        Ok(())
+    }
+
+    #[test]
+    fn soft_links_are_followed() -> TopLevelResult {
+        let bytes = root_group_file(&[
+            ("a", Some("/b")),
+            ("b", None),
+            ("c", Some("a")),
+            ("d", Some("missing")),
+        ]);
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        assert!(track!(file.contains("/a"))?);
+        assert!(track!(file.contains("/c"))?);
+        assert!(!track!(file.contains("/d"))?);
+        assert!(!track!(file.contains("/missing"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn circular_soft_links_are_detected() -> TopLevelResult {
+        let bytes =
+            root_group_file(&[("a", Some("/b")), ("b", Some("a")), ("loop", Some("/loop"))]);
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        for path in &["/loop", "/a", "/b"] {
+            let e = file.contains(path).expect_err("should fail");
+            assert_eq!(*e.kind(), ErrorKind::InvalidFile);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn in_memory_file_can_be_shared_across_threads() {
+        fn assert_send_sync_clone<T: Send + Sync + Clone>() {}
+        assert_send_sync_clone::<Hdf5File<Cursor<Arc<[u8]>>>>();
+        assert_send_sync_clone::<Hdf5File<PositionedReader<Arc<File>>>>();
+
+        assert!(Hdf5File::from_bytes(vec![0; 16]).is_err());
+    }
+
+    #[test]
+    fn cloned_files_can_be_read_on_several_threads() -> TopLevelResult {
+        let mut writer = crate::writer::Hdf5Writer::new(Vec::new());
+        for i in 0..4 {
+            let values = (0..1000)
+                .map(|j| f64::from(i * 1000 + j))
+                .collect::<Vec<_>>();
+            track!(writer.add_dataset(&format!("d{}", i), &[10, 100], &values))?;
+        }
+        let file = track!(Hdf5File::from_bytes(track!(writer.finish())?))?;
+
+        fn read_all(mut file: Hdf5File<Cursor<Arc<[u8]>>>) -> Result<Vec<Vec<f64>>> {
+            (0..4)
+                .map(|i| {
+                    let path = format!("/d{}", i);
+                    let mut dataset =
+                        track_assert_some!(track!(file.dataset(&path))?, ErrorKind::Other; path);
+                    let (items, _) = track!(track!(dataset.read())?.into_vec::<f64>())?;
+                    Ok(items)
+                })
+                .collect()
+        }
+        let expected = track!(read_all(file.clone()))?;
+        assert_eq!(expected[3][999], 3999.0);
+
+        let handles = (0..4)
+            .map(|_| {
+                let file = file.clone();
+                std::thread::spawn(move || read_all(file))
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            let values = track!(handle.join().expect("the reading thread panicked"))?;
+            assert_eq!(values, expected);
+        }
+        Ok(())
+    }
+}
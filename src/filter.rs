@@ -0,0 +1,577 @@
+//! Filters used in data storage filter pipelines.
+//!
+//! See: https://support.hdfgroup.org/HDF5/doc/H5.format.html#FilterMessage
+use crate::{ErrorKind, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Filter identifier of the deflate (zlib) filter.
+#[cfg(feature = "deflate")]
+pub const FILTER_DEFLATE: u16 = 1;
+
+/// Filter identifier of the shuffle filter.
+pub const FILTER_SHUFFLE: u16 = 2;
+
+/// Filter identifier of the Fletcher32 checksum filter.
+pub const FILTER_FLETCHER32: u16 = 3;
+
+/// Filter identifier of the N-bit filter.
+pub const FILTER_NBIT: u16 = 5;
+
+/// Filter identifier of the scale-offset filter.
+pub const FILTER_SCALEOFFSET: u16 = 6;
+
+/// Datatype class of the N-bit filter parameters for integers and floating-point numbers.
+const NBIT_ATOMIC: u32 = 1;
+
+/// Size of the header (minimum bits and minimum value) written by the scale-offset filter.
+const SCALEOFFSET_HEADER_SIZE: usize = 21;
+
+/// A function that reverts a filter.
+///
+/// The first argument is the client data stored in the filter pipeline message,
+/// and the second one is the filtered data.
+pub type FilterDecoder = dyn Fn(&[u32], Vec<u8>) -> Result<Vec<u8>> + Send + Sync;
+
+/// A `FilterDecoder` that also takes the size of the data after all filters have been reverted.
+type BoundedFilterDecoder = dyn Fn(&[u32], Vec<u8>, usize) -> Result<Vec<u8>> + Send + Sync;
+
+/// Registry of the decoders of filters.
+///
+/// The default registry contains the decoders of the shuffle, Fletcher32, N-bit and scale-offset filters,
+/// and the one of the deflate filter if the `deflate` feature is enabled.
+/// Decoders of other filters (e.g., third-party compressors such as Blosc)
+/// can be added by using `FilterRegistry::register`.
+#[derive(Clone)]
+pub struct FilterRegistry {
+    decoders: HashMap<u16, Arc<BoundedFilterDecoder>>,
+}
+impl FilterRegistry {
+    /// Makes a new empty `FilterRegistry` instance.
+    pub fn new() -> Self {
+        Self {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Registers the decoder of the filter identified by `id`.
+    ///
+    /// If a decoder has already been registered for `id`, it will be replaced.
+    pub fn register<F>(&mut self, id: u16, decoder: F) -> &mut Self
+    where
+        F: Fn(&[u32], Vec<u8>) -> Result<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.register_bounded(id, move |client_data, data, _| decoder(client_data, data))
+    }
+
+    /// Same as `register` except that `decoder` also takes the size of the fully decoded data.
+    fn register_bounded<F>(&mut self, id: u16, decoder: F) -> &mut Self
+    where
+        F: Fn(&[u32], Vec<u8>, usize) -> Result<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.decoders.insert(id, Arc::new(decoder));
+        self
+    }
+
+    /// Returns `true` if a decoder has been registered for `id`, otherwise `false`.
+    pub fn contains(&self, id: u16) -> bool {
+        self.decoders.contains_key(&id)
+    }
+
+    /// Reverts the filter identified by `id`.
+    ///
+    /// `decoded_size` is the size of the data after all filters of the pipeline have been reverted.
+    pub(crate) fn decode(
+        &self,
+        id: u16,
+        client_data: &[u32],
+        data: Vec<u8>,
+        decoded_size: usize,
+    ) -> Result<Vec<u8>> {
+        let decoder = track_assert_some!(
+            self.decoders.get(&id),
+            ErrorKind::Unsupported,
+            "No decoder is registered for the filter {}",
+            id
+        );
+        track!(decoder(client_data, data, decoded_size))
+    }
+}
+impl Default for FilterRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register(FILTER_SHUFFLE, |client_data, data| {
+                track!(decode_shuffle(data, client_data))
+            })
+            .register(FILTER_FLETCHER32, |_, data| track!(decode_fletcher32(data)))
+            .register(FILTER_NBIT, |client_data, data| {
+                track!(decode_nbit(&data, client_data))
+            })
+            .register_bounded(FILTER_SCALEOFFSET, |client_data, data, decoded_size| {
+                track!(decode_scaleoffset(&data, client_data, decoded_size))
+            });
+        #[cfg(feature = "deflate")]
+        registry.register(FILTER_DEFLATE, |_, data| track!(decode_deflate(&data)));
+        registry
+    }
+}
+impl fmt::Debug for FilterRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut ids = self.decoders.keys().collect::<Vec<_>>();
+        ids.sort();
+        f.debug_struct("FilterRegistry").field("ids", &ids).finish()
+    }
+}
+
+/// Decompresses the zlib stream written by the deflate filter.
+#[cfg(feature = "deflate")]
+pub fn decode_deflate(data: &[u8]) -> Result<Vec<u8>> {
+    use std::io::Read;
+    use trackable::error::ErrorKindExt;
+
+    let mut output = Vec::new();
+    track!(flate2::read::ZlibDecoder::new(data)
+        .read_to_end(&mut output)
+        .map_err(|e| crate::Error::from(ErrorKind::InvalidFile.cause(e))))?;
+    Ok(output)
+}
+
+/// Reverts the byte shuffling applied by the shuffle filter.
+pub fn decode_shuffle(data: Vec<u8>, client_data: &[u32]) -> Result<Vec<u8>> {
+    let size = *track_assert_some!(client_data.first(), ErrorKind::InvalidFile) as usize;
+    if size <= 1 || data.len() < size {
+        return Ok(data);
+    }
+
+    // Trailing bytes that don't form a whole element are left as they are.
+    let count = data.len() / size;
+    let mut output = vec![0u8; data.len()];
+    for (i, byte) in data[..count * size].iter().enumerate() {
+        output[(i % count) * size + i / count] = *byte;
+    }
+    output[count * size..].copy_from_slice(&data[count * size..]);
+    Ok(output)
+}
+
+/// Strips the checksum appended by the Fletcher32 filter from `data` without verifying it.
+pub fn strip_fletcher32(mut data: Vec<u8>) -> Result<Vec<u8>> {
+    track_assert!(data.len() >= 4, ErrorKind::InvalidFile; data.len());
+    data.truncate(data.len() - 4);
+    Ok(data)
+}
+
+/// Verifies the checksum appended by the Fletcher32 filter and strips it from `data`.
+pub fn decode_fletcher32(data: Vec<u8>) -> Result<Vec<u8>> {
+    track_assert!(data.len() >= 4, ErrorKind::InvalidFile; data.len());
+    let payload_len = data.len() - 4;
+    let stored = u32::from_le_bytes([
+        data[payload_len],
+        data[payload_len + 1],
+        data[payload_len + 2],
+        data[payload_len + 3],
+    ]);
+    let data = track!(strip_fletcher32(data))?;
+
+    let computed = fletcher32(&data);
+    // Some old versions of the HDF5 library stored the checksum with swapped bytes.
+    let reversed = ((computed & 0x00FF_00FF) << 8) | ((computed & 0xFF00_FF00) >> 8);
+    track_assert!(
+        stored == computed || stored == reversed,
+        ErrorKind::InvalidFile,
+        "Fletcher32 checksum mismatch: stored={:#010x}, computed={:#010x}",
+        stored,
+        computed
+    );
+    Ok(data)
+}
+
+/// Calculates the Fletcher32 checksum in the same manner as the HDF5 library
+/// (i.e., 16-bit words are read in big-endian order).
+pub fn fletcher32(data: &[u8]) -> u32 {
+    fn reduce(x: u32) -> u32 {
+        (x & 0xFFFF) + (x >> 16)
+    }
+
+    let mut sum1: u32 = 0;
+    let mut sum2: u32 = 0;
+
+    // 360 words (720 bytes) is the largest block size for which the sums don't overflow.
+    let (words, rest) = data.split_at(data.len() & !1);
+    for block in words.chunks(720) {
+        for word in block.chunks_exact(2) {
+            sum1 += (u32::from(word[0]) << 8) | u32::from(word[1]);
+            sum2 += sum1;
+        }
+        sum1 = reduce(sum1);
+        sum2 = reduce(sum2);
+    }
+    if let [last] = rest {
+        sum1 += u32::from(*last) << 8;
+        sum2 += sum1;
+        sum1 = reduce(sum1);
+        sum2 = reduce(sum2);
+    }
+
+    sum1 = reduce(sum1);
+    sum2 = reduce(sum2);
+    (sum2 << 16) | sum1
+}
+
+/// Decodes data packed by the N-bit filter.
+///
+/// Each element is stored as its significant bits only (i.e., `precision` bits starting at `offset`),
+/// and the bits of all elements are concatenated from the most significant one.
+/// Unpacked elements have zeros in their padding bits.
+///
+/// Currently only atomic (integer and floating-point) datatypes of at most 8 bytes are supported.
+pub fn decode_nbit(data: &[u8], client_data: &[u32]) -> Result<Vec<u8>> {
+    track_assert!(client_data.len() >= 4, ErrorKind::InvalidFile; client_data.len());
+    let need_not_compress = client_data[1] == 1;
+    let element_count = client_data[2] as usize;
+    let class = client_data[3];
+    if need_not_compress {
+        return Ok(data.to_vec());
+    }
+    track_assert_eq!(
+        class,
+        NBIT_ATOMIC,
+        ErrorKind::Unsupported,
+        "Only atomic datatypes are supported by the N-bit filter"
+    );
+    track_assert!(client_data.len() >= 8, ErrorKind::InvalidFile; client_data.len());
+    let size = client_data[4] as usize;
+    let big_endian = client_data[5] == 1;
+    let precision = client_data[6] as usize;
+    let offset = client_data[7] as usize;
+    track_assert!((1..=8).contains(&size), ErrorKind::Unsupported; size);
+    track_assert!(
+        precision >= 1 && precision + offset <= size * 8,
+        ErrorKind::InvalidFile;
+        size, precision, offset
+    );
+    track_assert!(
+        data.len() * 8 >= element_count * precision,
+        ErrorKind::InvalidFile
+    );
+
+    let mut output = Vec::with_capacity(element_count * size);
+    let mut bits = BitReader::new(data);
+    for _ in 0..element_count {
+        let value = track!(bits.read_bits(precision))? << offset;
+        write_uint(&mut output, value, size, big_endian);
+    }
+    Ok(output)
+}
+
+/// Decodes data compressed by the scale-offset filter.
+///
+/// The element count is taken from `client_data`, and the decoded data must not be larger than `max_size` bytes.
+/// Currently only integer datatypes are supported.
+pub fn decode_scaleoffset(data: &[u8], client_data: &[u32], max_size: usize) -> Result<Vec<u8>> {
+    track_assert!(client_data.len() >= 8, ErrorKind::InvalidFile; client_data.len());
+    let scale_type = client_data[0];
+    let element_count = client_data[2] as usize;
+    let class = client_data[3];
+    let size = client_data[4] as usize;
+    let big_endian = client_data[6] == 1;
+    let fill_value_defined = client_data[7] == 1;
+    track_assert_eq!(
+        class,
+        0,
+        ErrorKind::Unsupported,
+        "Only integers are supported by the scale-offset filter"
+    );
+    track_assert_eq!(scale_type, 2, ErrorKind::InvalidFile);
+    track_assert!((1..=8).contains(&size), ErrorKind::InvalidFile; size);
+    track_assert!(data.len() >= SCALEOFFSET_HEADER_SIZE, ErrorKind::InvalidFile; data.len());
+
+    let min_bits = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let min_value_size = std::cmp::min(usize::from(data[4]), 8);
+    let min_value = read_le_uint(&data[5..5 + min_value_size]);
+    track_assert!(min_bits <= size * 8, ErrorKind::InvalidFile; min_bits, size);
+
+    let payload = &data[SCALEOFFSET_HEADER_SIZE..];
+    let output_size = element_count.checked_mul(size);
+    let output_size = track_assert_some!(output_size, ErrorKind::InvalidFile; element_count, size);
+
+    // Elements packed in zero bits take no input, so only the expected size bounds the output.
+    track_assert!(output_size <= max_size, ErrorKind::InvalidFile; output_size, max_size);
+    if min_bits == size * 8 {
+        // The values are stored as-is (in the byte order of the dataset).
+        let values = payload.get(..output_size);
+        return Ok(track_assert_some!(values, ErrorKind::InvalidFile; payload.len()).to_vec());
+    }
+    let packed_bits = element_count.checked_mul(min_bits);
+    let packed_bits = track_assert_some!(packed_bits, ErrorKind::InvalidFile; element_count);
+    track_assert!(
+        packed_bits <= payload.len().saturating_mul(8),
+        ErrorKind::InvalidFile;
+        element_count,
+        min_bits,
+        payload.len()
+    );
+
+    let fill_value = if fill_value_defined {
+        let bytes = client_data[8..]
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        track_assert!(bytes.len() >= size, ErrorKind::InvalidFile);
+        Some(read_le_uint(&bytes[..size]))
+    } else {
+        None
+    };
+
+    let max_packed_value = (1u64 << min_bits) - 1;
+    let type_mask = if size == 8 {
+        u64::MAX
+    } else {
+        (1u64 << (size * 8)) - 1
+    };
+    // Unless every element is packed in zero bits, the element count is bounded by the input length.
+    let capacity = std::cmp::min(output_size, payload.len().saturating_mul(8 * size));
+    let mut output = Vec::with_capacity(capacity);
+    let mut bits = BitReader::new(payload);
+    for _ in 0..element_count {
+        let packed = track!(bits.read_bits(min_bits))?;
+        let value = match fill_value {
+            Some(fill_value) if packed == max_packed_value => fill_value,
+            _ => packed.wrapping_add(min_value) & type_mask,
+        };
+        write_uint(&mut output, value, size, big_endian);
+    }
+    Ok(output)
+}
+
+fn read_le_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |acc, &b| (acc << 8) | u64::from(b))
+}
+
+fn write_uint(buf: &mut Vec<u8>, value: u64, size: usize, big_endian: bool) {
+    let bytes = value.to_le_bytes();
+    if big_endian {
+        buf.extend(bytes[..size].iter().rev());
+    } else {
+        buf.extend_from_slice(&bytes[..size]);
+    }
+}
+
+/// A reader that reads bits from the most significant one of each byte.
+#[derive(Debug)]
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn read_bits(&mut self, count: usize) -> Result<u64> {
+        let mut value = 0;
+        for _ in 0..count {
+            let byte = track_assert_some!(
+                self.bytes.get(self.position / 8),
+                ErrorKind::InvalidFile,
+                "Unexpected end of packed data"
+            );
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | u64::from(bit);
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn fletcher32_works() {
+        // The well-known test vectors (0xF04FC729, 0x56502D2A) adjusted for big-endian words.
+        assert_eq!(fletcher32(b"abcde"), 0x4FF0_29C7);
+        assert_eq!(fletcher32(b"abcdef"), 0x5056_2A2D);
+
+        let data = (0..1024).map(|i| i as u8).collect::<Vec<_>>();
+        assert_eq!(fletcher32(&data), 0x1516_00FF);
+    }
+
+    #[test]
+    fn decode_fletcher32_works() -> TopLevelResult {
+        let mut data = b"abcdef".to_vec();
+        data.extend_from_slice(&0x5056_2A2Du32.to_le_bytes());
+        assert_eq!(track!(decode_fletcher32(data))?, b"abcdef");
+
+        let mut data = b"abcdeg".to_vec();
+        data.extend_from_slice(&0x5056_2A2Du32.to_le_bytes());
+        assert_eq!(
+            decode_fletcher32(data).map_err(|e| *e.kind()),
+            Err(ErrorKind::InvalidFile)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_scaleoffset_works() -> TopLevelResult {
+        let values: [i16; 6] = [-300, -299, -250, -300, -290, -281];
+        let min = -300i16;
+        let min_bits = 6;
+
+        // Encodes the values in the same manner as the HDF5 library.
+        let mut data = Vec::new();
+        data.extend_from_slice(&(min_bits as u32).to_le_bytes());
+        data.push(8);
+        data.extend_from_slice(&(i64::from(min) as u64).to_le_bytes());
+        data.resize(SCALEOFFSET_HEADER_SIZE, 0);
+        let mut bits = Vec::new();
+        for &v in &values {
+            let packed = (v - min) as u64;
+            bits.extend((0..min_bits).rev().map(|i| (packed >> i) & 1));
+        }
+        for byte in bits.chunks(8) {
+            let byte = byte
+                .iter()
+                .chain(std::iter::repeat(&0))
+                .take(8)
+                .fold(0, |acc, &b| (acc << 1) | b as u8);
+            data.push(byte);
+        }
+
+        let client_data = [2, 0, values.len() as u32, 0, 2, 1, 0, 0];
+        let decoded = track!(decode_scaleoffset(&data, &client_data, values.len() * 2))?;
+        let decoded = decoded
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, values);
+
+        // Values stored in full precision are copied in the byte order of the dataset.
+        data[..4].copy_from_slice(&16u32.to_le_bytes());
+        data.truncate(SCALEOFFSET_HEADER_SIZE);
+        data.extend_from_slice(&[0x12, 0x34, 0xAB, 0xCD]);
+        let client_data = [2, 0, 2, 0, 2, 1, 1, 0];
+        let decoded = track!(decode_scaleoffset(&data, &client_data, 4))?;
+        assert_eq!(decoded, [0x12, 0x34, 0xAB, 0xCD]);
+
+        // Element counts exceeding the input are rejected.
+        let client_data = [2, 0, u32::MAX, 0, 8, 1, 0, 0];
+        assert!(decode_scaleoffset(&data, &client_data, usize::MAX).is_err());
+
+        // So are those exceeding the expected size, even if every element is packed in zero bits.
+        data.truncate(SCALEOFFSET_HEADER_SIZE);
+        data[..4].copy_from_slice(&0u32.to_le_bytes());
+        let client_data = [2, 0, u32::MAX, 0, 8, 1, 0, 0];
+        assert!(decode_scaleoffset(&data, &client_data, 1024).is_err());
+        let client_data = [2, 0, 3, 0, 2, 1, 0, 0];
+        assert_eq!(track!(decode_scaleoffset(&data, &client_data, 6))?.len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_nbit_works() -> TopLevelResult {
+        let values: [u16; 5] = [0x0FFF, 0, 0x0123, 0x0ABC, 1];
+        let (precision, offset) = (12, 2);
+
+        // Packs the significant bits in the same manner as the HDF5 library.
+        let mut bits = Vec::new();
+        for &v in &values {
+            bits.extend((0..precision).rev().map(|i| (v >> i) & 1));
+        }
+        let data = bits
+            .chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .chain(std::iter::repeat(&0))
+                    .take(8)
+                    .fold(0, |acc, &b| (acc << 1) | b as u8)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(data.len(), 8);
+
+        let client_data = [8, 0, values.len() as u32, 1, 2, 0, precision, offset];
+        let decoded = track!(decode_nbit(&data, &client_data))?;
+        let decoded = decoded
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            values.iter().map(|v| v << offset).collect::<Vec<_>>()
+        );
+
+        // Big-endian.
+        let client_data = [8, 0, 1, 1, 2, 1, precision, offset];
+        assert_eq!(track!(decode_nbit(&data, &client_data))?, [0x3F, 0xFC]);
+
+        // Stored without compression.
+        let client_data = [8, 1, 1, 1, 2, 0, 16, 0];
+        assert_eq!(track!(decode_nbit(&[1, 2], &client_data))?, [1, 2]);
+
+        // Truncated data.
+        let client_data = [8, 0, values.len() as u32, 1, 2, 0, precision, offset];
+        assert!(decode_nbit(&data[..7], &client_data).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn decode_deflate_works() -> TopLevelResult {
+        use flate2::write::ZlibEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let data = (0..1000).map(|i| (i % 7) as u8).collect::<Vec<_>>();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        track!(encoder.write_all(&data).map_err(crate::Error::from))?;
+        let compressed = track!(encoder.finish().map_err(crate::Error::from))?;
+        assert!(compressed.len() < data.len());
+
+        let registry = FilterRegistry::default();
+        assert_eq!(
+            track!(registry.decode(FILTER_DEFLATE, &[6], compressed.clone(), data.len()))?,
+            data
+        );
+        assert_eq!(
+            decode_deflate(&compressed[..compressed.len() / 2]).map_err(|e| *e.kind()),
+            Err(ErrorKind::InvalidFile)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_shuffle_works() -> TopLevelResult {
+        let data = vec![1, 3, 5, 2, 4, 6, 7];
+        assert_eq!(track!(decode_shuffle(data, &[2]))?, [1, 2, 3, 4, 5, 6, 7]);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_registry_works() -> TopLevelResult {
+        let mut registry = FilterRegistry::default();
+        assert!(registry.contains(FILTER_FLETCHER32));
+        assert!(!registry.contains(32001));
+        assert_eq!(
+            registry
+                .decode(32001, &[], vec![1], 1)
+                .map_err(|e| *e.kind()),
+            Err(ErrorKind::Unsupported)
+        );
+
+        registry.register(32001, |client_data, mut data| {
+            data.extend(client_data.iter().map(|&v| v as u8));
+            Ok(data)
+        });
+        assert_eq!(
+            track!(registry.decode(32001, &[2, 3], vec![1], 3))?,
+            [1, 2, 3]
+        );
+        Ok(())
+    }
+}
@@ -1,15 +1,261 @@
 use crate::{Error, ErrorKind, Result};
-use byteorder::{LittleEndian, ReadBytesExt};
-use std::io::{Read, Seek, SeekFrom};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::Arc;
+
+/// This trait allows for reading bytes at the given offsets (like `pread(2)`).
+///
+/// Unlike `Read + Seek`, reads don't depend on (or move) a shared cursor,
+/// so an instance can be shared among multiple readers.
+pub trait ReadAt {
+    /// Reads bytes starting at `offset` into `buf`, and returns the number of bytes read.
+    ///
+    /// `0` is returned if `offset` is at (or beyond) the end of the data.
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize>;
+}
+impl ReadAt for [u8] {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        if offset >= self.len() as u64 {
+            return Ok(0);
+        }
+        let src = &self[offset as usize..];
+        let n = std::cmp::min(src.len(), buf.len());
+        buf[..n].copy_from_slice(&src[..n]);
+        Ok(n)
+    }
+}
+impl ReadAt for Vec<u8> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self[..].read_at(buf, offset)
+    }
+}
+#[cfg(unix)]
+impl ReadAt for File {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        std::os::unix::fs::FileExt::read_at(self, buf, offset)
+    }
+}
+#[cfg(windows)]
+impl ReadAt for File {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        std::os::windows::fs::FileExt::seek_read(self, buf, offset)
+    }
+}
+impl<T: ReadAt + ?Sized> ReadAt for &T {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+}
+impl<T: ReadAt + ?Sized> ReadAt for Arc<T> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+}
+
+/// The member files of a file split by the family driver, which are presented as their concatenation.
+///
+/// Every member except the last one holds exactly `member_size` bytes of the logical file,
+/// so a logical offset maps to the member `offset / member_size` at `offset % member_size`.
+#[derive(Debug)]
+pub struct FamilyMembers<T = File> {
+    members: Vec<T>,
+    member_size: u64,
+}
+impl<T: ReadAt> FamilyMembers<T> {
+    /// Makes a new `FamilyMembers` instance from the members in order.
+    ///
+    /// If `member_size` is zero, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn new(members: Vec<T>, member_size: u64) -> Result<Self> {
+        track_assert_ne!(member_size, 0, ErrorKind::InvalidInput);
+        Ok(Self {
+            members,
+            member_size,
+        })
+    }
+
+    /// Returns the number of the members.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns `true` if there are no members.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Returns the size of each member.
+    pub fn member_size(&self) -> u64 {
+        self.member_size
+    }
+}
+impl<T: ReadAt> ReadAt for FamilyMembers<T> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let index = offset / self.member_size;
+        if index >= self.members.len() as u64 {
+            return Ok(0);
+        }
+        let member = &self.members[index as usize];
+        // Reads never cross the boundary of a member; `read_exact` issues another read for the rest.
+        let offset = offset % self.member_size;
+        let len = std::cmp::min(buf.len() as u64, self.member_size - offset) as usize;
+        member.read_at(&mut buf[..len], offset)
+    }
+}
+
+/// An adapter that provides `Read + Seek` over a `ReadAt` with its own cursor.
+///
+/// Each read is issued as a single positioned read,
+/// and clones (if `T` is cheap to clone, e.g., `Arc<File>`) can be used independently.
+/// Note that seeking relative to the end of the data isn't supported.
+#[derive(Debug, Clone)]
+pub struct PositionedReader<T> {
+    inner: T,
+    position: u64,
+}
+impl<T: ReadAt> PositionedReader<T> {
+    /// Makes a new `PositionedReader` instance whose cursor is at the beginning of `inner`.
+    pub fn new(inner: T) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Takes the ownership of the underlying reader.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+impl<T: ReadAt> Read for PositionedReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read_at(buf, self.position)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+impl<T: ReadAt> Seek for PositionedReader<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) if delta < 0 => {
+                self.position.checked_sub(delta.unsigned_abs())
+            }
+            SeekFrom::Current(delta) => self.position.checked_add(delta as u64),
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Seeking from the end isn't supported",
+                ));
+            }
+        };
+        self.position = position
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek position"))?;
+        Ok(self.position)
+    }
+}
+
+/// A reader that keeps the bytes read through it (e.g., to compute the checksum of a structure).
+#[derive(Debug)]
+pub(crate) struct RecordingReader<R> {
+    inner: R,
+    bytes: Vec<u8>,
+}
+impl<R> RecordingReader<R> {
+    /// Makes a new `RecordingReader` whose record starts with `bytes` (e.g., an already read signature).
+    pub fn new(inner: R, bytes: Vec<u8>) -> Self {
+        Self { inner, bytes }
+    }
+
+    pub fn recorded(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+impl<R: Read> Read for RecordingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// The stream through which all the reads of an HDF5 file are issued.
+///
+/// Addresses in HDF5 files are relative to the base address stored in the superblock,
+/// so seeking to `SeekFrom::Start(address)` is offset by it, and positions are reported relative to it.
+#[derive(Debug, Clone)]
+pub(crate) struct FileContext<T> {
+    inner: T,
+    base_address: u64,
+}
+impl<T> FileContext<T> {
+    pub fn new(inner: T, base_address: u64) -> Self {
+        Self {
+            inner,
+            base_address,
+        }
+    }
+}
+impl<T: Read> Read for FileContext<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+impl<T: Seek> Seek for FileContext<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(address) => {
+                let position = address.checked_add(self.base_address).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek position")
+                })?;
+                SeekFrom::Start(position)
+            }
+            pos => pos,
+        };
+        let position = self.inner.seek(pos)?;
+        position.checked_sub(self.base_address).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seeking before the base address",
+            )
+        })
+    }
+}
 
 pub trait SeekExt: Seek {
     fn seek_to(&mut self, offset: u64) -> Result<()> {
         track!(self.seek(SeekFrom::Start(offset)).map_err(Error::from))?;
         Ok(())
     }
+
+    fn position(&mut self) -> Result<u64> {
+        track!(self.stream_position().map_err(Error::from))
+    }
+
+    /// Calls `f` and, if it fails, records the file offset at which the failure occurred.
+    fn with_offset<F, T>(&mut self, what: &str, f: F) -> Result<T>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        f(self).map_err(|e| match self.position() {
+            Ok(offset) => track!(
+                e,
+                "failed at file offset {:#x} while reading {}",
+                offset,
+                what
+            ),
+            Err(_) => track!(e, "failed while reading {}", what),
+        })
+    }
 }
 impl<T: Seek> SeekExt for T {}
 
+/// The default maximum number of bytes allocated up front by `ReadExt::read_vec`.
+pub(crate) const DEFAULT_PREALLOCATION_LIMIT: usize = 1024 * 1024;
+
 pub trait ReadExt: Read {
     fn assert_signature(&mut self, expected: &[u8]) -> Result<()> {
         let mut signature = [0; 4];
@@ -32,6 +278,12 @@ pub trait ReadExt: Read {
     }
 
     fn read_null_terminated_string(&mut self) -> Result<String> {
+        track!(self.read_padded_null_terminated_string(1))
+    }
+
+    /// Reads a null-terminated string, and then skips the padding bytes that follow it
+    /// so that the number of consumed bytes (including the terminator) becomes a multiple of `alignment`.
+    fn read_padded_null_terminated_string(&mut self, alignment: usize) -> Result<String> {
         let mut buf = Vec::new();
         loop {
             let b = track!(self.read_u8())?;
@@ -41,7 +293,12 @@ pub trait ReadExt: Read {
                 buf.push(b);
             }
         }
-        track!(String::from_utf8(buf).map_err(Error::from))
+        let len = buf.len() + 1;
+        track!(self.skip((alignment - len % alignment) % alignment))?;
+        track!(
+            String::from_utf8(buf).map_err(Error::from),
+            "Not a UTF-8 string"
+        )
     }
 
     fn read_bytes(&mut self, buf: &mut [u8]) -> Result<()> {
@@ -49,8 +306,31 @@ pub trait ReadExt: Read {
     }
 
     fn read_vec(&mut self, n: usize) -> Result<Vec<u8>> {
-        let mut bytes = vec![0; n];
-        track!(self.read_bytes(&mut bytes))?;
+        track!(self.read_exact_vec(n, DEFAULT_PREALLOCATION_LIMIT))
+    }
+
+    /// Reads exactly `len` bytes, allocating at most `cap` bytes up front.
+    ///
+    /// A bogus length read from a corrupted file must not cause a huge allocation,
+    /// so buffers larger than `cap` grow as the data is actually read.
+    fn read_exact_vec(&mut self, len: usize, cap: usize) -> Result<Vec<u8>> {
+        if len <= cap {
+            let mut bytes = vec![0; len];
+            track!(self.read_bytes(&mut bytes))?;
+            return Ok(bytes);
+        }
+
+        let mut bytes = Vec::with_capacity(cap);
+        track!(self
+            .take(len as u64)
+            .read_to_end(&mut bytes)
+            .map_err(Error::from))?;
+        track_assert_eq!(
+            bytes.len(),
+            len,
+            ErrorKind::IoError,
+            "Unexpected end of data"
+        );
         Ok(bytes)
     }
 
@@ -74,12 +354,159 @@ pub trait ReadExt: Read {
         track!(ReadBytesExt::read_u64::<LittleEndian>(self).map_err(Error::from))
     }
 
+    /// Reads a little-endian unsigned integer of `size` bytes (at most 8).
+    fn read_uint(&mut self, size: usize) -> Result<u64> {
+        track_assert!(size <= 8, ErrorKind::InvalidFile; size);
+        if size == 0 {
+            return Ok(0);
+        }
+        track!(ReadBytesExt::read_uint::<LittleEndian>(self, size).map_err(Error::from))
+    }
+
     fn read_u128(&mut self) -> Result<u128> {
         track!(ReadBytesExt::read_u128::<LittleEndian>(self).map_err(Error::from))
     }
 
-    fn read_f32(&mut self) -> Result<f32> {
-        track!(ReadBytesExt::read_f32::<LittleEndian>(self).map_err(Error::from))
+    fn read_u16_be(&mut self) -> Result<u16> {
+        track!(ReadBytesExt::read_u16::<BigEndian>(self).map_err(Error::from))
+    }
+
+    fn read_u32_be(&mut self) -> Result<u32> {
+        track!(ReadBytesExt::read_u32::<BigEndian>(self).map_err(Error::from))
+    }
+
+    fn read_u64_be(&mut self) -> Result<u64> {
+        track!(ReadBytesExt::read_u64::<BigEndian>(self).map_err(Error::from))
+    }
+
+    fn read_i16_be(&mut self) -> Result<i16> {
+        track!(ReadBytesExt::read_i16::<BigEndian>(self).map_err(Error::from))
+    }
+
+    fn read_i32_be(&mut self) -> Result<i32> {
+        track!(ReadBytesExt::read_i32::<BigEndian>(self).map_err(Error::from))
+    }
+
+    fn read_i64_be(&mut self) -> Result<i64> {
+        track!(ReadBytesExt::read_i64::<BigEndian>(self).map_err(Error::from))
     }
 }
 impl<T: Read> ReadExt for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn with_offset_reports_failure_position() {
+        let mut reader = Cursor::new(vec![0; 6]);
+        let result = reader.with_offset("test structure", |r| {
+            track!(r.read_vec(4))?;
+            track!(r.read_vec(4))
+        });
+        let e = result.expect_err("must fail");
+        assert!(e
+            .to_string()
+            .contains("failed at file offset 0x6 while reading test structure"));
+    }
+
+    #[test]
+    fn big_endian_reads_work() -> TopLevelResult {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0x1234u16.to_be_bytes());
+        bytes.extend_from_slice(&0x1234_5678u32.to_be_bytes());
+        bytes.extend_from_slice(&u64::MAX.to_be_bytes());
+        bytes.extend_from_slice(&i16::MIN.to_be_bytes());
+        bytes.extend_from_slice(&(-2i32).to_be_bytes());
+        bytes.extend_from_slice(&i64::MAX.to_be_bytes());
+
+        let mut reader = &bytes[..];
+        assert_eq!(track!(reader.read_u16_be())?, 0x1234);
+        assert_eq!(track!(reader.read_u32_be())?, 0x1234_5678);
+        assert_eq!(track!(reader.read_u64_be())?, u64::MAX);
+        assert_eq!(track!(reader.read_i16_be())?, i16::MIN);
+        assert_eq!(track!(reader.read_i32_be())?, -2);
+        assert_eq!(track!(reader.read_i64_be())?, i64::MAX);
+        assert_eq!(reader, b"");
+        assert!(reader.read_u16_be().is_err());
+
+        let mut reader = &[0x12, 0x34][..];
+        assert_eq!(track!(ReadExt::read_u16(&mut reader))?, 0x3412);
+        Ok(())
+    }
+
+    #[test]
+    fn read_null_terminated_string_works() -> TopLevelResult {
+        let mut reader = &b"foo\0\0\0\0\0bar\0abcdefg\0"[..];
+        assert_eq!(track!(reader.read_padded_null_terminated_string(8))?, "foo");
+        assert_eq!(track!(reader.read_null_terminated_string())?, "bar");
+        assert_eq!(
+            track!(reader.read_padded_null_terminated_string(8))?,
+            "abcdefg"
+        );
+        assert_eq!(reader, b"");
+
+        let mut reader = &b"\xff\0"[..];
+        assert_eq!(
+            reader.read_null_terminated_string().map_err(|e| *e.kind()),
+            Err(ErrorKind::InvalidFile)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn positioned_reader_works() -> TopLevelResult {
+        let bytes: Arc<[u8]> = Arc::from(&b"0123456789"[..]);
+        let mut reader = PositionedReader::new(bytes.clone());
+        let mut other = reader.clone();
+
+        track!(reader.seek_to(4))?;
+        assert_eq!(track!(reader.read_vec(3))?, b"456");
+        assert_eq!(track!(reader.position())?, 7);
+        assert_eq!(track!(other.read_vec(2))?, b"01");
+
+        assert_eq!(track!(reader.read_all())?, b"789");
+        assert!(reader.read_vec(1).is_err());
+        assert!(reader.seek(SeekFrom::End(0)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn family_members_work() -> TopLevelResult {
+        let members = vec![b"0123".to_vec(), b"4567".to_vec(), b"89".to_vec()];
+        let family = track!(FamilyMembers::new(members, 4))?;
+        let mut reader = PositionedReader::new(family);
+        track!(reader.seek_to(2))?;
+        assert_eq!(track!(reader.read_vec(7))?, b"2345678");
+        assert_eq!(track!(reader.read_all())?, b"9");
+        assert!(FamilyMembers::<Vec<u8>>::new(Vec::new(), 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn file_context_works() -> TopLevelResult {
+        let mut context = FileContext::new(Cursor::new(&b"0123456789"[..]), 4);
+        track!(context.seek_to(2))?;
+        assert_eq!(track!(context.read_vec(2))?, b"67");
+        assert_eq!(track!(context.position())?, 4);
+
+        track!(context.seek(SeekFrom::Current(-3)).map_err(Error::from))?;
+        assert_eq!(track!(context.read_vec(1))?, b"5");
+        assert!(context.seek(SeekFrom::Current(-3)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn read_exact_vec_works() -> TopLevelResult {
+        let mut reader = &b"0123456789"[..];
+        assert_eq!(track!(reader.read_exact_vec(4, 16))?, b"0123");
+        assert_eq!(track!(reader.read_exact_vec(4, 2))?, b"4567");
+
+        // A bogus huge length fails without being allocated up front.
+        let error = reader.read_exact_vec(usize::MAX / 2, 16).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::IoError);
+        Ok(())
+    }
+}
@@ -2,6 +2,11 @@
 //!
 //! Note that this crate is in a very early stage of development and not intended to be used for production purposes.
 //!
+//! # Features
+//!
+//! - `ndarray` (enabled by default): Returns data as `ndarray::ArrayD`.
+//!   If disabled, a minimal `Array` type (a flat `Vec` with a shape) is used instead.
+//!
 //! [HDF5 File Format]: https://support.hdfgroup.org/HDF5/doc/H5.format.html
 #![warn(missing_docs)]
 
@@ -10,14 +15,26 @@ extern crate bitflags;
 #[macro_use]
 extern crate trackable;
 
+pub use self::array::Array;
+pub use self::dataset::{Dataset, ElementsF64, StorageLayout};
 pub use self::error::{Error, ErrorKind};
-pub use self::file::Hdf5File;
-pub use lowlevel::level2::DataObject;
+pub use self::file::{parse_all, Hdf5File, LinkOrder, TopLevelData, UsagePhase, UsageReport};
+pub use self::filter::{FilterDecoder, FilterRegistry};
+pub use self::io::{FamilyMembers, PositionedReader, ReadAt};
+pub use self::options::ReaderOptions;
+pub use lowlevel::level1::ChunkInfo;
+pub use lowlevel::level2::{DataObject, DatatypeClass, FromDataObject, ScalarValue, Table};
+
+pub mod lowlevel;
+pub mod writer;
 
+mod array;
+mod dataset;
 mod error;
 mod file;
+mod filter;
 mod io;
-mod lowlevel;
+mod options;
 
 /// This crate specific `Result` type.
 pub type Result<T> = std::result::Result<T, Error>;
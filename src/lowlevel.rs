@@ -1,3 +1,15 @@
-pub mod level0;
-pub mod level1;
-pub mod level2;
+//! Low-level structures of HDF5 files.
+pub use self::level0::{DriverInfo, Superblock};
+pub use self::level1::{
+    BTreeNode, BTreeV2, FractalHeap, FreeBlock, GlobalHeapCollection, LocalHeap,
+};
+pub use self::level2::{
+    CharacterSet, CompoundDatatype, CompoundMember, DatatypeMessage, Endian, EnumeratedDatatype,
+    FixedPointDatatype, FloatingPointDatatype, GroupInfoMessage, LinkMessage, LinkTarget,
+    ObjectHeader, OpaqueDatatype, ReferenceDatatype, RegionReference, Selection, StringDatatype,
+    VariableLengthDatatype, VirtualMapping,
+};
+
+pub(crate) mod level0;
+pub(crate) mod level1;
+pub(crate) mod level2;
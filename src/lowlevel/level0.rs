@@ -1,28 +1,61 @@
-use crate::io::ReadExt as _;
+use crate::io::{ReadExt as _, SeekExt as _};
 use crate::lowlevel::level1::SymbolTableEntry;
-use crate::{ErrorKind, Result};
+use crate::lowlevel::level2::ObjectHeader;
+use crate::{ErrorKind, ReaderOptions, Result};
 use std;
-use std::io::Read;
+use std::fmt;
+use std::io::{Read, Seek};
 
 const FORMAT_SIGNATURE: [u8; 8] = [137, 72, 68, 70, 13, 10, 26, 10];
-const UNDEFINED_ADDRESS: u64 = std::u64::MAX;
-// const UNLIMITED_SIZE: u64 = std::u64::MAX;
+pub const UNDEFINED_ADDRESS: u64 = u64::MAX;
+/// Dimension size that stands for an unlimited dimension.
+pub const UNLIMITED_SIZE: u64 = u64::MAX;
 
+/// Superblock.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#Superblock
 #[derive(Debug, Clone)]
 pub struct Superblock {
-    pub group_leaf_node_k: u16,     // TODO: NonZeroU16
+    /// Version number of the superblock.
+    pub version: u8,
+
+    /// Half of the maximum number of entries in a leaf node of a group B-tree.
+    pub group_leaf_node_k: u16, // TODO: NonZeroU16
+
+    /// Half of the maximum number of entries in an internal node of a group B-tree.
     pub group_internal_node_k: u16, // TODO: NonZeroU16
+
+    /// File consistency flags.
+    ///
+    /// The HDF5 library sets them while the file is open for writing
+    /// (bit 0 for write access and bit 2 for SWMR writing) and clears them when the file is closed.
+    pub file_consistency_flags: u32,
+
+    /// Absolute file address that the other addresses in the file are relative to.
+    ///
+    /// This is usually `0`, or the size of the user block preceding the superblock.
+    pub base_address: u64,
+
+    /// Address of the first byte past the end of the file data.
     pub end_of_file_address: u64,
+
+    /// Address of the driver information block (`UNDEFINED_ADDRESS` if there is none).
+    ///
+    /// See `Superblock::driver_info`.
+    pub driver_information_block_address: u64,
+
+    /// Symbol table entry of the root group.
     pub root_group_symbol_table_entry: SymbolTableEntry,
 }
 impl Superblock {
+    /// Reads a superblock.
     pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
         let mut signature = [0; 8];
         track!(reader.read_bytes(&mut signature))?;
         track_assert_eq!(signature, FORMAT_SIGNATURE, ErrorKind::InvalidFile);
 
-        let superblock_version = track!(reader.read_u8())?;
-        track_assert_eq!(superblock_version, 0, ErrorKind::Unsupported);
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 0, ErrorKind::Unsupported);
 
         let free_space_storage_version = track!(reader.read_u8())?;
         track_assert_eq!(free_space_storage_version, 0, ErrorKind::Unsupported);
@@ -54,10 +87,8 @@ impl Superblock {
         let group_internal_node_k = track!(reader.read_u16())?;
 
         let file_consistency_flags = track!(reader.read_u32())?;
-        track_assert_eq!(file_consistency_flags, 0, ErrorKind::Unsupported);
 
         let base_address = track!(reader.read_u64())?;
-        track_assert_eq!(base_address, 0, ErrorKind::Unsupported);
 
         let address_of_file_free_space_info = track!(reader.read_u64())?;
         track_assert_eq!(
@@ -69,18 +100,108 @@ impl Superblock {
         let end_of_file_address = track!(reader.read_u64())?;
 
         let driver_information_block_address = track!(reader.read_u64())?;
-        track_assert_eq!(
-            driver_information_block_address,
-            UNDEFINED_ADDRESS,
-            ErrorKind::Unsupported
-        );
 
         let root_group_symbol_table_entry = track!(SymbolTableEntry::from_reader(&mut reader))?;
         Ok(Self {
+            version,
             group_leaf_node_k,
             group_internal_node_k,
+            file_consistency_flags,
+            base_address,
             end_of_file_address,
+            driver_information_block_address,
             root_group_symbol_table_entry,
         })
     }
+
+    /// Reads the driver information block if the superblock refers to one.
+    pub fn driver_info<R: Read + Seek>(&self, mut reader: R) -> Result<Option<DriverInfo>> {
+        if self.driver_information_block_address == UNDEFINED_ADDRESS {
+            return Ok(None);
+        }
+        track!(reader.seek_to(self.driver_information_block_address))?;
+        track!(reader.with_offset("driver information block", |r| {
+            track!(DriverInfo::from_reader(r))
+        }))
+        .map(Some)
+    }
+
+    /// Returns `true` if the file was closed cleanly (i.e., no consistency flags are set).
+    ///
+    /// `false` means that the file may still be open for writing or that the writer crashed,
+    /// so the file may be in an inconsistent state.
+    pub fn is_consistent(&self) -> bool {
+        self.file_consistency_flags == 0
+    }
+
+    /// Reads the object header of the root group.
+    ///
+    /// The header is located via the symbol table entry of the root group
+    /// (only version 0 superblocks, which always have the entry, are supported).
+    pub fn root_object_header<R: Read + Seek>(
+        &self,
+        reader: R,
+        options: &ReaderOptions,
+    ) -> Result<ObjectHeader> {
+        track!(self
+            .root_group_symbol_table_entry
+            .object_header(reader, options))
+    }
+}
+/// Driver information block, which describes how the file was stored by the virtual file driver that wrote it.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#DriverInfo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DriverInfo {
+    /// The file is split into member files of the given size (driver identification `"NCSAfami"`).
+    Family {
+        /// Size of each member file (except the last one).
+        member_size: u64,
+    },
+
+    /// Information of another driver (e.g., `"NCSAmult"` for the multi driver).
+    Other {
+        /// Driver identification.
+        driver_id: String,
+
+        /// Driver-specific information.
+        data: Vec<u8>,
+    },
+}
+impl DriverInfo {
+    /// Reads a driver information block.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 0, ErrorKind::Unsupported);
+        track!(reader.skip(3))?;
+
+        let size = track!(reader.read_u32())?;
+        let mut driver_id = [0; 8];
+        track!(reader.read_bytes(&mut driver_id))?;
+        if &driver_id == b"NCSAfami" {
+            track_assert_eq!(size, 8, ErrorKind::InvalidFile);
+            let member_size = track!(reader.read_u64())?;
+            track_assert_ne!(member_size, 0, ErrorKind::InvalidFile);
+            return Ok(DriverInfo::Family { member_size });
+        }
+        let data = track!(reader.read_vec(size as usize))?;
+        Ok(DriverInfo::Other {
+            driver_id: String::from_utf8_lossy(&driver_id).into_owned(),
+            data,
+        })
+    }
+}
+
+impl fmt::Display for Superblock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "superblock (version {}): root object header at {:#x}, end of file at {:#x}, group K (leaf={}, internal={})",
+            self.version,
+            self.root_group_symbol_table_entry.object_header_address(),
+            self.end_of_file_address,
+            self.group_leaf_node_k,
+            self.group_internal_node_k
+        )
+    }
 }
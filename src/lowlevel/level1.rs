@@ -1,11 +1,19 @@
-use crate::io::{ReadExt as _, SeekExt as _};
+use crate::io::{ReadExt as _, RecordingReader, SeekExt as _};
+use crate::lowlevel::level0::UNDEFINED_ADDRESS;
 use crate::lowlevel::level2::{DataObject, ObjectHeader};
-use crate::{Error, ErrorKind, Result};
+use crate::{Error, ErrorKind, ReaderOptions, Result};
 use itertools::Either;
 use std;
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::io::{Read, Seek};
 
+/// The offset used by the HDF5 library to mark the end of a local heap free list.
+const LOCAL_HEAP_FREE_NULL: u64 = 1;
+
+/// Maximum depth of version 2 B-trees.
+const MAX_B_TREE_V2_DEPTH: u16 = 64;
+
 /// https://support.hdfgroup.org/HDF5/doc/H5.format.html#LocalHeap
 #[derive(Debug, Clone)]
 pub struct LocalHeap {
@@ -14,11 +22,85 @@ pub struct LocalHeap {
     data_segment_address: u64,
 }
 impl LocalHeap {
+    /// Returns the total size of the data segment of the heap in bytes.
+    pub fn data_segment_size(&self) -> u64 {
+        self.data_segment_size
+    }
+
+    /// Returns the address of the data segment of the heap.
+    pub fn data_segment_address(&self) -> u64 {
+        self.data_segment_address
+    }
+
+    /// Returns the offset of the first free block within the data segment.
+    ///
+    /// `None` means that the heap has no free blocks.
+    pub fn free_list_head_offset(&self) -> Option<u64> {
+        if self.free_list_head_offset == LOCAL_HEAP_FREE_NULL
+            || self.free_list_head_offset == UNDEFINED_ADDRESS
+        {
+            None
+        } else {
+            Some(self.free_list_head_offset)
+        }
+    }
+
+    /// Reads the free list of the heap.
+    pub fn free_blocks<R: Read + Seek>(&self, mut reader: R) -> Result<Vec<FreeBlock>> {
+        let mut blocks = Vec::new();
+        let mut next = self.free_list_head_offset();
+        while let Some(offset) = next {
+            // Each free block occupies at least 16 bytes, so a longer list must contain a cycle.
+            track_assert!(
+                (blocks.len() as u64) < self.data_segment_size / 16,
+                ErrorKind::InvalidFile,
+                "Too many local heap free blocks"
+            );
+            track_assert!(
+                offset < self.data_segment_size,
+                ErrorKind::InvalidFile;
+                offset,
+                self.data_segment_size
+            );
+            track!(reader.seek_to(self.data_segment_address.saturating_add(offset)))?;
+
+            let next_offset = track!(reader.read_u64())?;
+            let size = track!(reader.read_u64())?;
+            track_assert!(
+                offset
+                    .checked_add(size)
+                    .map_or(false, |end| end <= self.data_segment_size),
+                ErrorKind::InvalidFile;
+                offset,
+                size,
+                self.data_segment_size
+            );
+            blocks.push(FreeBlock { offset, size });
+
+            next = if next_offset == LOCAL_HEAP_FREE_NULL || next_offset == UNDEFINED_ADDRESS {
+                None
+            } else {
+                Some(next_offset)
+            };
+        }
+        Ok(blocks)
+    }
+
+    /// Reads the null-terminated string located at `offset` in the data segment.
     pub fn read_string<R: Read + Seek>(&self, offset: u64, mut reader: R) -> Result<String> {
-        track!(reader.seek_to(self.data_segment_address + offset))?;
-        track!(reader.read_null_terminated_string())
+        track_assert!(
+            offset < self.data_segment_size,
+            ErrorKind::InvalidFile;
+            offset,
+            self.data_segment_size
+        );
+        track!(reader.seek_to(self.data_segment_address.saturating_add(offset)))?;
+        reader.with_offset("local heap string", |r| {
+            track!(r.read_null_terminated_string())
+        })
     }
 
+    /// Reads a local heap header from the given reader.
     pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
         let mut signature = [0; 4];
         track!(reader.read_bytes(&mut signature))?;
@@ -39,6 +121,72 @@ impl LocalHeap {
     }
 }
 
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#GlobalHeap
+#[derive(Debug, Clone)]
+pub struct GlobalHeapCollection {
+    objects: Vec<(u16, Vec<u8>)>,
+}
+impl GlobalHeapCollection {
+    /// Returns the data of the object with the given index, if any.
+    pub fn object(&self, index: u32) -> Option<&[u8]> {
+        self.objects
+            .iter()
+            .find(|&&(i, _)| u32::from(i) == index)
+            .map(|(_, data)| &data[..])
+    }
+
+    /// Reads a global heap collection from the given reader.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let mut signature = [0; 4];
+        track!(reader.read_bytes(&mut signature))?;
+        track_assert_eq!(&signature, b"GCOL", ErrorKind::InvalidFile);
+
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 1, ErrorKind::Unsupported);
+        track!(reader.skip(3))?;
+
+        // The collection size includes the 16-byte header above.
+        let collection_size = track!(reader.read_u64())?;
+        track_assert!(collection_size >= 16, ErrorKind::InvalidFile; collection_size);
+        let mut rest = collection_size - 16;
+
+        let mut objects = Vec::new();
+        while rest >= 16 {
+            let index = track!(reader.read_u16())?;
+            let _reference_count = track!(reader.read_u16())?;
+            track!(reader.skip(4))?;
+            let size = track!(reader.read_u64())?;
+            rest -= 16;
+            if index == 0 {
+                // The free space object is always the last one.
+                break;
+            }
+
+            let padded_size = size.checked_add(7).map(|n| n / 8 * 8);
+            let padded_size = track_assert_some!(
+                padded_size.filter(|&n| n <= rest),
+                ErrorKind::InvalidFile;
+                index, size, rest
+            );
+            let data = track!(reader.read_vec(size as usize))?;
+            track!(reader.skip((padded_size - size) as usize))?;
+            rest -= padded_size;
+            objects.push((index, data));
+        }
+        Ok(Self { objects })
+    }
+}
+
+/// A free block in the data segment of a local heap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FreeBlock {
+    /// Offset of the block within the data segment.
+    pub offset: u64,
+
+    /// Size of the block in bytes.
+    pub size: u64,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum NodeType {
     Group = 0,
@@ -57,18 +205,81 @@ impl TryFrom<u8> for NodeType {
 }
 
 /// https://support.hdfgroup.org/HDF5/doc/H5.format.html#Btrees
+///
+/// A v1 B-tree node that indexes the members of a group (node type 0).
 #[derive(Debug, Clone)]
 pub enum BTreeNode {
+    /// Group node.
     Group {
+        /// Level of the node (`0` for leaves, whose children are symbol table nodes).
         node_level: u8,
+
+        /// Offsets of the names in the local heap of the group (one more than the children).
         keys: Vec<u64>,
+
+        /// Address of the left sibling node.
         left_sibling_address: u64,
+
+        /// Address of the right sibling node.
         right_sibling_address: u64,
+
+        /// Addresses of the child nodes.
         children: Vec<u64>,
     },
 }
 impl BTreeNode {
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+    /// Returns the level of the node (`0` for leaves).
+    pub fn node_level(&self) -> u8 {
+        let BTreeNode::Group { node_level, .. } = self;
+        *node_level
+    }
+
+    /// Returns the keys of the node (i.e., offsets of names in the local heap of the group).
+    ///
+    /// There is always one more key than children.
+    pub fn key_offsets(&self) -> &[u64] {
+        let BTreeNode::Group { keys, .. } = self;
+        keys
+    }
+
+    /// Returns the addresses of the child nodes.
+    ///
+    /// The children of a leaf are symbol table nodes, otherwise they are B-tree nodes one level below.
+    pub fn child_addresses(&self) -> &[u64] {
+        let BTreeNode::Group { children, .. } = self;
+        children
+    }
+
+    /// Returns pairs of a child address and the key that bounds it from above.
+    ///
+    /// The names in the subtree of each child are greater than the previous key and
+    /// less than or equal to the paired key (the first key is always the empty string).
+    pub fn entries(&self) -> Vec<(u64, u64)> {
+        let BTreeNode::Group { keys, children, .. } = self;
+        keys.iter()
+            .skip(1)
+            .cloned()
+            .zip(children.iter().cloned())
+            .collect()
+    }
+
+    /// Returns the addresses of the left and right sibling nodes.
+    ///
+    /// `None` means that the node has no sibling on that side.
+    pub fn sibling_addresses(&self) -> (Option<u64>, Option<u64>) {
+        let BTreeNode::Group {
+            left_sibling_address,
+            right_sibling_address,
+            ..
+        } = self;
+        let defined = |a: u64| Some(a).filter(|&a| a != UNDEFINED_ADDRESS);
+        (
+            defined(*left_sibling_address),
+            defined(*right_sibling_address),
+        )
+    }
+
+    pub(crate) fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
         let mut signature = [0; 4];
         track!(reader.read_bytes(&mut signature))?;
         track_assert_eq!(&signature, b"TREE", ErrorKind::InvalidFile);
@@ -99,7 +310,7 @@ impl BTreeNode {
         })
     }
 
-    pub fn children<'a, R: 'a + Read + Seek>(
+    pub(crate) fn children<'a, R: 'a + Read + Seek>(
         &'a self,
         mut reader: R,
     ) -> impl 'a + Iterator<Item = Result<BTreeNodeChild>> {
@@ -111,17 +322,32 @@ impl BTreeNode {
         if *node_level == 0 {
             Either::Left(children.iter().map(move |&addr| {
                 track!(reader.seek_to(addr))?;
-                track!(SymbolTableNode::from_reader(&mut reader)).map(BTreeNodeChild::GroupLeaf)
+                reader
+                    .with_offset("symbol table node", |r| {
+                        track!(SymbolTableNode::from_reader(r))
+                    })
+                    .map(BTreeNodeChild::GroupLeaf)
             }))
         } else {
+            let parent_level = *node_level;
             Either::Right(children.iter().map(move |&addr| {
                 track!(reader.seek_to(addr))?;
-                track!(Self::from_reader(&mut reader)).map(BTreeNodeChild::Intermediate)
+                let child =
+                    track!(reader.with_offset("B-tree node", |r| track!(Self::from_reader(r))))?;
+
+                // Levels must decrease towards the leaves, otherwise the tree would contain a cycle.
+                let BTreeNode::Group { node_level, .. } = child;
+                track_assert_eq!(
+                    parent_level.checked_sub(1),
+                    Some(node_level),
+                    ErrorKind::InvalidFile
+                );
+                Ok(BTreeNodeChild::Intermediate(child))
             }))
         }
     }
 
-    pub fn keys<'a, R: 'a + Read + Seek>(
+    pub(crate) fn keys<'a, R: 'a + Read + Seek>(
         &'a self,
         heap: LocalHeap,
         mut reader: R,
@@ -132,6 +358,126 @@ impl BTreeNode {
     }
 }
 
+/// Key of a v1 B-tree node for raw data chunks (node type 1).
+#[derive(Debug, Clone)]
+pub struct ChunkKey {
+    pub chunk_size: u32,
+    pub filter_mask: u32,
+    pub offsets: Vec<u64>,
+}
+impl ChunkKey {
+    fn from_reader<R: Read>(mut reader: R, dimensionality: usize) -> Result<Self> {
+        let chunk_size = track!(reader.read_u32())?;
+        let filter_mask = track!(reader.read_u32())?;
+        let mut offsets = (0..dimensionality)
+            .map(|_| track!(reader.read_u64()))
+            .collect::<Result<Vec<_>>>()?;
+
+        // The last offset corresponds to the dataset element size and is always zero.
+        offsets.pop();
+        Ok(Self {
+            chunk_size,
+            filter_mask,
+            offsets,
+        })
+    }
+}
+
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#Btrees
+///
+/// A v1 B-tree node that indexes the raw data chunks of a chunked dataset (node type 1).
+#[derive(Debug, Clone)]
+pub struct ChunkBTreeNode {
+    pub node_level: u8,
+    pub keys: Vec<ChunkKey>,
+    pub children: Vec<u64>,
+
+    // Dimensionality of the dataset plus one.
+    dimensionality: usize,
+}
+impl ChunkBTreeNode {
+    pub fn from_reader<R: Read>(mut reader: R, dimensionality: usize) -> Result<Self> {
+        track!(reader.assert_signature(b"TREE"))?;
+
+        let node_type = track!(reader.read_u8().and_then(NodeType::try_from))?;
+        track_assert_eq!(node_type, NodeType::RawDataChunk, ErrorKind::InvalidFile);
+
+        let node_level = track!(reader.read_u8())?;
+        let entries_used = track!(reader.read_u16())?;
+
+        // Chunks are collected from the root, so the sibling addresses are not needed.
+        let _left_sibling_address = track!(reader.read_u64())?;
+        let _right_sibling_address = track!(reader.read_u64())?;
+
+        let mut keys = Vec::with_capacity(entries_used as usize + 1);
+        let mut children = Vec::with_capacity(entries_used as usize);
+        for _ in 0..entries_used {
+            keys.push(track!(ChunkKey::from_reader(&mut reader, dimensionality))?);
+            children.push(track!(reader.read_u64())?);
+        }
+        keys.push(track!(ChunkKey::from_reader(&mut reader, dimensionality))?);
+
+        Ok(Self {
+            node_level,
+            keys,
+            children,
+            dimensionality,
+        })
+    }
+
+    /// Collects the chunks indexed by the subtree rooted at this node.
+    pub fn chunks<R: Read + Seek>(&self, mut reader: R) -> Result<Vec<ChunkInfo>> {
+        let mut chunks = Vec::new();
+        track!(self.collect_chunks(&mut reader, &mut chunks))?;
+        Ok(chunks)
+    }
+
+    fn collect_chunks<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        chunks: &mut Vec<ChunkInfo>,
+    ) -> Result<()> {
+        for (key, &address) in self.keys.iter().zip(self.children.iter()) {
+            if self.node_level == 0 {
+                chunks.push(ChunkInfo {
+                    offset: key.offsets.clone(),
+                    address,
+                    size: u64::from(key.chunk_size),
+                    filter_mask: key.filter_mask,
+                });
+            } else {
+                track!(reader.seek_to(address))?;
+                let child = track!(reader.with_offset("chunk B-tree node", |r| {
+                    track!(Self::from_reader(r, self.dimensionality))
+                }))?;
+                track_assert_eq!(
+                    self.node_level.checked_sub(1),
+                    Some(child.node_level),
+                    ErrorKind::InvalidFile
+                );
+                track!(child.collect_chunks(reader, chunks))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Location of a chunk of a chunked dataset.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChunkInfo {
+    /// Logical offset of the first element of the chunk in the dataset.
+    pub offset: Vec<u64>,
+
+    /// Address of the chunk in the file.
+    pub address: u64,
+
+    /// Size of the chunk stored in the file in bytes (after filters have been applied).
+    pub size: u64,
+
+    /// Bit mask indicating which filters were skipped for the chunk.
+    pub filter_mask: u32,
+}
+
 /// https://support.hdfgroup.org/HDF5/doc/H5.format.html#SymbolTable
 #[derive(Debug, Clone)]
 pub struct SymbolTableNode {
@@ -166,42 +512,132 @@ pub struct SymbolTableEntry {
     scratch_pad: ScratchPad,
 }
 impl SymbolTableEntry {
-    pub fn get_data_object<R: Read + Seek>(&self, mut reader: R) -> Result<DataObject> {
-        let header = track!(self.object_header(&mut reader))?;
-        track!(header.get_data_object(&mut reader))
+    /// Makes an entry that refers to the object header at the given address without cached information.
+    pub fn from_object_header_address(object_header_address: u64) -> Self {
+        Self {
+            link_name_offset: 0,
+            object_header_address,
+            scratch_pad: ScratchPad::None,
+        }
+    }
+
+    /// Returns `true` if the entry caches neither a symbol table nor a soft link.
+    pub fn has_empty_scratch_pad(&self) -> bool {
+        matches!(self.scratch_pad, ScratchPad::None)
+    }
+
+    /// Returns the cache type of the entry.
+    ///
+    /// `1` means the scratch pad holds the B-tree and local heap addresses of a group,
+    /// and `2` means it holds the link value offset of a soft link.
+    pub fn cache_type(&self) -> u32 {
+        match self.scratch_pad {
+            ScratchPad::None => 0,
+            ScratchPad::ObjectHeader { .. } => 1,
+            ScratchPad::SymbolicLink { .. } => 2,
+        }
+    }
+
+    /// Returns the address of the group B-tree cached in the scratch pad.
+    pub fn cached_b_tree_address(&self) -> Option<u64> {
+        if let ScratchPad::ObjectHeader { btree_address, .. } = self.scratch_pad {
+            Some(btree_address)
+        } else {
+            None
+        }
     }
 
+    /// Returns the address of the group local heap cached in the scratch pad.
+    pub fn cached_local_heap_address(&self) -> Option<u64> {
+        if let ScratchPad::ObjectHeader {
+            name_heap_address, ..
+        } = self.scratch_pad
+        {
+            Some(name_heap_address)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_data_object<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        options: &ReaderOptions,
+    ) -> Result<DataObject> {
+        let header = track!(self.object_header(&mut reader, options))?;
+        track!(header.get_data_object(&mut reader, options))
+    }
+
+    /// Reads the link name of the entry from `heap` (or the local heap cached in the scratch pad if `heap` is `None`).
+    ///
+    /// `Ok(None)` is returned for entries without a name, i.e., those whose name is the empty string
+    /// (e.g., the root group) or that have no heap to look the name up in.
     pub fn link_name<R: Read + Seek>(
         &self,
         mut reader: R,
         heap: Option<&LocalHeap>,
     ) -> Result<Option<String>> {
-        let mut addr = if let Some(heap) = heap {
+        let addr = if let Some(heap) = heap {
             heap.data_segment_address
-        } else if let ScratchPad::ObjectHeader {
-            name_heap_address, ..
-        } = self.scratch_pad
-        {
+        } else if let Some(name_heap_address) = self.cached_local_heap_address() {
             name_heap_address
         } else {
             return Ok(None);
         };
 
-        addr += self.link_name_offset;
+        let addr = track_assert_some!(
+            addr.checked_add(self.link_name_offset),
+            ErrorKind::InvalidFile;
+            addr,
+            self.link_name_offset
+        );
         track!(reader.seek_to(addr))?;
 
-        track!(reader.read_null_terminated_string()).map(Some)
+        let name =
+            track!(reader.with_offset("link name", |r| track!(r.read_null_terminated_string())))?;
+        Ok(Some(name).filter(|name| !name.is_empty()))
+    }
+
+    /// Reads the value (i.e., the target path) of the soft link represented by this entry.
+    ///
+    /// `heap` must be the local heap of the group containing the entry.
+    /// If the entry isn't a soft link, `Ok(None)` will be returned.
+    pub fn soft_link_value<R: Read + Seek>(
+        &self,
+        reader: R,
+        heap: &LocalHeap,
+    ) -> Result<Option<String>> {
+        if let ScratchPad::SymbolicLink { link_value_offset } = self.scratch_pad {
+            track!(heap.read_string(u64::from(link_value_offset), reader)).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn object_header_address(&self) -> u64 {
+        self.object_header_address
     }
 
-    pub fn object_header<R: Read + Seek>(&self, mut reader: R) -> Result<ObjectHeader> {
+    pub fn object_header<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        options: &ReaderOptions,
+    ) -> Result<ObjectHeader> {
         track!(reader.seek_to(self.object_header_address))?;
-        track!(ObjectHeader::from_reader(reader))
+        let mut header = track!(reader.with_offset("object header", |r| {
+            track!(ObjectHeader::from_reader(r, options))
+        }))?;
+        track!(header.resolve_shared_messages(&mut reader, options))?;
+        track!(header.load_dense_attributes(&mut reader, options))?;
+        Ok(header)
     }
 
     pub fn b_tree_node<R: Read + Seek>(&self, mut reader: R) -> Result<Option<BTreeNode>> {
-        if let ScratchPad::ObjectHeader { btree_address, .. } = self.scratch_pad {
+        if let Some(btree_address) = self.cached_b_tree_address() {
             track!(reader.seek_to(btree_address))?;
-            track!(BTreeNode::from_reader(reader)).map(Some)
+            reader
+                .with_offset("B-tree node", |r| track!(BTreeNode::from_reader(r)))
+                .map(Some)
         } else {
             // TODO: find header messages
             Ok(None)
@@ -209,12 +645,11 @@ impl SymbolTableEntry {
     }
 
     pub fn local_heap<R: Read + Seek>(&self, mut reader: R) -> Result<Option<LocalHeap>> {
-        if let ScratchPad::ObjectHeader {
-            name_heap_address, ..
-        } = self.scratch_pad
-        {
+        if let Some(name_heap_address) = self.cached_local_heap_address() {
             track!(reader.seek_to(name_heap_address))?;
-            track!(LocalHeap::from_reader(reader)).map(Some)
+            reader
+                .with_offset("local heap", |r| track!(LocalHeap::from_reader(r)))
+                .map(Some)
         } else {
             // TODO: find header messages
             Ok(None)
@@ -271,3 +706,975 @@ impl ScratchPad {
         }
     }
 }
+
+/// Fractal heap header.
+///
+/// Only managed objects stored in unfiltered blocks and tiny objects can be read.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#FractalHeap
+#[derive(Debug, Clone)]
+pub struct FractalHeap {
+    heap_id_len: u16,
+    io_filters_len: u16,
+    flags: u8,
+    max_managed_object_size: u32,
+    table_width: u16,
+    starting_block_size: u64,
+    max_direct_block_size: u64,
+    max_heap_size: u16,
+    root_block_address: u64,
+    root_block_rows: u16,
+}
+impl FractalHeap {
+    /// Returns the length of the IDs of the objects in the heap in bytes.
+    pub fn heap_id_len(&self) -> usize {
+        usize::from(self.heap_id_len)
+    }
+
+    /// Reads the object identified by `heap_id`.
+    pub fn get_object<R: Read + Seek>(&self, mut reader: R, heap_id: &[u8]) -> Result<Vec<u8>> {
+        track_assert!(!heap_id.is_empty(), ErrorKind::InvalidFile);
+        let flags = heap_id[0];
+        track_assert_eq!(flags >> 6, 0, ErrorKind::Unsupported, "Heap ID version");
+        match (flags >> 4) & 0b11 {
+            0 => {
+                let mut id = &heap_id[1..];
+                let offset = track!(id.read_uint(self.heap_offset_size()))?;
+                let length = track!(id.read_uint(self.heap_length_size()))?;
+                track!(self.read_managed_object(&mut reader, offset, length))
+            }
+            1 => track_panic!(ErrorKind::Unsupported, "Huge fractal heap objects"),
+            2 => {
+                // Tiny objects are stored in the heap IDs themselves.
+                let (length, data) = if heap_id.len() <= 18 {
+                    (usize::from(flags & 0x0F) + 1, &heap_id[1..])
+                } else {
+                    track_assert!(heap_id.len() > 2, ErrorKind::InvalidFile);
+                    let length = (usize::from(flags & 0x0F) << 8 | usize::from(heap_id[1])) + 1;
+                    (length, &heap_id[2..])
+                };
+                track_assert!(length <= data.len(), ErrorKind::InvalidFile; length, data.len());
+                Ok(data[..length].to_vec())
+            }
+            kind => track_panic!(ErrorKind::InvalidFile, "Unknown heap ID type: {}", kind),
+        }
+    }
+
+    fn read_managed_object<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>> {
+        track_assert!(
+            self.io_filters_len == 0,
+            ErrorKind::Unsupported,
+            "Filtered fractal heaps"
+        );
+        track_assert!(
+            length <= u64::from(self.max_managed_object_size),
+            ErrorKind::InvalidFile;
+            length,
+            self.max_managed_object_size
+        );
+
+        let (block_address, block_offset, block_size) = if self.root_block_rows == 0 {
+            (self.root_block_address, 0, self.starting_block_size)
+        } else {
+            track!(self.find_direct_block(reader, offset))?
+        };
+        let relative_offset = track_assert_some!(
+            offset.checked_sub(block_offset),
+            ErrorKind::InvalidFile;
+            offset,
+            block_offset
+        );
+        track_assert!(
+            relative_offset >= self.direct_block_header_size()
+                && relative_offset.saturating_add(length) <= block_size,
+            ErrorKind::InvalidFile;
+            offset,
+            length,
+            block_offset,
+            block_size
+        );
+        let object_address = track_assert_some!(
+            block_address.checked_add(relative_offset),
+            ErrorKind::InvalidFile;
+            block_address,
+            relative_offset
+        );
+
+        track!(reader.seek_to(block_address))?;
+        track!(reader.with_offset("fractal heap direct block", |r| {
+            track!(r.assert_signature(b"FHDB"))?;
+            let version = track!(r.read_u8())?;
+            track_assert_eq!(version, 0, ErrorKind::Unsupported);
+            let _heap_header_address = track!(r.read_u64())?;
+            let offset_in_heap = track!(r.read_uint(self.heap_offset_size()))?;
+            track_assert_eq!(offset_in_heap, block_offset, ErrorKind::InvalidFile);
+            Ok(())
+        }))?;
+        track!(reader.seek_to(object_address))?;
+        reader.with_offset("fractal heap object", |r| {
+            track!(r.read_vec(length as usize))
+        })
+    }
+
+    /// Walks down the indirect blocks, and returns the address, the heap offset and the size of
+    /// the direct block containing the given heap offset.
+    fn find_direct_block<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        offset: u64,
+    ) -> Result<(u64, u64, u64)> {
+        let width = u64::from(self.table_width);
+        let max_direct_rows = self.max_direct_rows();
+
+        let mut address = self.root_block_address;
+        let mut rows = u32::from(self.root_block_rows);
+        let mut block_offset = 0;
+        loop {
+            track_assert_ne!(address, UNDEFINED_ADDRESS, ErrorKind::InvalidFile);
+            track!(reader.seek_to(address))?;
+            track!(reader.with_offset("fractal heap indirect block", |r| {
+                track!(r.assert_signature(b"FHIB"))?;
+                let version = track!(r.read_u8())?;
+                track_assert_eq!(version, 0, ErrorKind::Unsupported);
+                let _heap_header_address = track!(r.read_u64())?;
+                let offset_in_heap = track!(r.read_uint(self.heap_offset_size()))?;
+                track_assert_eq!(offset_in_heap, block_offset, ErrorKind::InvalidFile);
+                Ok(())
+            }))?;
+
+            let mut start = block_offset;
+            let mut found = None;
+            'rows: for row in 0..rows {
+                let size = track!(self.row_block_size(row))?;
+                for column in 0..width {
+                    let end = track_assert_some!(start.checked_add(size), ErrorKind::InvalidFile);
+                    if offset < end {
+                        found = Some((u64::from(row) * width + column, row, start, size));
+                        break 'rows;
+                    }
+                    start = end;
+                }
+            }
+            let (index, row, start, size) = track_assert_some!(
+                found,
+                ErrorKind::InvalidFile,
+                "Heap offset {} is out of the indirect block at {:#x}",
+                offset,
+                address
+            );
+
+            // Child block entries are addresses, since filtered heaps are not supported.
+            let entry_address = index
+                .checked_mul(8)
+                .and_then(|n| n.checked_add(self.indirect_block_header_size()))
+                .and_then(|n| n.checked_add(address));
+            let entry_address = track_assert_some!(entry_address, ErrorKind::InvalidFile; address);
+            track!(reader.seek_to(entry_address))?;
+            let child = track!(reader.read_u64())?;
+            track_assert_ne!(child, UNDEFINED_ADDRESS, ErrorKind::InvalidFile; offset);
+            if row < max_direct_rows {
+                return Ok((child, start, size));
+            }
+
+            let child_rows = track!(self.indirect_block_rows(size))?;
+            // The blocks get smaller towards the leaves, so the loop always terminates.
+            track_assert!(child_rows < rows, ErrorKind::InvalidFile; child_rows, rows);
+            address = child;
+            rows = child_rows;
+            block_offset = start;
+        }
+    }
+
+    /// Returns the size of the blocks in the given row of the doubling table.
+    fn row_block_size(&self, row: u32) -> Result<u64> {
+        if row == 0 {
+            Ok(self.starting_block_size)
+        } else {
+            let shift = row - 1;
+            track_assert!(
+                shift <= self.starting_block_size.leading_zeros(),
+                ErrorKind::InvalidFile;
+                row
+            );
+            Ok(self.starting_block_size << shift)
+        }
+    }
+
+    /// Returns the number of rows that consist of direct blocks.
+    fn max_direct_rows(&self) -> u32 {
+        self.max_direct_block_size.trailing_zeros() - self.starting_block_size.trailing_zeros() + 2
+    }
+
+    /// Returns the number of rows of an indirect block of the given size.
+    fn indirect_block_rows(&self, size: u64) -> Result<u32> {
+        let first_row_size = self
+            .starting_block_size
+            .checked_mul(u64::from(self.table_width));
+        let first_row_size = track_assert_some!(first_row_size, ErrorKind::InvalidFile);
+        track_assert!(size >= first_row_size, ErrorKind::InvalidFile; size);
+        Ok(size.trailing_zeros() - first_row_size.trailing_zeros() + 1)
+    }
+
+    fn heap_offset_size(&self) -> usize {
+        (usize::from(self.max_heap_size) + 7) / 8
+    }
+
+    fn heap_length_size(&self) -> usize {
+        let direct_block_size = (self.max_direct_block_size.trailing_zeros() as usize + 7) / 8;
+        let max_object_size = (31 - self.max_managed_object_size.leading_zeros()) as usize / 8 + 1;
+        std::cmp::min(direct_block_size, max_object_size)
+    }
+
+    fn direct_block_header_size(&self) -> u64 {
+        let checksum_size = if self.flags & 0b0000_0010 != 0 { 4 } else { 0 };
+        (4 + 1 + 8 + self.heap_offset_size() + checksum_size) as u64
+    }
+
+    fn indirect_block_header_size(&self) -> u64 {
+        (4 + 1 + 8 + self.heap_offset_size()) as u64
+    }
+
+    /// Reads a fractal heap header.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        track!(reader.assert_signature(b"FRHP"))?;
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 0, ErrorKind::Unsupported);
+
+        let heap_id_len = track!(reader.read_u16())?;
+        let io_filters_len = track!(reader.read_u16())?;
+        let flags = track!(reader.read_u8())?;
+        let max_managed_object_size = track!(reader.read_u32())?;
+        let _next_huge_object_id = track!(reader.read_u64())?;
+        let _huge_object_b_tree_address = track!(reader.read_u64())?;
+        let _free_space = track!(reader.read_u64())?;
+        let _free_space_manager_address = track!(reader.read_u64())?;
+        let _managed_space = track!(reader.read_u64())?;
+        let _allocated_managed_space = track!(reader.read_u64())?;
+        let _direct_block_allocation_iterator_offset = track!(reader.read_u64())?;
+        let _managed_object_count = track!(reader.read_u64())?;
+        let _huge_object_size = track!(reader.read_u64())?;
+        let _huge_object_count = track!(reader.read_u64())?;
+        let _tiny_object_size = track!(reader.read_u64())?;
+        let _tiny_object_count = track!(reader.read_u64())?;
+
+        let table_width = track!(reader.read_u16())?;
+        let starting_block_size = track!(reader.read_u64())?;
+        let max_direct_block_size = track!(reader.read_u64())?;
+        let max_heap_size = track!(reader.read_u16())?;
+        let _starting_root_rows = track!(reader.read_u16())?;
+        let root_block_address = track!(reader.read_u64())?;
+        let root_block_rows = track!(reader.read_u16())?;
+
+        track_assert!(
+            table_width.is_power_of_two()
+                && starting_block_size.is_power_of_two()
+                && max_direct_block_size.is_power_of_two()
+                && starting_block_size <= max_direct_block_size
+                && max_managed_object_size != 0
+                && (1..=64).contains(&max_heap_size),
+            ErrorKind::InvalidFile;
+            table_width,
+            starting_block_size,
+            max_direct_block_size,
+            max_managed_object_size,
+            max_heap_size
+        );
+        Ok(Self {
+            heap_id_len,
+            io_filters_len,
+            flags,
+            max_managed_object_size,
+            table_width,
+            starting_block_size,
+            max_direct_block_size,
+            max_heap_size,
+            root_block_address,
+            root_block_rows,
+        })
+    }
+}
+
+/// Version 2 B-tree header.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#V2Btrees
+#[derive(Debug, Clone)]
+pub struct BTreeV2 {
+    record_type: u8,
+    node_size: u32,
+    record_size: u16,
+    depth: u16,
+    root_node_address: u64,
+    root_record_count: u16,
+    total_record_count: u64,
+    verify_checksums: bool,
+}
+impl BTreeV2 {
+    /// Returns the type of the records stored in the B-tree.
+    ///
+    /// For example, `5` is the name index of links and `6` is their creation order index.
+    pub fn record_type(&self) -> u8 {
+        self.record_type
+    }
+
+    /// Returns the size of each record in bytes.
+    pub fn record_size(&self) -> usize {
+        usize::from(self.record_size)
+    }
+
+    /// Returns the total number of records in the B-tree.
+    pub fn record_count(&self) -> u64 {
+        self.total_record_count
+    }
+
+    /// Reads all the records of the B-tree in their index order.
+    ///
+    /// The records are returned as raw bytes, since their layout depends on the record type.
+    pub fn records<R: Read + Seek>(&self, mut reader: R) -> Result<Vec<Vec<u8>>> {
+        let mut records = Vec::new();
+        if self.root_node_address == UNDEFINED_ADDRESS {
+            return Ok(records);
+        }
+
+        let (record_count_size, total_record_count_sizes) = self.field_sizes();
+        track!(self.collect_records(
+            &mut reader,
+            self.root_node_address,
+            self.depth,
+            u64::from(self.root_record_count),
+            (record_count_size, &total_record_count_sizes),
+            &mut records
+        ))?;
+        track_assert_eq!(
+            records.len() as u64,
+            self.total_record_count,
+            ErrorKind::InvalidFile
+        );
+        Ok(records)
+    }
+
+    /// Reads the records for which `compare` returns `Ordering::Equal`.
+    ///
+    /// `compare` tells how a record is ordered relative to the searched key in the index order of the B-tree,
+    /// so only the nodes which may contain matching records are read.
+    pub fn find_records<R, F>(&self, mut reader: R, compare: F) -> Result<Vec<Vec<u8>>>
+    where
+        R: Read + Seek,
+        F: Fn(&[u8]) -> Ordering,
+    {
+        let mut records = Vec::new();
+        if self.root_node_address == UNDEFINED_ADDRESS {
+            return Ok(records);
+        }
+
+        let (record_count_size, total_record_count_sizes) = self.field_sizes();
+        track!(self.search_node(
+            &mut reader,
+            (self.root_node_address, u64::from(self.root_record_count)),
+            self.depth,
+            (record_count_size, &total_record_count_sizes),
+            &compare,
+            &mut records
+        ))?;
+        Ok(records)
+    }
+
+    /// Returns the sizes of the fields holding the number of records in child nodes.
+    ///
+    /// The second element is indexed by depth.
+    fn field_sizes(&self) -> (usize, Vec<usize>) {
+        let leaf_records =
+            u64::from(self.node_size.saturating_sub(10) / u32::from(self.record_size));
+        let record_count_size = encoded_size(leaf_records);
+        let mut total_record_count_sizes = vec![0];
+        let mut cumulative_records = leaf_records;
+        for depth in 1..self.depth {
+            let pointer_size = 8 + record_count_size + total_record_count_sizes[depth as usize - 1];
+            let node_records = u64::from(self.node_size).saturating_sub(10 + pointer_size as u64)
+                / (u64::from(self.record_size) + pointer_size as u64);
+            cumulative_records = (node_records + 1)
+                .saturating_mul(cumulative_records)
+                .saturating_add(node_records);
+            total_record_count_sizes.push(encoded_size(cumulative_records));
+        }
+        (record_count_size, total_record_count_sizes)
+    }
+
+    fn collect_records<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        address: u64,
+        depth: u16,
+        record_count: u64,
+        field_sizes: (usize, &[usize]),
+        records: &mut Vec<Vec<u8>>,
+    ) -> Result<()> {
+        track_assert!(
+            (records.len() as u64).saturating_add(record_count) <= self.total_record_count,
+            ErrorKind::InvalidFile;
+            record_count,
+            self.total_record_count
+        );
+        let node = track!(self.read_node(reader, address, depth, record_count, field_sizes))?;
+        if depth == 0 {
+            records.extend(node.records);
+            return Ok(());
+        }
+        let mut node_records = node.records.into_iter();
+        for (address, count) in node.children {
+            track!(self.collect_records(reader, address, depth - 1, count, field_sizes, records))?;
+            records.extend(node_records.next());
+        }
+        Ok(())
+    }
+
+    /// Appends the matching records of the subtree rooted at `node` (its address and the number of its records).
+    fn search_node<R, F>(
+        &self,
+        reader: &mut R,
+        node: (u64, u64),
+        depth: u16,
+        field_sizes: (usize, &[usize]),
+        compare: &F,
+        records: &mut Vec<Vec<u8>>,
+    ) -> Result<()>
+    where
+        R: Read + Seek,
+        F: Fn(&[u8]) -> Ordering,
+    {
+        let (address, record_count) = node;
+        let node = track!(self.read_node(reader, address, depth, record_count, field_sizes))?;
+        let orderings = node.records.iter().map(|r| compare(r)).collect::<Vec<_>>();
+        for (i, record) in node.records.into_iter().enumerate() {
+            // The child before a record holds the records between it and the previous record.
+            if let Some(&child) = node.children.get(i) {
+                let after_previous = i == 0 || orderings[i - 1] != Ordering::Greater;
+                if after_previous && orderings[i] != Ordering::Less {
+                    track!(self.search_node(
+                        reader,
+                        child,
+                        depth - 1,
+                        field_sizes,
+                        compare,
+                        records
+                    ))?;
+                }
+            }
+            if orderings[i] == Ordering::Equal {
+                records.push(record);
+            }
+        }
+        if let Some(&child) = node.children.last() {
+            if orderings.last() != Some(&Ordering::Greater) {
+                track!(self.search_node(reader, child, depth - 1, field_sizes, compare, records))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a node containing `record_count` records.
+    fn read_node<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        address: u64,
+        depth: u16,
+        record_count: u64,
+        field_sizes: (usize, &[usize]),
+    ) -> Result<BTreeV2Node> {
+        track_assert!(
+            record_count.saturating_mul(u64::from(self.record_size)) <= u64::from(self.node_size),
+            ErrorKind::InvalidFile;
+            record_count,
+            self.node_size
+        );
+        track!(reader.seek_to(address))?;
+        reader.with_offset("v2 B-tree node", |r| {
+            let mut r = RecordingReader::new(r, Vec::new());
+            let signature: &[u8; 4] = if depth == 0 { b"BTLF" } else { b"BTIN" };
+            track!(r.assert_signature(signature))?;
+            let version = track!(r.read_u8())?;
+            track_assert_eq!(version, 0, ErrorKind::Unsupported);
+            let record_type = track!(r.read_u8())?;
+            track_assert_eq!(record_type, self.record_type, ErrorKind::InvalidFile);
+
+            let node_records = (0..record_count)
+                .map(|_| track!(r.read_vec(usize::from(self.record_size))))
+                .collect::<Result<Vec<_>>>()?;
+            let mut children = Vec::new();
+            if depth > 0 {
+                let (record_count_size, total_record_count_sizes) = field_sizes;
+                for _ in 0..=record_count {
+                    let address = track!(r.read_u64())?;
+                    let count = track!(r.read_uint(record_count_size))?;
+                    if depth > 1 {
+                        let _total =
+                            track!(r.read_uint(total_record_count_sizes[depth as usize - 1]))?;
+                    }
+                    children.push((address, count));
+                }
+            }
+            let checksum = track!(r.read_u32())?;
+            if self.verify_checksums {
+                let bytes = r.recorded();
+                track!(verify_checksum(&bytes[..bytes.len() - 4], checksum))?;
+            }
+            Ok(BTreeV2Node {
+                records: node_records,
+                children,
+            })
+        })
+    }
+
+    /// Reads a version 2 B-tree header.
+    ///
+    /// If `options` says so, the checksums of the header and of the nodes read afterwards are verified.
+    pub fn from_reader<R: Read>(reader: R, options: &ReaderOptions) -> Result<Self> {
+        let mut reader = RecordingReader::new(reader, Vec::new());
+        track!(reader.assert_signature(b"BTHD"))?;
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 0, ErrorKind::Unsupported);
+
+        let record_type = track!(reader.read_u8())?;
+        let node_size = track!(reader.read_u32())?;
+        let record_size = track!(reader.read_u16())?;
+        let depth = track!(reader.read_u16())?;
+        let _split_percent = track!(reader.read_u8())?;
+        let _merge_percent = track!(reader.read_u8())?;
+        let root_node_address = track!(reader.read_u64())?;
+        let root_record_count = track!(reader.read_u16())?;
+        let total_record_count = track!(reader.read_u64())?;
+        let checksum = track!(reader.read_u32())?;
+        let verify_checksums = options.is_verifying_checksums();
+        if verify_checksums {
+            let bytes = reader.recorded();
+            track!(verify_checksum(&bytes[..bytes.len() - 4], checksum))?;
+        }
+
+        track_assert!(
+            record_size != 0 && node_size > 10,
+            ErrorKind::InvalidFile;
+            record_size,
+            node_size
+        );
+        // Each level multiplies the number of records by at least two, so deeper trees can't be valid.
+        track_assert!(depth <= MAX_B_TREE_V2_DEPTH, ErrorKind::InvalidFile; depth);
+        Ok(Self {
+            record_type,
+            node_size,
+            record_size,
+            depth,
+            root_node_address,
+            root_record_count,
+            total_record_count,
+            verify_checksums,
+        })
+    }
+}
+
+/// Records and children of a version 2 B-tree node.
+#[derive(Debug)]
+struct BTreeV2Node {
+    records: Vec<Vec<u8>>,
+
+    // Addresses and numbers of records of the children (empty for leaves).
+    children: Vec<(u64, u64)>,
+}
+
+/// Returns an error if `checksum` isn't the lookup3 hash of `data`.
+///
+/// This is how the checksums of the metadata structures introduced in HDF5 1.8 are computed.
+pub(crate) fn verify_checksum(data: &[u8], checksum: u32) -> Result<()> {
+    let expected = lookup3_hash(data);
+    track_assert_eq!(
+        checksum,
+        expected,
+        ErrorKind::InvalidFile,
+        "Checksum mismatch"
+    );
+    Ok(())
+}
+
+/// Computes the Jenkins lookup3 hash of `key` (`hashlittle` with the initial value of zero).
+///
+/// This is the hash of the names stored in the name indices of links and attributes.
+pub(crate) fn lookup3_hash(key: &[u8]) -> u32 {
+    fn word(bytes: &[u8]) -> u32 {
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    let mut a = 0xDEAD_BEEF_u32.wrapping_add(key.len() as u32);
+    let mut b = a;
+    let mut c = a;
+    let mut key = key;
+    while key.len() > 12 {
+        a = a.wrapping_add(word(&key[0..]));
+        b = b.wrapping_add(word(&key[4..]));
+        c = c.wrapping_add(word(&key[8..]));
+        for &(r1, r2, r3) in &[(4, 6, 8), (16, 19, 4)] {
+            a = a.wrapping_sub(c) ^ c.rotate_left(r1);
+            c = c.wrapping_add(b);
+            b = b.wrapping_sub(a) ^ a.rotate_left(r2);
+            a = a.wrapping_add(c);
+            c = c.wrapping_sub(b) ^ b.rotate_left(r3);
+            b = b.wrapping_add(a);
+        }
+        key = &key[12..];
+    }
+    if key.is_empty() {
+        return c;
+    }
+
+    // The last block is padded with zeros.
+    let mut tail = [0; 12];
+    tail[..key.len()].copy_from_slice(key);
+    a = a.wrapping_add(word(&tail[0..]));
+    b = b.wrapping_add(word(&tail[4..]));
+    c = c.wrapping_add(word(&tail[8..]));
+    c = (c ^ b).wrapping_sub(b.rotate_left(14));
+    a = (a ^ c).wrapping_sub(c.rotate_left(11));
+    b = (b ^ a).wrapping_sub(a.rotate_left(25));
+    c = (c ^ b).wrapping_sub(b.rotate_left(16));
+    a = (a ^ c).wrapping_sub(c.rotate_left(4));
+    b = (b ^ a).wrapping_sub(a.rotate_left(14));
+    (c ^ b).wrapping_sub(b.rotate_left(24))
+}
+
+/// Returns the number of bytes used to encode the given maximum value in B-tree nodes.
+fn encoded_size(max_value: u64) -> usize {
+    (63 - max_value.max(1).leading_zeros()) as usize / 8 + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn symbol_table_entry_scratch_pad_works() -> TopLevelResult {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&8u64.to_le_bytes()); // link name offset
+        bytes.extend_from_slice(&96u64.to_le_bytes()); // object header address
+        bytes.extend_from_slice(&1u32.to_le_bytes()); // cache type
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&136u64.to_le_bytes()); // B-tree address
+        bytes.extend_from_slice(&680u64.to_le_bytes()); // local heap address
+
+        let entry = track!(SymbolTableEntry::from_reader(&bytes[..]))?;
+        assert_eq!(entry.cache_type(), 1);
+        assert_eq!(entry.cached_b_tree_address(), Some(136));
+        assert_eq!(entry.cached_local_heap_address(), Some(680));
+
+        let entry = SymbolTableEntry::from_object_header_address(96);
+        assert_eq!(entry.cache_type(), 0);
+        assert_eq!(entry.cached_b_tree_address(), None);
+        assert_eq!(entry.cached_local_heap_address(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn link_name_works() -> TopLevelResult {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"HEAP\x00\x00\x00\x00");
+        bytes.extend_from_slice(&16u64.to_le_bytes()); // data segment size
+        bytes.extend_from_slice(&LOCAL_HEAP_FREE_NULL.to_le_bytes()); // free list head offset
+        bytes.extend_from_slice(&32u64.to_le_bytes()); // data segment address
+        bytes.extend_from_slice(b"\0\0\0\0\0\0\0\0foo\0\0\0\0\0");
+        let heap = track!(LocalHeap::from_reader(&bytes[..]))?;
+
+        let mut entry = SymbolTableEntry::from_object_header_address(96);
+        assert_eq!(track!(entry.link_name(Cursor::new(&bytes), None))?, None);
+        assert_eq!(
+            track!(entry.link_name(Cursor::new(&bytes), Some(&heap)))?,
+            None
+        );
+
+        entry.link_name_offset = 8;
+        let name = track!(entry.link_name(Cursor::new(&bytes), Some(&heap)))?;
+        assert_eq!(name.as_deref(), Some("foo"));
+        Ok(())
+    }
+
+    #[test]
+    fn global_heap_collection_works() -> TopLevelResult {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"GCOL\x01\x00\x00\x00");
+        bytes.extend_from_slice(&80u64.to_le_bytes()); // collection size
+        bytes.extend_from_slice(&[1, 0, 1, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&3u64.to_le_bytes());
+        bytes.extend_from_slice(b"foo\0\0\0\0\0");
+        bytes.extend_from_slice(&[2, 0, 1, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&8u64.to_le_bytes());
+        bytes.extend_from_slice(&96u64.to_le_bytes());
+        bytes.extend_from_slice(&[0; 8]); // free space
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+
+        let collection = track!(GlobalHeapCollection::from_reader(&bytes[..]))?;
+        assert_eq!(collection.object(1), Some(&b"foo"[..]));
+        assert_eq!(collection.object(2), Some(&96u64.to_le_bytes()[..]));
+        assert_eq!(collection.object(3), None);
+
+        bytes[8..16].copy_from_slice(&32u64.to_le_bytes());
+        assert!(GlobalHeapCollection::from_reader(&bytes[..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn local_heap_free_blocks_works() -> TopLevelResult {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"HEAP\x00\x00\x00\x00");
+        bytes.extend_from_slice(&64u64.to_le_bytes()); // data segment size
+        bytes.extend_from_slice(&16u64.to_le_bytes()); // free list head offset
+        bytes.extend_from_slice(&32u64.to_le_bytes()); // data segment address
+
+        let mut segment = vec![0; 64];
+        segment[16..24].copy_from_slice(&40u64.to_le_bytes());
+        segment[24..32].copy_from_slice(&16u64.to_le_bytes());
+        segment[40..48].copy_from_slice(&LOCAL_HEAP_FREE_NULL.to_le_bytes());
+        segment[48..56].copy_from_slice(&24u64.to_le_bytes());
+        bytes.extend_from_slice(&segment);
+
+        let heap = track!(LocalHeap::from_reader(&bytes[..]))?;
+        assert_eq!(heap.data_segment_size(), 64);
+        assert_eq!(heap.free_list_head_offset(), Some(16));
+
+        let blocks = track!(heap.free_blocks(Cursor::new(&bytes)))?;
+        assert_eq!(
+            blocks,
+            vec![
+                FreeBlock {
+                    offset: 16,
+                    size: 16
+                },
+                FreeBlock {
+                    offset: 40,
+                    size: 24
+                }
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn b_tree_node_children_with_invalid_levels_are_rejected() -> TopLevelResult {
+        let node = |level: u8, child: Option<u64>| {
+            let mut bytes = b"TREE\x00".to_vec();
+            bytes.push(level);
+            bytes.extend_from_slice(&u16::from(child.is_some()).to_le_bytes());
+            bytes.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+            bytes.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+            bytes.extend_from_slice(&0u64.to_le_bytes());
+            if let Some(child) = child {
+                bytes.extend_from_slice(&child.to_le_bytes());
+                bytes.extend_from_slice(&8u64.to_le_bytes());
+            }
+            bytes
+        };
+        let mut bytes = node(1, Some(48));
+        assert_eq!(bytes.len(), 48);
+        bytes.extend_from_slice(&node(255, None));
+
+        let parent = track!(BTreeNode::from_reader(&bytes[..]))?;
+        let error = parent
+            .children(Cursor::new(&bytes))
+            .next()
+            .expect("no children")
+            .unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_b_tree_node_works() -> TopLevelResult {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"TREE\x01\x00\x02\x00");
+        bytes.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+        bytes.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+        for (size, offset, address) in &[(100u32, 0u64, 1000u64), (80, 4, 2000), (0, 8, 0)] {
+            bytes.extend_from_slice(&size.to_le_bytes());
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&0u64.to_le_bytes());
+            if *address != 0 {
+                bytes.extend_from_slice(&address.to_le_bytes());
+            }
+        }
+
+        let node = track!(ChunkBTreeNode::from_reader(&bytes[..], 2))?;
+        let chunks = track!(node.chunks(Cursor::new(&bytes)))?;
+        assert_eq!(
+            chunks,
+            vec![
+                ChunkInfo {
+                    offset: vec![0],
+                    address: 1000,
+                    size: 100,
+                    filter_mask: 0
+                },
+                ChunkInfo {
+                    offset: vec![4],
+                    address: 2000,
+                    size: 80,
+                    filter_mask: 0
+                }
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_b_tree_node_children_with_invalid_levels_are_rejected() -> TopLevelResult {
+        let node = |level: u8, child: Option<u64>| {
+            let mut bytes = b"TREE\x01".to_vec();
+            bytes.push(level);
+            bytes.extend_from_slice(&u16::from(child.is_some()).to_le_bytes());
+            bytes.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+            bytes.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+            bytes.extend_from_slice(&[0; 24]);
+            if let Some(child) = child {
+                bytes.extend_from_slice(&child.to_le_bytes());
+                bytes.extend_from_slice(&[0; 24]);
+            }
+            bytes
+        };
+        let mut bytes = node(1, Some(80));
+        assert_eq!(bytes.len(), 80);
+        bytes.extend_from_slice(&node(255, None));
+
+        let parent = track!(ChunkBTreeNode::from_reader(&bytes[..], 2))?;
+        let error = parent.chunks(Cursor::new(&bytes)).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        Ok(())
+    }
+
+    #[test]
+    fn lookup3_hash_works() {
+        assert_eq!(lookup3_hash(b""), 0xDEAD_BEEF);
+        assert_eq!(lookup3_hash(b"Four score and seven years ago"), 0x1777_0551);
+    }
+
+    #[test]
+    fn b_tree_v2_records_work() -> TopLevelResult {
+        let header = BTreeV2 {
+            record_type: 6,
+            node_size: 512,
+            record_size: 4,
+            depth: 1,
+            root_node_address: 0,
+            root_record_count: 1,
+            total_record_count: 4,
+            verify_checksums: true,
+        };
+        let record = |n: u32| n.to_le_bytes().to_vec();
+        let append_checksum = |bytes: &mut Vec<u8>, start: usize| {
+            let checksum = lookup3_hash(&bytes[start..]);
+            bytes.extend_from_slice(&checksum.to_le_bytes());
+        };
+
+        // Internal node: one record and two children (each pointer is an address and a 1-byte count).
+        let mut bytes = b"BTIN\x00\x06".to_vec();
+        bytes.extend(record(2));
+        bytes.extend_from_slice(&100u64.to_le_bytes());
+        bytes.push(2);
+        bytes.extend_from_slice(&200u64.to_le_bytes());
+        bytes.push(1);
+        append_checksum(&mut bytes, 0);
+        bytes.resize(100, 0);
+        bytes.extend_from_slice(b"BTLF\x00\x06");
+        bytes.extend(record(0));
+        bytes.extend(record(1));
+        append_checksum(&mut bytes, 100);
+        bytes.resize(200, 0);
+        bytes.extend_from_slice(b"BTLF\x00\x06");
+        bytes.extend(record(3));
+        append_checksum(&mut bytes, 200);
+
+        let records = track!(header.records(Cursor::new(&bytes)))?;
+        assert_eq!(records, (0..4).map(record).collect::<Vec<_>>());
+
+        // Only the nodes which may contain the key are read, so the broken second leaf doesn't matter.
+        let find = |bytes: &[u8], key: u32| {
+            header.find_records(Cursor::new(bytes), |r| {
+                u32::from_le_bytes([r[0], r[1], r[2], r[3]]).cmp(&key)
+            })
+        };
+        let mut broken = bytes.clone();
+        broken[200] = 0;
+        assert_eq!(track!(find(&broken, 1))?, [record(1)]);
+        assert_eq!(track!(find(&broken, 0))?, [record(0)]);
+        assert_eq!(track!(find(&bytes, 2))?, [record(2)]);
+        assert_eq!(track!(find(&bytes, 3))?, [record(3)]);
+        assert!(track!(find(&bytes, 5))?.is_empty());
+        assert!(find(&broken, 3).is_err());
+
+        // Corrupted nodes are rejected unless checksums aren't verified.
+        let mut corrupted = bytes.clone();
+        corrupted[106] ^= 0x01;
+        let error = header.records(Cursor::new(&corrupted)).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        let unverified = BTreeV2 {
+            verify_checksums: false,
+            ..header.clone()
+        };
+        assert_eq!(
+            track!(unverified.records(Cursor::new(&corrupted)))?.len(),
+            4
+        );
+
+        // Nodes containing more records than the whole tree are rejected.
+        let header = BTreeV2 {
+            total_record_count: 2,
+            ..header
+        };
+        assert!(header.records(Cursor::new(&bytes)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn fractal_heap_indirect_block_works() -> TopLevelResult {
+        let heap = FractalHeap {
+            heap_id_len: 7,
+            io_filters_len: 0,
+            flags: 0,
+            max_managed_object_size: 1024,
+            table_width: 2,
+            starting_block_size: 512,
+            max_direct_block_size: 1024,
+            max_heap_size: 32,
+            root_block_address: 0,
+            root_block_rows: 2,
+        };
+
+        // The root indirect block refers to four 512-byte direct blocks, the last of which is at 100.
+        let mut bytes = b"FHIB\x00".to_vec();
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        for &address in &[UNDEFINED_ADDRESS, UNDEFINED_ADDRESS, UNDEFINED_ADDRESS, 100] {
+            bytes.extend_from_slice(&address.to_le_bytes());
+        }
+        bytes.resize(100, 0);
+        bytes.extend_from_slice(b"FHDB\x00");
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&1536u32.to_le_bytes());
+        bytes.extend_from_slice(b"\x00\x00\x00hello");
+
+        let mut heap_id = vec![0];
+        heap_id.extend_from_slice(&(1536u32 + 20).to_le_bytes());
+        heap_id.extend_from_slice(&5u16.to_le_bytes());
+        let object = track!(heap.get_object(Cursor::new(&bytes), &heap_id))?;
+        assert_eq!(object, b"hello");
+
+        // The block containing this offset isn't allocated.
+        heap_id[1..5].copy_from_slice(&(512u32 + 20).to_le_bytes());
+        assert!(heap.get_object(Cursor::new(&bytes), &heap_id).is_err());
+
+        // Tiny objects are stored in the heap IDs.
+        let object = track!(heap.get_object(Cursor::new(&bytes), &[0x22, 1, 2, 3, 0, 0, 0]))?;
+        assert_eq!(object, [1, 2, 3]);
+        Ok(())
+    }
+}
@@ -1,585 +1,5726 @@
-use crate::io::{ReadExt as _, SeekExt as _};
-use crate::{Error, ErrorKind, Result};
-use ndarray;
-use ndarray::ArrayD;
+use crate::array;
+use crate::filter::FilterRegistry;
+use crate::io::{ReadExt as _, RecordingReader, SeekExt as _};
+use crate::lowlevel::level0::{UNDEFINED_ADDRESS, UNLIMITED_SIZE};
+use crate::lowlevel::level1::{
+    verify_checksum, BTreeV2, ChunkBTreeNode, ChunkInfo, FractalHeap, GlobalHeapCollection,
+};
+use crate::{Array, Error, ErrorKind, ReaderOptions, Result};
 use std;
+use std::collections::hash_map::{Entry, HashMap};
 use std::convert::TryFrom;
+use std::fmt;
 use std::io::{Read, Seek};
 
 /// Data object.
 #[derive(Debug)]
 pub enum DataObject {
     /// Floating-point numbers.
-    Float(ArrayD<f64>),
-}
+    Float(Array<f64>),
 
-// TODO: move level2a
-/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#ObjectHeader
-#[derive(Debug, Clone)]
-pub struct ObjectHeader {
-    prefix: ObjectHeaderPrefix,
+    /// Single-precision (IEEE 754 binary32) floating-point numbers.
+    Float32(Array<f32>),
+
+    /// 8-bit unsigned integers.
+    U8(Array<u8>),
+
+    /// 16-bit unsigned integers.
+    U16(Array<u16>),
+
+    /// Signed integers.
+    I64(Array<i64>),
+
+    /// Unsigned integers that don't fit in `U8` or `U16`.
+    U64(Array<u64>),
+
+    /// Booleans.
+    Bool(Array<bool>),
+
+    /// Compound values (i.e., records consisting of named members) stored column by column.
+    Compound(Table),
+
+    /// Enumeration values.
+    Enum {
+        /// The integer values of the elements.
+        codes: Array<i64>,
+
+        /// The names and the values of the members of the enumeration.
+        mapping: Vec<(String, i64)>,
+    },
+
+    /// Values of types that have no dedicated variant (e.g., strings in a column of `DataObject::Compound`).
+    Values(Array<ScalarValue>),
 }
-impl ObjectHeader {
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        let prefix = track!(ObjectHeaderPrefix::from_reader(&mut reader))?;
-        Ok(Self { prefix })
+impl DataObject {
+    /// Returns the shape of the data object.
+    pub fn shape(&self) -> &[usize] {
+        match self {
+            DataObject::Float(a) => a.shape(),
+            DataObject::Float32(a) => a.shape(),
+            DataObject::U8(a) => a.shape(),
+            DataObject::U16(a) => a.shape(),
+            DataObject::I64(a) => a.shape(),
+            DataObject::U64(a) => a.shape(),
+            DataObject::Bool(a) => a.shape(),
+            DataObject::Compound(t) => t.shape(),
+            DataObject::Enum { codes, .. } => codes.shape(),
+            DataObject::Values(a) => a.shape(),
+        }
     }
 
-    pub fn get_data_object<R: Read + Seek>(&self, mut reader: R) -> Result<DataObject> {
-        let bytes = track!(self.get_data_bytes(&mut reader))?;
-        let dimensions = track!(self.dimensions())?
-            .iter()
-            .map(|&d| d as usize)
-            .collect::<Vec<_>>();
-        let datatype = track!(self.datatype())?;
+    /// Converts the data object into an array of `T`.
+    ///
+    /// If the conversion is lossy or the element types don't match,
+    /// an `ErrorKind::InvalidInput` error will be returned.
+    #[cfg(feature = "ndarray")]
+    pub fn into_ndarray<T: FromDataObject>(self) -> Result<Array<T>> {
+        track!(self.into_array())
+    }
 
-        let count = dimensions.iter().cloned().product::<usize>();
-        let mut reader = &bytes[..];
-        match datatype {
-            DatatypeMessage::FloatingPoint(t) => {
-                let items = (0..count)
-                    .map(|i| track!(t.decode(&mut reader); i))
-                    .collect::<Result<Vec<_>>>()?;
-                track_assert_eq!(reader, b"", ErrorKind::InvalidFile);
+    /// Converts the data object into an array of `T`.
+    ///
+    /// This is the same as `into_ndarray` if the `ndarray` feature is enabled.
+    pub fn into_array<T: FromDataObject>(self) -> Result<Array<T>> {
+        track!(T::from_data_object(self))
+    }
 
-                let items = track!(ndarray::aview1(&items)
-                    .into_shape(dimensions)
-                    .map_err(Error::from))?;
-                Ok(DataObject::Float(items.to_owned()))
-            }
-            _ => track_panic!(ErrorKind::Unsupported),
+    /// Converts the data object into row-major items of `T` and the shape of the data.
+    ///
+    /// If the conversion is lossy or the element types don't match,
+    /// an `ErrorKind::InvalidInput` error will be returned.
+    pub fn into_vec<T: FromDataObject>(self) -> Result<(Vec<T>, Vec<usize>)> {
+        let array = track!(T::from_data_object(self))?;
+        let shape = array.shape().to_vec();
+        Ok((array.into_raw_vec(), shape))
+    }
+
+    /// Returns the floating-point numbers of the data object as a flat row-major slice without copying them.
+    ///
+    /// If the data object isn't `DataObject::Float` or its array isn't in the standard (row-major contiguous) layout,
+    /// `None` will be returned.
+    pub fn as_slice_f64(&self) -> Option<&[f64]> {
+        if let DataObject::Float(a) = self {
+            a.as_slice()
+        } else {
+            None
         }
     }
 
-    fn dimensions(&self) -> Result<&[u64]> {
-        for m in &self.prefix.messages {
-            if let Message::Dataspace(m) = &m.message {
-                return Ok(&m.dimension_sizes);
-            }
+    /// Returns the values of the compound member named `name` across all records (see `Table::column`).
+    ///
+    /// If the data object isn't `DataObject::Compound` or has no such member, `None` will be returned.
+    pub fn column(&self, name: &str) -> Option<&DataObject> {
+        if let DataObject::Compound(t) = self {
+            t.column(name)
+        } else {
+            None
         }
-        track_panic!(ErrorKind::Other);
     }
 
-    fn datatype(&self) -> Result<DatatypeMessage> {
-        for m in &self.prefix.messages {
-            if let Message::Datatype(m) = &m.message {
-                return Ok(m.clone());
+    /// Reorders the axes of the data object so that its `i`-th axis becomes the `order[i]`-th axis of `self`
+    /// (e.g., `&[1, 0]` transposes a two-dimensional data object).
+    ///
+    /// The resulting arrays are in the standard (row-major contiguous) layout.
+    /// If `order` isn't a permutation of the axes, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn permuted_axes(self, order: &[usize]) -> Result<Self> {
+        Ok(match self {
+            DataObject::Float(a) => DataObject::Float(track!(array::permute_axes(a, order))?),
+            DataObject::Float32(a) => DataObject::Float32(track!(array::permute_axes(a, order))?),
+            DataObject::U8(a) => DataObject::U8(track!(array::permute_axes(a, order))?),
+            DataObject::U16(a) => DataObject::U16(track!(array::permute_axes(a, order))?),
+            DataObject::I64(a) => DataObject::I64(track!(array::permute_axes(a, order))?),
+            DataObject::U64(a) => DataObject::U64(track!(array::permute_axes(a, order))?),
+            DataObject::Bool(a) => DataObject::Bool(track!(array::permute_axes(a, order))?),
+            DataObject::Compound(t) => {
+                track!(array::check_permutation(order, t.shape().len()))?;
+                let shape = order.iter().map(|&i| t.shape()[i]).collect();
+                let columns = t
+                    .into_columns()
+                    .into_iter()
+                    .map(|(name, c)| Ok((name, track!(c.permuted_axes(order))?)))
+                    .collect::<Result<Vec<_>>>()?;
+                DataObject::Compound(track!(Table::new(shape, columns))?)
             }
-        }
-        track_panic!(ErrorKind::Other);
+            DataObject::Enum { codes, mapping } => DataObject::Enum {
+                codes: track!(array::permute_axes(codes, order))?,
+                mapping,
+            },
+            DataObject::Values(a) => DataObject::Values(track!(array::permute_axes(a, order))?),
+        })
     }
 
-    pub fn get_data_bytes<R: Read + Seek>(&self, mut reader: R) -> Result<Vec<u8>> {
-        for m in &self.prefix.messages {
-            if let Message::DataLayout(m) = &m.message {
-                let Layout::Contiguous { address, size } = m.layout;
-                track!(reader.seek_to(address))?;
-                return track!(reader.read_vec(size as usize));
+    /// Serializes the elements of the data object as a tightly-packed little-endian byte buffer in row-major order.
+    ///
+    /// Booleans are written as single bytes (`0` or `1`).
+    /// Compound values have no canonical packed form, so an `ErrorKind::Unsupported` error will be returned for them.
+    pub fn to_le_bytes(&self) -> Result<Vec<u8>> {
+        fn pack<T, const N: usize>(a: &Array<T>, f: impl Fn(&T) -> [u8; N]) -> Vec<u8> {
+            let mut bytes = Vec::with_capacity(a.len() * N);
+            for x in a.iter() {
+                bytes.extend_from_slice(&f(x));
             }
+            bytes
         }
-        track_panic!(ErrorKind::Other, "Not a data object");
+
+        Ok(match self {
+            DataObject::Float(a) => pack(a, |x| x.to_le_bytes()),
+            DataObject::Float32(a) => pack(a, |x| x.to_le_bytes()),
+            DataObject::U8(a) => pack(a, |x| [*x]),
+            DataObject::U16(a) => pack(a, |x| x.to_le_bytes()),
+            DataObject::I64(a) => pack(a, |x| x.to_le_bytes()),
+            DataObject::U64(a) => pack(a, |x| x.to_le_bytes()),
+            DataObject::Bool(a) => pack(a, |x| [u8::from(*x)]),
+            DataObject::Enum { codes, .. } => pack(codes, |x| x.to_le_bytes()),
+            DataObject::Compound(_) | DataObject::Values(_) => track_panic!(
+                ErrorKind::Unsupported,
+                "Compound values and untyped values can't be serialized as packed bytes"
+            ),
+        })
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct ObjectHeaderPrefix {
-    messages: Vec<HeaderMessage>,
-    object_reference_count: u32,
-    object_header_size: u32,
+/// Compound values stored column by column.
+///
+/// Each column holds the values of a member of the compound datatype across all records,
+/// and has the same shape as the table.
+#[derive(Debug)]
+pub struct Table {
+    shape: Vec<usize>,
+    columns: Vec<(String, DataObject)>,
+    column_indices: HashMap<String, usize>,
 }
-impl ObjectHeaderPrefix {
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        let version = track!(reader.read_u8())?;
-        track_assert_eq!(version, 1, ErrorKind::InvalidFile);
-
-        let _reserved = track!(reader.read_u8())?;
-        track_assert_eq!(_reserved, 0, ErrorKind::InvalidFile);
-
-        let header_message_count = track!(reader.read_u16())?;
-        let object_reference_count = track!(reader.read_u32())?;
-        let object_header_size = track!(reader.read_u32())?;
-
-        // Header messages are aligned on 8-byte boundaries for version 1 object headers.
-        track!(reader.skip(4))?;
-
-        let mut reader = reader.take(u64::from(object_header_size));
-        let messages = (0..header_message_count)
-            .map(|_| track!(HeaderMessage::from_reader(&mut reader)))
-            .collect::<Result<_>>()?;
-        track_assert_eq!(reader.limit(), 0, ErrorKind::Other; object_header_size, messages);
-
+impl Table {
+    /// Makes a table of the given shape from named columns.
+    ///
+    /// If a column has a different shape or a name is duplicated, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn new(shape: Vec<usize>, columns: Vec<(String, DataObject)>) -> Result<Self> {
+        let mut column_indices = HashMap::with_capacity(columns.len());
+        for (i, (name, column)) in columns.iter().enumerate() {
+            track_assert_eq!(column.shape(), &shape[..], ErrorKind::InvalidInput; name);
+            track_assert!(
+                column_indices.insert(name.clone(), i).is_none(),
+                ErrorKind::InvalidInput,
+                "Duplicate column: {:?}",
+                name
+            );
+        }
         Ok(Self {
-            messages,
-            object_reference_count,
-            object_header_size,
+            shape,
+            columns,
+            column_indices,
         })
     }
-}
 
-bitflags! {
-    struct HeaderMessageFlags: u8 {
-        const CONSTANT = 0b0000_0001;
-        const SHARED = 0b0000_0010;
-        const UNSHARABLE = 0b0000_0100;
-        const CANNOT_WRITE_IF_UNKNOWN = 0b0000_1000;
-        const SET_5_BIT_IF_UNKNOWN = 0b0001_0000;
-        const UNKNOWN_BUT_MODIFIED = 0b0010_0000;
-        const SHARABLE = 0b0100_0000;
-        const FAIL_IF_UNKNOWN = 0b0100_0000;
+    /// Returns the shape of the table (i.e., that of each column).
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct HeaderMessage {
-    flags: HeaderMessageFlags,
-    message: Message,
-}
-impl HeaderMessage {
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        let kind = track!(reader.read_u16())?;
-        let data_len = track!(reader.read_u16())?;
-        let flags = HeaderMessageFlags::from_bits_truncate(track!(reader.read_u8())?);
-        track!(reader.skip(3))?;
-        let mut reader = reader.take(u64::from(data_len));
-        let message = match kind {
-            0x00 => track!(NilMessage::from_reader(&mut reader)).map(Message::Nil)?,
-            0x01 => track!(DataspaceMessage::from_reader(&mut reader)).map(Message::Dataspace)?,
-            0x03 => track!(DatatypeMessage::from_reader(&mut reader)).map(Message::Datatype)?,
-            0x05 => track!(FillValueMessage::from_reader(&mut reader)).map(Message::FillValue)?,
-            0x08 => track!(DataLayoutMessage::from_reader(&mut reader)).map(Message::DataLayout)?,
-            0x11 => {
-                track!(SymbolTableMessage::from_reader(&mut reader)).map(Message::SymbolTable)?
-            }
-            0x12 => track!(ObjectModificationTimeMessage::from_reader(&mut reader))
-                .map(Message::ObjectModificationTime)?,
-            _ => track_panic!(ErrorKind::Unsupported, "Message type: {}", kind),
-        };
-        track_assert_eq!(reader.limit(), 0, ErrorKind::Other);
+    /// Returns the column named `name`.
+    pub fn column(&self, name: &str) -> Option<&DataObject> {
+        self.column_indices.get(name).map(|&i| &self.columns[i].1)
+    }
 
-        Ok(Self { flags, message })
+    /// Returns the names and the columns of the table in the declaration order of the members.
+    pub fn columns(&self) -> &[(String, DataObject)] {
+        &self.columns
     }
-}
 
-/// type=0x00
-#[derive(Debug, Clone)]
-pub struct NilMessage {}
-impl NilMessage {
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        let _ = track!(reader.read_all())?;
-        Ok(Self {})
+    /// Converts the table into its named columns.
+    pub fn into_columns(self) -> Vec<(String, DataObject)> {
+        self.columns
     }
 }
 
-/// type=0x01
-#[derive(Debug, Clone)]
-pub struct DataspaceMessage {
-    dimension_sizes: Vec<u64>,
-    dimension_max_sizes: Option<Vec<u64>>,
-}
-impl DataspaceMessage {
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        let version = track!(reader.read_u8())?;
-        track_assert_eq!(version, 1, ErrorKind::Unsupported);
+/// A single decoded value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScalarValue {
+    /// Single-precision floating-point number.
+    F32(f32),
 
-        let dimensionality = track!(reader.read_u8())?;
-        let flags = track!(reader.read_u8())?; // TODO: consider flags
-        track!(reader.skip(5))?;
+    /// Floating-point number.
+    F64(f64),
 
-        let dimension_sizes = (0..dimensionality)
-            .map(|_| track!(reader.read_u64()))
-            .collect::<Result<Vec<_>>>()?;
+    /// 8-bit signed integer.
+    I8(i8),
 
-        let dimension_max_sizes = if (flags & 0b0000_0001) != 0 {
-            Some(
-                (0..dimensionality)
-                    .map(|_| track!(reader.read_u64()))
-                    .collect::<Result<Vec<_>>>()?,
-            )
-        } else {
-            None
-        };
+    /// 16-bit signed integer.
+    I16(i16),
 
-        if (flags & 0b0000_0010) != 0 {
-            track_panic!(ErrorKind::Unsupported);
-        }
+    /// 32-bit signed integer.
+    I32(i32),
 
-        Ok(Self {
-            dimension_sizes,
-            dimension_max_sizes,
-        })
-    }
+    /// 64-bit signed integer.
+    I64(i64),
+
+    /// 8-bit unsigned integer.
+    U8(u8),
+
+    /// 16-bit unsigned integer.
+    U16(u16),
+
+    /// 32-bit unsigned integer.
+    U32(u32),
+
+    /// 64-bit unsigned integer.
+    U64(u64),
+
+    /// Boolean.
+    Bool(bool),
+
+    /// String.
+    Str(String),
+
+    /// Uninterpreted bytes (e.g., an opaque value).
+    Bytes(Vec<u8>),
+
+    /// Compound value (named members in declaration order).
+    Compound(Vec<(String, ScalarValue)>),
+
+    /// Fixed-size array (in row-major order).
+    Array(Vec<ScalarValue>),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub enum DatatypeClass {
-    FixedPoint,
-    FloatingPoint,
-    Time,
-    String,
-    BitField,
-    Opaque,
-    Compound,
-    Reference,
-    Enumerated,
-    VariableLength,
-    Array,
+/// This trait allows for extracting typed arrays from `DataObject`s.
+pub trait FromDataObject: Sized {
+    /// Converts the given data object into an array of `Self`.
+    fn from_data_object(object: DataObject) -> Result<Array<Self>>;
+}
+impl FromDataObject for f64 {
+    fn from_data_object(object: DataObject) -> Result<Array<Self>> {
+        match object {
+            DataObject::Float(a) => Ok(a),
+            DataObject::Float32(a) => Ok(a.mapv(f64::from)),
+            DataObject::U8(a) => Ok(a.mapv(f64::from)),
+            DataObject::U16(a) => Ok(a.mapv(f64::from)),
+            DataObject::I64(a) => track!(convert_to_float(a, |v| v as f64, |v| v as i128)),
+            DataObject::U64(a) => track!(convert_to_float(a, |v| v as f64, |v| v as i128)),
+            DataObject::Bool(a) => Ok(a.mapv(|v| f64::from(u8::from(v)))),
+            DataObject::Enum { codes, .. } => {
+                track!(convert_to_float(codes, |v| v as f64, |v| v as i128))
+            }
+            DataObject::Compound(_) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Cannot convert compound values to `f64`"
+            ),
+            DataObject::Values(_) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Cannot convert untyped values to `f64`"
+            ),
+        }
+    }
+}
+impl FromDataObject for f32 {
+    fn from_data_object(object: DataObject) -> Result<Array<Self>> {
+        match object {
+            DataObject::Float(a) => {
+                for &v in a.iter() {
+                    track_assert!(
+                        f64::from(v as f32) == v || v.is_nan(),
+                        ErrorKind::InvalidInput,
+                        "Cannot convert {} to `f32` without loss of precision",
+                        v
+                    );
+                }
+                Ok(a.mapv(|v| v as f32))
+            }
+            DataObject::Float32(a) => Ok(a),
+            DataObject::U8(a) => Ok(a.mapv(f32::from)),
+            DataObject::U16(a) => Ok(a.mapv(f32::from)),
+            DataObject::I64(a) => track!(convert_to_float(a, |v| v as f32, |v| v as i128)),
+            DataObject::U64(a) => track!(convert_to_float(a, |v| v as f32, |v| v as i128)),
+            DataObject::Bool(a) => Ok(a.mapv(|v| f32::from(u8::from(v)))),
+            DataObject::Enum { codes, .. } => {
+                track!(convert_to_float(codes, |v| v as f32, |v| v as i128))
+            }
+            DataObject::Compound(_) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Cannot convert compound values to `f32`"
+            ),
+            DataObject::Values(_) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Cannot convert untyped values to `f32`"
+            ),
+        }
+    }
+}
+macro_rules! impl_integer_from_data_object {
+    ($($t:ty),*) => {
+        $(impl FromDataObject for $t {
+            fn from_data_object(object: DataObject) -> Result<Array<Self>> {
+                match object {
+                    DataObject::Float(_) | DataObject::Float32(_) => track_panic!(
+                        ErrorKind::InvalidInput,
+                        "Cannot convert floating-point numbers to `{}`",
+                        stringify!($t)
+                    ),
+                    DataObject::U8(a) => track!(convert_integers(a)),
+                    DataObject::U16(a) => track!(convert_integers(a)),
+                    DataObject::I64(a) => track!(convert_integers(a)),
+                    DataObject::U64(a) => track!(convert_integers(a)),
+                    DataObject::Bool(a) => track!(convert_integers(a)),
+                    DataObject::Enum { codes, .. } => track!(convert_integers(codes)),
+                    DataObject::Compound(_) => track_panic!(
+                        ErrorKind::InvalidInput,
+                        "Cannot convert compound values to `{}`",
+                        stringify!($t)
+                    ),
+                    DataObject::Values(_) => track_panic!(
+                        ErrorKind::InvalidInput,
+                        "Cannot convert untyped values to `{}`",
+                        stringify!($t)
+                    ),
+                }
+            }
+        })*
+    };
 }
-impl TryFrom<u8> for DatatypeClass {
-    type Error = Error;
 
-    fn try_from(f: u8) -> Result<Self> {
-        Ok(match f {
-            0 => DatatypeClass::FixedPoint,
-            1 => DatatypeClass::FloatingPoint,
-            2 => DatatypeClass::Time,
-            3 => DatatypeClass::String,
-            4 => DatatypeClass::BitField,
-            5 => DatatypeClass::Opaque,
-            6 => DatatypeClass::Compound,
-            7 => DatatypeClass::Reference,
-            8 => DatatypeClass::Enumerated,
-            9 => DatatypeClass::VariableLength,
-            10 => DatatypeClass::Array,
-            _ => track_panic!(ErrorKind::InvalidFile, "Unknown datatype class: {}", f),
-        })
+fn convert_integers<S, T>(array: Array<S>) -> Result<Array<T>>
+where
+    S: Copy + std::fmt::Display,
+    T: TryFrom<S>,
+{
+    let mut items = Vec::with_capacity(array.len());
+    for &v in array.iter() {
+        let v = track_assert_some!(
+            T::try_from(v).ok(),
+            ErrorKind::InvalidInput,
+            "Cannot convert {} to `{}` losslessly",
+            v,
+            std::any::type_name::<T>()
+        );
+        items.push(v);
     }
+    track!(array::from_shape_vec(array.shape().to_vec(), items))
 }
+impl_integer_from_data_object!(i8, i16, i32, i64, u8, u16, u32, u64);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum MantissaNorm {
-    None,
-    AlwaysSet,
-    ImpliedToBeSet,
+fn convert_to_float<S, T, F, G>(array: Array<S>, to_float: F, to_integer: G) -> Result<Array<T>>
+where
+    S: Copy + Into<i128> + std::fmt::Display,
+    T: Copy,
+    F: Fn(S) -> T,
+    G: Fn(T) -> i128,
+{
+    let mut items = Vec::with_capacity(array.len());
+    for &v in array.iter() {
+        let f = to_float(v);
+        track_assert_eq!(
+            to_integer(f),
+            v.into(),
+            ErrorKind::InvalidInput,
+            "Cannot convert {} to `{}` without loss of precision",
+            v,
+            std::any::type_name::<T>()
+        );
+        items.push(f);
+    }
+    track!(array::from_shape_vec(array.shape().to_vec(), items))
 }
-impl TryFrom<u8> for MantissaNorm {
-    type Error = Error;
+impl FromDataObject for bool {
+    fn from_data_object(object: DataObject) -> Result<Array<Self>> {
+        fn to_bool<T: Into<i128> + Copy>(array: Array<T>) -> Result<Array<bool>> {
+            for &v in array.iter() {
+                let v = v.into();
+                track_assert!(
+                    v == 0 || v == 1,
+                    ErrorKind::InvalidInput,
+                    "Cannot convert {} to `bool`",
+                    v
+                );
+            }
+            Ok(array.mapv(|v| v.into() == 1))
+        }
 
-    fn try_from(f: u8) -> Result<Self> {
-        match f {
-            0 => Ok(MantissaNorm::None),
-            1 => Ok(MantissaNorm::AlwaysSet),
-            2 => Ok(MantissaNorm::ImpliedToBeSet),
-            3 => track_panic!(ErrorKind::InvalidFile, "Reserved value"),
-            _ => track_panic!(ErrorKind::InvalidInput),
+        match object {
+            DataObject::Float(_) | DataObject::Float32(_) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Cannot convert floating-point numbers to `bool`"
+            ),
+            DataObject::U8(a) => track!(to_bool(a)),
+            DataObject::U16(a) => track!(to_bool(a)),
+            DataObject::I64(a) => track!(to_bool(a)),
+            DataObject::U64(a) => track!(to_bool(a)),
+            DataObject::Bool(a) => Ok(a),
+            DataObject::Enum { codes, .. } => track!(to_bool(codes)),
+            DataObject::Compound(_) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Cannot convert compound values to `bool`"
+            ),
+            DataObject::Values(_) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Cannot convert untyped values to `bool`"
+            ),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum Endian {
-    Little,
-    Big,
-    Vax,
+// TODO: move level2a
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#ObjectHeader
+#[derive(Debug, Clone)]
+pub struct ObjectHeader {
+    prefix: ObjectHeaderPrefix,
+    dense_attributes: Vec<AttributeMessage>,
 }
-impl TryFrom<u8> for Endian {
-    type Error = Error;
+impl ObjectHeader {
+    /// Reads an object header (version 1 or 2).
+    ///
+    /// Densely stored attributes are not read (see `ObjectHeader::load_dense_attributes`).
+    pub fn from_reader<R: Read>(mut reader: R, options: &ReaderOptions) -> Result<Self> {
+        let prefix = track!(ObjectHeaderPrefix::from_reader(&mut reader, options))?;
+        Ok(Self {
+            prefix,
+            dense_attributes: Vec::new(),
+        })
+    }
 
-    fn try_from(f: u8) -> Result<Self> {
-        match f {
-            0b0000_0000 => Ok(Endian::Little),
-            0b0000_0001 => Ok(Endian::Big),
-            0b0100_0000 => track_panic!(ErrorKind::InvalidFile, "Reserved endian bits"),
-            0b0100_0001 => Ok(Endian::Vax),
-            _ => track_panic!(ErrorKind::InvalidInput),
+    /// Reads the attributes stored in the fractal heap referred to by the attribute info message of the header.
+    ///
+    /// Afterwards, they are found by the attribute accessors like those stored in attribute messages.
+    pub fn load_dense_attributes<R: Read + Seek>(
+        &mut self,
+        mut reader: R,
+        options: &ReaderOptions,
+    ) -> Result<()> {
+        let attribute_info = match self.attribute_info_message() {
+            Some(m) => m.clone(),
+            None => return Ok(()),
+        };
+        let heap_address = if let Some(address) = attribute_info.fractal_heap_address() {
+            address
+        } else {
+            return Ok(());
+        };
+        track!(reader.seek_to(heap_address))?;
+        let heap = track!(
+            reader.with_offset("fractal heap", |r| { track!(FractalHeap::from_reader(r)) })
+        )?;
+
+        let b_tree_address = track_assert_some!(
+            attribute_info.name_index_b_tree_address(),
+            ErrorKind::InvalidFile
+        );
+        track!(reader.seek_to(b_tree_address))?;
+        let b_tree = track!(reader.with_offset("v2 B-tree header", |r| {
+            track!(BTreeV2::from_reader(r, options))
+        }))?;
+        track_assert_eq!(b_tree.record_type(), 8, ErrorKind::InvalidFile);
+
+        // Each record starts with the heap ID of the attribute followed by the flags of the message.
+        let heap_id_len = heap.heap_id_len();
+        let mut attributes = Vec::new();
+        for record in track!(b_tree.records(&mut reader))? {
+            track_assert!(record.len() > heap_id_len, ErrorKind::InvalidFile);
+            let flags = HeaderMessageFlags::from_bits_truncate(record[heap_id_len]);
+            track_assert!(
+                !flags.contains(HeaderMessageFlags::SHARED),
+                ErrorKind::Unsupported,
+                "Shared dense attributes"
+            );
+            let bytes = track!(heap.get_object(&mut reader, &record[..heap_id_len]))?;
+            attributes.push(track!(AttributeMessage::from_reader(&bytes[..]))?);
         }
+        self.dense_attributes = attributes;
+        Ok(())
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct FloatingPointDatatype {
-    size: u32,
+    /// Replaces the shared messages of the header with the messages they refer to.
+    pub fn resolve_shared_messages<R: Read + Seek>(
+        &mut self,
+        mut reader: R,
+        options: &ReaderOptions,
+    ) -> Result<()> {
+        track!(self.resolve_shared_messages_with_depth(&mut reader, options, 0))
+    }
 
-    endian: Endian,
-    low_padding_bit: u8,
-    high_padding_bit: u8,
-    internal_padding_bit: u8,
-    mantissa_norm: MantissaNorm,
-    sign_location: u8,
+    fn resolve_shared_messages_with_depth<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        options: &ReaderOptions,
+        depth: usize,
+    ) -> Result<()> {
+        for m in &mut self.prefix.messages {
+            let (kind, address) = if let Message::Shared { kind, address } = m.message {
+                (kind, address)
+            } else {
+                continue;
+            };
+            track_assert!(
+                depth < MAX_SHARED_MESSAGE_DEPTH,
+                ErrorKind::InvalidFile,
+                "Too deeply nested shared messages"
+            );
 
-    bit_offset: u16,
-    bit_precision: u16,
-    exponent_location: u8,
-    exponent_size: u8,
-    mantissa_location: u8,
-    mantissa_size: u8,
-    exponent_bias: u32,
-}
-impl FloatingPointDatatype {
-    pub fn decode<R: Read>(&self, mut reader: R) -> Result<f64> {
-        track_assert_eq!(self.endian, Endian::Little, ErrorKind::Unsupported);
-        track_assert_eq!(self.low_padding_bit, 0, ErrorKind::Unsupported);
-        track_assert_eq!(self.high_padding_bit, 0, ErrorKind::Unsupported);
-        track_assert_eq!(self.internal_padding_bit, 0, ErrorKind::Unsupported);
-        track_assert_eq!(
-            self.mantissa_norm,
-            MantissaNorm::ImpliedToBeSet,
-            ErrorKind::Unsupported
-        );
-        track_assert_eq!(self.sign_location, 31, ErrorKind::Unsupported);
-
-        track_assert_eq!(self.bit_offset, 0, ErrorKind::Unsupported);
-        track_assert_eq!(self.bit_precision, 32, ErrorKind::Unsupported);
-        track_assert_eq!(self.exponent_location, 23, ErrorKind::Unsupported);
-        track_assert_eq!(self.exponent_size, 8, ErrorKind::Unsupported);
-        track_assert_eq!(self.mantissa_location, 0, ErrorKind::Unsupported);
-        track_assert_eq!(self.mantissa_size, 23, ErrorKind::Unsupported);
-        track_assert_eq!(self.exponent_bias, 127, ErrorKind::Unsupported);
+            track!(reader.seek_to(address))?;
+            let mut header = track!(reader.with_offset("shared object header", |r| {
+                track!(Self::from_reader(r, options))
+            }))?;
+            track!(header.resolve_shared_messages_with_depth(reader, options, depth + 1))?;
 
-        track!(reader.read_f32()).map(f64::from)
+            let message = header
+                .prefix
+                .messages
+                .into_iter()
+                .map(|m| m.message)
+                .find(|m| m.kind() == kind);
+            m.message = track_assert_some!(
+                message,
+                ErrorKind::InvalidFile,
+                "No {} message in the shared object header at {:#x}",
+                message_type_name(kind),
+                address
+            );
+        }
+        Ok(())
     }
 
-    pub fn from_reader<R: Read>(bit_field: u32, size: u32, mut reader: R) -> Result<Self> {
-        let bit_offset = track!(reader.read_u16())?;
-        let bit_precision = track!(reader.read_u16())?;
-        let exponent_location = track!(reader.read_u8())?;
-        let exponent_size = track!(reader.read_u8())?;
-        let mantissa_location = track!(reader.read_u8())?;
-        let mantissa_size = track!(reader.read_u8())?;
-        let exponent_bias = track!(reader.read_u32())?;
-        track!(reader.skip(4))?;
+    /// Reads the data of the dataset described by this header.
+    pub fn get_data_object<R: Read + Seek>(
+        &self,
+        reader: R,
+        options: &ReaderOptions,
+    ) -> Result<DataObject> {
+        track!(self.get_data_object_with(reader, options, &mut Vec::new()))
+    }
 
-        Ok(Self {
-            size,
+    /// Reads the data of the dataset, and appends the offsets of the chunks
+    /// that were replaced with the fill value due to decoding failures to `corrupt_chunks`.
+    ///
+    /// Chunks are replaced only if `ReaderOptions::skip_corrupt_chunks` is enabled.
+    pub(crate) fn get_data_object_with<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        options: &ReaderOptions,
+        corrupt_chunks: &mut Vec<Vec<u64>>,
+    ) -> Result<DataObject> {
+        let bytes = track!(self.read_data_bytes(&mut reader, options, corrupt_chunks))?;
+        let dimensions = track!(self.dimensions())?
+            .iter()
+            .map(|&d| d as usize)
+            .collect::<Vec<_>>();
+        let datatype = track!(self.datatype())?;
 
-            endian: track!(Endian::try_from((bit_field & 0b0100_0001) as u8))?,
-            low_padding_bit: ((bit_field >> 1) & 1) as u8,
-            high_padding_bit: ((bit_field >> 2) & 1) as u8,
-            internal_padding_bit: ((bit_field >> 3) & 1) as u8,
-            mantissa_norm: track!(MantissaNorm::try_from(((bit_field >> 4) & 0b11) as u8))?,
-            sign_location: (bit_field >> 8) as u8,
+        track!(datatype.decode_array(bytes, dimensions))
+    }
 
-            bit_offset,
-            bit_precision,
-            exponent_location,
-            exponent_size,
-            mantissa_location,
-            mantissa_size,
-            exponent_bias,
-        })
+    /// Returns the messages in the header as they were read from the file.
+    pub fn header_messages(&self) -> &[HeaderMessage] {
+        &self.prefix.messages
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct FixedPointDatatype {
-    bit_field: u32,
-    size: u32,
+    /// Returns the size of the messages of the header in bytes (excluding the prefix).
+    ///
+    /// For version 2 headers, this is the size of the first chunk.
+    pub fn header_size(&self) -> u32 {
+        self.prefix.object_header_size
+    }
 
-    bit_offset: u16,
-    bit_precision: u16,
-}
-impl FixedPointDatatype {
-    pub fn from_reader<R: Read>(bit_field: u32, size: u32, mut reader: R) -> Result<Self> {
-        let bit_offset = track!(reader.read_u16())?;
-        let bit_precision = track!(reader.read_u16())?;
-        track!(reader.skip(4))?;
+    /// Returns the number of hard links to the object.
+    ///
+    /// A value greater than one means that the object is shared by several groups.
+    /// Version 2 headers store the count in an object reference count message,
+    /// which is omitted if the count is one.
+    pub fn reference_count(&self) -> u32 {
+        self.prefix.object_reference_count
+    }
 
-        Ok(Self {
-            bit_field,
-            size,
+    /// Returns `true` if the creation order of the attributes of the object is tracked.
+    ///
+    /// Only version 2 headers can track it, so `false` is always returned for version 1 headers.
+    pub fn is_attribute_creation_order_tracked(&self) -> bool {
+        self.prefix.creation_order_tracked
+    }
 
-            bit_offset,
-            bit_precision,
-        })
+    /// Returns an iterator over the messages in the header together with their type codes.
+    pub fn messages(&self) -> impl Iterator<Item = (u16, &Message)> {
+        self.prefix
+            .messages
+            .iter()
+            .map(|m| (m.message.kind(), &m.message))
     }
-}
 
-/// type=0x03
-#[derive(Debug, Clone)]
-pub enum DatatypeMessage {
-    FixedPoint(FixedPointDatatype),
-    FloatingPoint(FloatingPointDatatype),
-    // Time,
-    // String,
-    // BitField,
-    // Opaque,
-    // Compound,
-    // Reference,
-    // Enumerated,
-    // VariableLength,
-    // Array,
-}
-impl DatatypeMessage {
-    // pub fn decode<R: Read>(&self, reader: R) -> Result<DataItem> {
-    //     match self {
-    //         DatatypeMessage::FloatingPoint(t) => track!(t.decode(reader)).map(DataItem::Float),
-    //         _ => track_panic!(ErrorKind::Unsupported),
-    //     }
-    // }
+    /// Returns `true` if the header describes a dataset (i.e., it has a data layout message).
+    pub fn is_dataset(&self) -> bool {
+        self.prefix
+            .messages
+            .iter()
+            .any(|m| matches!(m.message, Message::DataLayout(_)))
+    }
 
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        let class_and_version = track!(reader.read_u8())?;
-        let version = class_and_version >> 4;
-        let class = track!(DatatypeClass::try_from(class_and_version & 0b0000_1111))?;
-        track_assert_eq!(version, 1, ErrorKind::Unsupported);
+    /// Returns `true` if the header describes a group (i.e., it has a symbol table or link info message).
+    pub fn is_group(&self) -> bool {
+        self.prefix
+            .messages
+            .iter()
+            .any(|m| matches!(m.message, Message::SymbolTable(_) | Message::LinkInfo(_)))
+    }
 
-        let bit_field = track!(reader.read_u24())?;
-        let size = track!(reader.read_u32())?;
+    /// Returns the dimensions of the dataspace of the dataset.
+    pub fn dimensions(&self) -> Result<&[u64]> {
+        for m in &self.prefix.messages {
+            if let Message::Dataspace(m) = &m.message {
+                return Ok(&m.dimension_sizes);
+            }
+        }
+        track_panic!(ErrorKind::Other);
+    }
 
-        match class {
-            DatatypeClass::FixedPoint => {
-                track!(FixedPointDatatype::from_reader(bit_field, size, reader))
-                    .map(DatatypeMessage::FixedPoint)
+    /// Returns the maximum dimensions of the dataspace of the dataset.
+    ///
+    /// `None` elements are unlimited dimensions.
+    /// If the maximum dimensions aren't stored, they are the same as the current dimensions.
+    pub fn max_dimensions(&self) -> Result<Vec<Option<u64>>> {
+        for m in &self.prefix.messages {
+            if let Message::Dataspace(m) = &m.message {
+                return Ok(match m.max_dimensions() {
+                    Some(max) => max.to_vec(),
+                    None => m.dimension_sizes.iter().map(|&d| Some(d)).collect(),
+                });
             }
-            DatatypeClass::FloatingPoint => {
-                track!(FloatingPointDatatype::from_reader(bit_field, size, reader))
-                    .map(DatatypeMessage::FloatingPoint)
+        }
+        track_panic!(ErrorKind::Other);
+    }
+
+    /// Returns the datatype of the dataset.
+    pub fn datatype(&self) -> Result<DatatypeMessage> {
+        for m in &self.prefix.messages {
+            if let Message::Datatype(m) = &m.message {
+                return Ok(m.clone());
             }
-            _ => track_panic!(ErrorKind::Unsupported; class),
         }
+        track_panic!(ErrorKind::Other);
     }
-}
 
-/// type=0x05
-#[derive(Debug, Clone)]
-pub struct FillValueMessage {
-    space_allocation_time: u8,
-    fill_value_write_time: u8,
-    fill_value: Option<Vec<u8>>,
-}
-impl FillValueMessage {
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        let version = track!(reader.read_u8())?;
-        track_assert_eq!(version, 2, ErrorKind::Unsupported);
+    /// Returns the datatype and the shape of the attribute named `name`.
+    ///
+    /// The value of the attribute isn't decoded.
+    /// If there is no such attribute, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn attribute_info(&self, name: &str) -> Result<(DatatypeMessage, Vec<usize>)> {
+        let attribute = track_assert_some!(
+            self.attribute(name),
+            ErrorKind::InvalidInput,
+            "No such attribute: {:?}",
+            name
+        );
+        let shape = attribute
+            .dataspace
+            .dimension_sizes
+            .iter()
+            .map(|&d| d as usize)
+            .collect();
+        Ok((attribute.datatype.clone(), shape))
+    }
 
-        let space_allocation_time = track!(reader.read_u8())?;
-        let fill_value_write_time = track!(reader.read_u8())?;
-        let fill_value_defined = track!(reader.read_u8())?;
-        let fill_value = if fill_value_defined == 1 {
-            let size = track!(reader.read_u32())?;
-            let fill_value = track!(reader.read_vec(size as usize))?;
-            Some(fill_value)
+    /// Reads the value of the attribute named `name`.
+    ///
+    /// Scalar attributes are returned as they are, and the elements of the others
+    /// as a flat (row-major) `ScalarValue::Array`.
+    /// This is the inverse of `Hdf5Writer::attach_attribute`.
+    /// If there is no such attribute, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn read_attribute(&self, name: &str) -> Result<ScalarValue> {
+        let attribute = track_assert_some!(
+            self.attribute(name),
+            ErrorKind::InvalidInput,
+            "No such attribute: {:?}",
+            name
+        );
+        // The data may be followed by the padding of the message, which is ignored.
+        let mut data = &attribute.data[..];
+        let value = if attribute.dataspace.dimension_sizes.is_empty() {
+            track!(attribute.datatype.decode_scalar(&mut data); name)?
         } else {
-            None
+            let count = attribute
+                .dataspace
+                .dimension_sizes
+                .iter()
+                .try_fold(1u64, |n, &d| n.checked_mul(d));
+            let count = track_assert_some!(count, ErrorKind::InvalidFile; name);
+            let element_size = u64::from(attribute.datatype.size());
+            track_assert!(
+                count.saturating_mul(element_size) <= data.len() as u64,
+                ErrorKind::InvalidFile;
+                name, count
+            );
+            let items = (0..count)
+                .map(|_| track!(attribute.datatype.decode_scalar(&mut data)))
+                .collect::<Result<Vec<_>>>()?;
+            ScalarValue::Array(items)
         };
-        Ok(Self {
-            space_allocation_time,
-            fill_value_write_time,
-            fill_value,
+        Ok(value)
+    }
+
+    /// Returns the link info message of the header if it is a new-style group.
+    pub fn link_info(&self) -> Option<&LinkInfoMessage> {
+        self.prefix.messages.iter().find_map(|m| {
+            if let Message::LinkInfo(m) = &m.message {
+                Some(m)
+            } else {
+                None
+            }
         })
     }
-}
 
-#[derive(Debug, Clone)]
-pub enum Layout {
-    Contiguous { address: u64, size: u64 },
-}
-impl Layout {
-    pub fn from_reader<R: Read>(class: u8, mut reader: R) -> Result<Self> {
-        match class {
-            0 => track_panic!(ErrorKind::Unsupported),
-            1 => {
-                let address = track!(reader.read_u64())?;
-                let size = track!(reader.read_u64())?;
-                Ok(Layout::Contiguous { address, size })
+    /// Returns the group info message of the header if it is a new-style group.
+    pub fn group_info(&self) -> Option<&GroupInfoMessage> {
+        self.prefix.messages.iter().find_map(|m| {
+            if let Message::GroupInfo(m) = &m.message {
+                Some(m)
+            } else {
+                None
             }
-            2 => track_panic!(ErrorKind::Unsupported),
-            _ => track_panic!(ErrorKind::InvalidFile, "Unknown layout class: {}", class),
-        }
+        })
     }
-}
 
-/// type=0x08
-#[derive(Debug, Clone)]
-pub struct DataLayoutMessage {
-    layout: Layout,
-}
-impl DataLayoutMessage {
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        let version = track!(reader.read_u8())?;
-        track_assert_eq!(version, 3, ErrorKind::Unsupported);
+    /// Returns the link messages of the header (i.e., the members of a new-style group).
+    pub fn links(&self) -> impl Iterator<Item = &LinkMessage> {
+        self.prefix.messages.iter().filter_map(|m| {
+            if let Message::Link(m) = &m.message {
+                Some(m)
+            } else {
+                None
+            }
+        })
+    }
 
-        let layout_class = track!(reader.read_u8())?;
-        let layout = track!(Layout::from_reader(layout_class, &mut reader))?;
-        let _padding = track!(reader.read_all())?;
-        Ok(Self { layout })
+    pub(crate) fn attribute(&self, name: &str) -> Option<&AttributeMessage> {
+        self.attributes().find(|a| a.name == name)
     }
-}
 
-/// type=0x11
-#[derive(Debug, Clone)]
-pub struct SymbolTableMessage {
-    pub b_tree_address: u64,
-    pub local_heap_address: u64,
-}
-impl SymbolTableMessage {
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        Ok(Self {
-            b_tree_address: track!(reader.read_u64())?,
-            local_heap_address: track!(reader.read_u64())?,
+    fn attributes(&self) -> impl Iterator<Item = &AttributeMessage> {
+        self.prefix
+            .messages
+            .iter()
+            .filter_map(|m| {
+                if let Message::Attribute(m) = &m.message {
+                    Some(m)
+                } else {
+                    None
+                }
+            })
+            .chain(&self.dense_attributes)
+    }
+
+    fn attribute_info_message(&self) -> Option<&AttributeInfoMessage> {
+        self.prefix.messages.iter().find_map(|m| {
+            if let Message::AttributeInfo(m) = &m.message {
+                Some(m)
+            } else {
+                None
+            }
         })
     }
-}
 
-/// type=0x12
-#[derive(Debug, Clone)]
-pub struct ObjectModificationTimeMessage {
-    unixtime_seconds: u32,
-}
-impl ObjectModificationTimeMessage {
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        let version = track!(reader.read_u8())?;
-        track_assert_eq!(version, 1, ErrorKind::Unsupported);
-        track!(reader.skip(3))?;
+    /// Returns the filter pipeline message of the header, if any.
+    pub fn filter_pipeline(&self) -> Option<&FilterPipelineMessage> {
+        self.prefix.messages.iter().find_map(|m| {
+            if let Message::FilterPipeline(m) = &m.message {
+                Some(m)
+            } else {
+                None
+            }
+        })
+    }
 
-        let unixtime_seconds = track!(reader.read_u32())?;
-        Ok(Self { unixtime_seconds })
+    /// Reads the bytes of the data of the dataset (with its filters decoded).
+    pub fn get_data_bytes<R: Read + Seek>(
+        &self,
+        reader: R,
+        options: &ReaderOptions,
+    ) -> Result<Vec<u8>> {
+        track!(self.read_data_bytes(reader, options, &mut Vec::new()))
     }
-}
 
-#[derive(Debug, Clone)]
-pub enum Message {
-    Nil(NilMessage),
-    Dataspace(DataspaceMessage),
-    // LinkInfo,
-    Datatype(DatatypeMessage),
-    // FillValueOld,
-    FillValue(FillValueMessage),
-    // Link,
-    // ExternalDataFile,
-    DataLayout(DataLayoutMessage),
-    // Bogus,
-    // GroupInfo,
-    // FilePipeline,
-    // Attribute,
-    // ObjectComment,
-    // ObjectModificationTimeOld,
-    // SharedMessageTable,
-    // ObjectHeaderContinuation,
-    SymbolTable(SymbolTableMessage),
-    ObjectModificationTime(ObjectModificationTimeMessage),
-    // BTreeKValues,
-    // DriverInfo,
-    // AttributeInfo,
-    // ObjectReferenceCount,
-}
+    fn read_data_bytes<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        options: &ReaderOptions,
+        corrupt_chunks: &mut Vec<Vec<u64>>,
+    ) -> Result<Vec<u8>> {
+        match *track!(self.layout())? {
+            Layout::Compact { ref data } => Ok(data.clone()),
+            Layout::Contiguous { size: 0, .. } => {
+                // Empty datasets may have no storage allocated (i.e., the address is undefined).
+                Ok(Vec::new())
+            }
+            Layout::Contiguous {
+                address: UNDEFINED_ADDRESS,
+                ..
+            } => {
+                // The data has never been written, so every element holds the fill value.
+                let fill_value = track!(self.resolved_fill_value())?;
+                let dimensions = track!(self.dimensions())?;
+                let count = dimensions.iter().try_fold(1u64, |n, &d| n.checked_mul(d));
+                let count = track_assert_some!(count, ErrorKind::InvalidFile; dimensions);
+                let size = count.checked_mul(fill_value.len() as u64);
+                let size = track_assert_some!(size, ErrorKind::InvalidFile; dimensions);
+                track!(options.check_allocation_size(size))?;
+                Ok(fill_value.repeat(count as usize))
+            }
+            Layout::Contiguous { address, size } => {
+                track!(options.check_allocation_size(size))?;
+                track!(reader.seek_to(address))?;
+                let cap = options.get_preallocation_limit();
+                let data = track!(reader
+                    .with_offset("raw data", |r| track!(r.read_exact_vec(size as usize, cap))))?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use trackable::result::TopLevelResult;
+                // Filters such as shuffle and Fletcher32 may be applied to the whole contiguous data.
+                match self.filter_pipeline() {
+                    None => Ok(data),
+                    Some(pipeline) => {
+                        let element_size = u64::from(track!(self.datatype())?.size());
+                        let decoded_size = track!(self.dimensions())?
+                            .iter()
+                            .try_fold(element_size, |n, &d| n.checked_mul(d));
+                        let decoded_size = track_assert_some!(decoded_size, ErrorKind::InvalidFile);
+                        track!(pipeline.decode(&options.filters, data, 0, decoded_size as usize))
+                    }
+                }
+            }
+            Layout::Chunked {
+                ref chunk_dimensions,
+                element_size,
+                ..
+            } => track!(self.get_chunked_data_bytes(
+                reader,
+                options,
+                chunk_dimensions,
+                element_size as usize,
+                corrupt_chunks
+            )),
+            Layout::Virtual { .. } => track_panic!(
+                ErrorKind::Unsupported,
+                "The data of virtual datasets can't be read (see `virtual_mappings`)"
+            ),
+        }
+    }
 
-    #[test]
-    fn floating_point_decode_works() -> TopLevelResult {
-        let datatype = FloatingPointDatatype {
-            size: 4,
-            endian: Endian::Little,
-            low_padding_bit: 0,
-            high_padding_bit: 0,
-            internal_padding_bit: 0,
-            mantissa_norm: MantissaNorm::ImpliedToBeSet,
-            sign_location: 31,
-            bit_offset: 0,
-            bit_precision: 32,
-            exponent_location: 23,
-            exponent_size: 8,
-            mantissa_location: 0,
-            mantissa_size: 23,
-            exponent_bias: 127,
-        };
-        let bytes = [166, 73, 90, 67];
+    /// Reads the chunks of the dataset and assembles them into a row-major byte buffer.
+    fn get_chunked_data_bytes<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        options: &ReaderOptions,
+        chunk_dimensions: &[u32],
+        element_size: usize,
+        corrupt_chunks: &mut Vec<Vec<u64>>,
+    ) -> Result<Vec<u8>> {
+        let dimensions = track!(self.dimensions())?;
+        track_assert_eq!(
+            dimensions.len(),
+            chunk_dimensions.len(),
+            ErrorKind::InvalidFile
+        );
+        for &c in chunk_dimensions {
+            track_assert_ne!(c, 0, ErrorKind::InvalidFile);
+        }
 
-        let item = track!(datatype.decode(&bytes[..]))?;
-        assert_eq!(item, 218.28768920898438);
+        let total_size = dimensions
+            .iter()
+            .try_fold(element_size as u64, |n, &d| n.checked_mul(d));
+        let total_size = track_assert_some!(total_size, ErrorKind::InvalidFile; dimensions);
+        track!(options.check_allocation_size(total_size))?;
+        let fill_value = track!(self.resolved_fill_value())?;
+        track_assert_eq!(fill_value.len(), element_size, ErrorKind::InvalidFile);
+        let mut bytes = fill_value.repeat(total_size as usize / element_size);
+
+        let chunk_dimensions = chunk_dimensions
+            .iter()
+            .map(|&c| u64::from(c))
+            .collect::<Vec<_>>();
+        for chunk in track!(self.chunks(&mut reader, options))? {
+            let result = track!(self.read_chunk(&mut reader, &chunk, options)).and_then(|data| {
+                track!(copy_chunk(
+                    &mut bytes,
+                    dimensions,
+                    &chunk_dimensions,
+                    &chunk.offset,
+                    &data,
+                    element_size
+                ))
+            });
+            match result {
+                // The region of the chunk still holds the fill value.
+                Err(_) if options.is_skipping_corrupt_chunks() => corrupt_chunks.push(chunk.offset),
+                result => track!(result; chunk.offset)?,
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Reads the given chunk and reverts the filters applied to it.
+    pub fn read_chunk<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        chunk: &ChunkInfo,
+        options: &ReaderOptions,
+    ) -> Result<Vec<u8>> {
+        track!(options.check_allocation_size(chunk.size))?;
+        track!(reader.seek_to(chunk.address))?;
+        let cap = options.get_preallocation_limit();
+        let data = track!(reader.with_offset("chunk", |r| track!(
+            r.read_exact_vec(chunk.size as usize, cap)
+        )))?;
+        match self.filter_pipeline() {
+            None => {
+                track_assert_eq!(chunk.filter_mask, 0, ErrorKind::InvalidFile);
+                Ok(data)
+            }
+            Some(pipeline) => {
+                let decoded_size = match *track!(self.layout())? {
+                    Layout::Chunked {
+                        ref chunk_dimensions,
+                        element_size,
+                        ..
+                    } => track!(chunk_size(chunk_dimensions, element_size))?,
+                    _ => track_panic!(ErrorKind::InvalidInput, "Not a chunked dataset"),
+                };
+                track!(pipeline.decode(
+                    &options.filters,
+                    data,
+                    chunk.filter_mask,
+                    decoded_size as usize
+                ))
+            }
+        }
+    }
+
+    /// Reads the element at the given flat (row-major) index of the dataset.
+    ///
+    /// Only the bytes of the element are read for unfiltered contiguous data,
+    /// and only the chunk containing the element is read for chunked data.
+    pub fn read_element<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        options: &ReaderOptions,
+        index: usize,
+    ) -> Result<ScalarValue> {
+        let dimensions = track!(self.dimensions())?;
+        let count = dimensions.iter().try_fold(1u64, |n, &d| n.checked_mul(d));
+        let count = track_assert_some!(count, ErrorKind::InvalidFile; dimensions);
+        track_assert!((index as u64) < count, ErrorKind::InvalidInput; index, count);
+
+        let datatype = track!(self.datatype())?;
+        let element_size = datatype.size() as usize;
+        let index = index as u64;
+        let offset = index.checked_mul(element_size as u64);
+        let offset = track_assert_some!(offset, ErrorKind::InvalidFile; index, element_size);
+        let bytes = match *track!(self.layout())? {
+            Layout::Compact { ref data } => {
+                let bytes = data
+                    .get(offset as usize..)
+                    .and_then(|data| data.get(..element_size));
+                track_assert_some!(bytes, ErrorKind::InvalidFile; index, data.len()).to_vec()
+            }
+            Layout::Contiguous {
+                address: UNDEFINED_ADDRESS,
+                ..
+            } => track!(self.resolved_fill_value())?,
+            Layout::Contiguous { address, size } if self.filter_pipeline().is_none() => {
+                track_assert!(
+                    offset.saturating_add(element_size as u64) <= size,
+                    ErrorKind::InvalidFile;
+                    index, size
+                );
+                let address =
+                    track_assert_some!(address.checked_add(offset), ErrorKind::InvalidFile);
+                track!(reader.seek_to(address))?;
+                track!(reader.with_offset("raw data", |r| track!(r.read_vec(element_size))))?
+            }
+            Layout::Contiguous { .. } => {
+                // Filtered data can only be decoded as a whole.
+                let data = track!(self.get_data_bytes(&mut reader, options))?;
+                let bytes = data
+                    .get(offset as usize..)
+                    .and_then(|data| data.get(..element_size));
+                track_assert_some!(bytes, ErrorKind::InvalidFile; index, data.len()).to_vec()
+            }
+            Layout::Chunked {
+                ref chunk_dimensions,
+                ..
+            } => {
+                track_assert_eq!(
+                    dimensions.len(),
+                    chunk_dimensions.len(),
+                    ErrorKind::InvalidFile
+                );
+                let mut coordinates = vec![0; dimensions.len()];
+                let mut rest = index;
+                for (c, &d) in coordinates.iter_mut().zip(dimensions).rev() {
+                    *c = rest % d;
+                    rest /= d;
+                }
+                let mut chunk_offset = Vec::with_capacity(coordinates.len());
+                let mut position = 0u64;
+                for (&c, &chunk_dimension) in coordinates.iter().zip(chunk_dimensions) {
+                    let chunk_dimension = u64::from(chunk_dimension);
+                    track_assert_ne!(chunk_dimension, 0, ErrorKind::InvalidFile);
+                    chunk_offset.push(c - c % chunk_dimension);
+                    let next = position
+                        .checked_mul(chunk_dimension)
+                        .and_then(|p| p.checked_add(c % chunk_dimension));
+                    position = track_assert_some!(next, ErrorKind::InvalidFile; index);
+                }
+
+                let chunks = track!(self.chunks(&mut reader, options))?;
+                match chunks
+                    .into_iter()
+                    .find(|chunk| chunk.offset == chunk_offset)
+                {
+                    // Chunks that have never been written hold the fill value.
+                    None => track!(self.resolved_fill_value())?,
+                    Some(chunk) => {
+                        let data = track!(self.read_chunk(&mut reader, &chunk, options))?;
+                        let start = (position as usize).checked_mul(element_size);
+                        let bytes = start
+                            .and_then(|start| data.get(start..))
+                            .and_then(|data| data.get(..element_size));
+                        track_assert_some!(bytes, ErrorKind::InvalidFile; index, data.len())
+                            .to_vec()
+                    }
+                }
+            }
+            Layout::Virtual { .. } => track_panic!(
+                ErrorKind::Unsupported,
+                "The data of virtual datasets can't be read (see `virtual_mappings`)"
+            ),
+        };
+        track!(datatype.decode_scalar(&bytes[..]))
+    }
+
+    /// Returns the fill value of the dataset decoded with its datatype.
+    ///
+    /// If no fill value is defined, `Ok(None)` will be returned.
+    pub fn fill_value_scalar(&self) -> Result<Option<ScalarValue>> {
+        let bytes = if let Some(bytes) = self.fill_value() {
+            bytes
+        } else {
+            return Ok(None);
+        };
+        let datatype = track!(self.datatype())?;
+        track_assert_eq!(
+            bytes.len(),
+            datatype.size() as usize,
+            ErrorKind::InvalidFile
+        );
+        track!(datatype.decode_scalar(bytes)).map(Some)
+    }
+
+    /// Returns the raw bytes of the fill value that elements never written hold.
+    ///
+    /// The fill value message (type 0x05) takes precedence over the old one (type 0x04),
+    /// and if neither defines a fill value, zeros of the size of the datatype will be returned.
+    pub fn resolved_fill_value(&self) -> Result<Vec<u8>> {
+        let size = track!(self.datatype())?.size() as usize;
+        match self.fill_value() {
+            Some(bytes) => {
+                track_assert_eq!(bytes.len(), size, ErrorKind::InvalidFile);
+                Ok(bytes.to_vec())
+            }
+            None => Ok(vec![0; size]),
+        }
+    }
+
+    fn fill_value(&self) -> Option<&[u8]> {
+        let new = self.prefix.messages.iter().find_map(|m| {
+            if let Message::FillValue(m) = &m.message {
+                m.fill_value.as_deref()
+            } else {
+                None
+            }
+        });
+        new.or_else(|| {
+            self.prefix.messages.iter().find_map(|m| {
+                if let Message::OldFillValue(m) = &m.message {
+                    Some(&m.fill_value[..])
+                } else {
+                    None
+                }
+            })
+        })
+    }
+
+    /// Returns the data layout of the dataset.
+    pub fn layout(&self) -> Result<&Layout> {
+        for m in &self.prefix.messages {
+            if let Message::DataLayout(m) = &m.message {
+                return Ok(&m.layout);
+            }
+        }
+        track_panic!(ErrorKind::Other, "Not a data object");
+    }
+
+    /// Reads the mappings from source datasets of the virtual dataset.
+    ///
+    /// If the dataset isn't virtual, an empty vector will be returned.
+    pub fn virtual_mappings<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        options: &ReaderOptions,
+    ) -> Result<Vec<VirtualMapping>> {
+        let (heap_address, index) = match *track!(self.layout())? {
+            Layout::Virtual {
+                heap_address,
+                index,
+            } => (heap_address, index),
+            _ => return Ok(Vec::new()),
+        };
+        track!(reader.seek_to(heap_address))?;
+        let collection = track!(reader.with_offset("global heap collection", |r| {
+            track!(GlobalHeapCollection::from_reader(r))
+        }))?;
+        let object = track_assert_some!(
+            collection.object(index),
+            ErrorKind::InvalidFile;
+            heap_address,
+            index
+        );
+        track!(VirtualMapping::read_all(object, options))
+    }
+
+    /// Reads the dataset region references stored in the dataset (in row-major order).
+    ///
+    /// `None` is returned for null references.
+    /// If the datatype of the dataset isn't a region reference, an `ErrorKind::InvalidInput` error will be returned.
+    pub fn region_references<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        options: &ReaderOptions,
+    ) -> Result<Vec<Option<RegionReference>>> {
+        let datatype = track!(self.datatype())?;
+        track_assert!(
+            matches!(datatype, DatatypeMessage::Reference(ref t) if t.is_region_reference()),
+            ErrorKind::InvalidInput,
+            "Not a region reference datatype: {}",
+            datatype
+        );
+        // Each element is a global heap ID (collection address and object index).
+        track_assert_eq!(datatype.size(), 12, ErrorKind::InvalidFile);
+        let data = track!(self.get_data_bytes(&mut reader, options))?;
+
+        let mut collections = HashMap::new();
+        let mut references = Vec::new();
+        for mut element in data.chunks(12) {
+            let collection_address = track!(element.read_u64())?;
+            let index = track!(element.read_u32())?;
+            if collection_address == 0 || collection_address == UNDEFINED_ADDRESS {
+                references.push(None);
+                continue;
+            }
+            let collection = match collections.entry(collection_address) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => {
+                    track!(reader.seek_to(collection_address))?;
+                    e.insert(track!(reader.with_offset("global heap collection", |r| {
+                        track!(GlobalHeapCollection::from_reader(r))
+                    }))?)
+                }
+            };
+            let object = track_assert_some!(
+                collection.object(index),
+                ErrorKind::InvalidFile;
+                collection_address,
+                index
+            );
+            references.push(Some(track!(RegionReference::from_bytes(object))?));
+        }
+        Ok(references)
+    }
+
+    /// Returns the chunks of the dataset.
+    ///
+    /// If the dataset isn't chunked, an empty vector will be returned.
+    pub fn chunks<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        options: &ReaderOptions,
+    ) -> Result<Vec<ChunkInfo>> {
+        if let Layout::Chunked {
+            address,
+            ref chunk_dimensions,
+            element_size,
+            ref index,
+        } = *track!(self.layout())?
+        {
+            if address == UNDEFINED_ADDRESS {
+                // No chunks have been allocated yet.
+                return Ok(Vec::new());
+            }
+            let chunk_size = track!(chunk_size(chunk_dimensions, element_size))?;
+            match *index {
+                ChunkIndex::BTreeV1 => {}
+                ChunkIndex::SingleChunk {
+                    filtered_size,
+                    filter_mask,
+                } => {
+                    return Ok(vec![ChunkInfo {
+                        offset: vec![0; chunk_dimensions.len()],
+                        address,
+                        size: filtered_size.unwrap_or(chunk_size),
+                        filter_mask,
+                    }]);
+                }
+                ChunkIndex::Implicit => {
+                    let dimensions = track!(self.dimensions())?;
+                    return track!(implicit_chunks(
+                        dimensions,
+                        chunk_dimensions,
+                        address,
+                        chunk_size,
+                        options
+                    ));
+                }
+            }
+            track!(reader.seek_to(address))?;
+            let dimensionality = chunk_dimensions.len() + 1;
+            let node = track!(reader.with_offset("chunk B-tree node", |r| {
+                track!(ChunkBTreeNode::from_reader(r, dimensionality))
+            }))?;
+            track!(node.chunks(reader))
+        } else {
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Lists the chunks of an implicit chunk index.
+///
+/// The address of each chunk is computed from its row-major position in the grid of chunks.
+fn implicit_chunks(
+    dimensions: &[u64],
+    chunk_dimensions: &[u32],
+    address: u64,
+    chunk_size: u64,
+    options: &ReaderOptions,
+) -> Result<Vec<ChunkInfo>> {
+    track_assert_eq!(
+        dimensions.len(),
+        chunk_dimensions.len(),
+        ErrorKind::InvalidFile
+    );
+    let mut grid = Vec::with_capacity(dimensions.len());
+    for (&d, &c) in dimensions.iter().zip(chunk_dimensions) {
+        track_assert_ne!(c, 0, ErrorKind::InvalidFile);
+        let c = u64::from(c);
+        grid.push(d / c + u64::from(d % c != 0));
+    }
+
+    let count = grid.iter().try_fold(1u64, |n, &g| n.checked_mul(g));
+    let count = track_assert_some!(count, ErrorKind::InvalidFile; grid);
+    let info_size = std::mem::size_of::<ChunkInfo>() + grid.len() * std::mem::size_of::<u64>();
+    let total_size = count.checked_mul(info_size as u64);
+    let total_size = track_assert_some!(total_size, ErrorKind::InvalidFile; count);
+    track!(options.check_allocation_size(total_size))?;
+    let mut chunks = Vec::with_capacity(count as usize);
+    for i in 0..count {
+        let mut offset = vec![0; grid.len()];
+        let mut rest = i;
+        for ((o, &n), &c) in offset.iter_mut().zip(&grid).zip(chunk_dimensions).rev() {
+            *o = (rest % n) * u64::from(c);
+            rest /= n;
+        }
+        let chunk_address = track_assert_some!(
+            i.checked_mul(chunk_size)
+                .and_then(|n| n.checked_add(address)),
+            ErrorKind::InvalidFile
+        );
+        chunks.push(ChunkInfo {
+            offset,
+            address: chunk_address,
+            size: chunk_size,
+            filter_mask: 0,
+        });
+    }
+    Ok(chunks)
+}
+
+/// Returns the size of a chunk in bytes (before any filters are applied).
+fn chunk_size(chunk_dimensions: &[u32], element_size: u32) -> Result<u64> {
+    let size = chunk_dimensions
+        .iter()
+        .try_fold(u64::from(element_size), |n, &d| n.checked_mul(u64::from(d)));
+    Ok(track_assert_some!(size, ErrorKind::InvalidFile; chunk_dimensions))
+}
+
+/// Copies the decoded `chunk` located at `offset` into the row-major buffer `dst` of the dataset.
+///
+/// Edge chunks may extend beyond the dataset, and the elements outside of it are discarded.
+fn copy_chunk(
+    dst: &mut [u8],
+    dimensions: &[u64],
+    chunk_dimensions: &[u64],
+    offset: &[u64],
+    chunk: &[u8],
+    element_size: usize,
+) -> Result<()> {
+    let chunk_len = chunk_dimensions.iter().product::<u64>() as usize * element_size;
+    track_assert_eq!(chunk.len(), chunk_len, ErrorKind::InvalidFile);
+    track_assert_eq!(offset.len(), dimensions.len(), ErrorKind::InvalidFile);
+    for ((&o, &c), &d) in offset.iter().zip(chunk_dimensions).zip(dimensions) {
+        track_assert!(o % c == 0 && o < d, ErrorKind::InvalidFile; offset, dimensions);
+    }
+
+    // Each run is a row along the fastest-varying (last) dimension.
+    let rank = dimensions.len();
+    let last = rank - 1;
+    let run_len = chunk_dimensions[last] as usize * element_size;
+    let copy_len =
+        chunk_dimensions[last].min(dimensions[last] - offset[last]) as usize * element_size;
+    let mut index = vec![0; last];
+    for run in chunk.chunks_exact(run_len) {
+        let inside = (0..last).all(|i| offset[i] + index[i] < dimensions[i]);
+        if inside {
+            let mut position = 0;
+            for i in 0..rank {
+                let coordinate = offset[i] + index.get(i).cloned().unwrap_or(0);
+                position = position * dimensions[i] + coordinate;
+            }
+            let start = position as usize * element_size;
+            dst[start..start + copy_len].copy_from_slice(&run[..copy_len]);
+        }
+
+        for i in (0..last).rev() {
+            index[i] += 1;
+            if index[i] < chunk_dimensions[i] {
+                break;
+            }
+            index[i] = 0;
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for ObjectHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "object header ({} messages, reference count {})",
+            self.prefix.messages.len(),
+            self.prefix.object_reference_count
+        )?;
+        for m in &self.prefix.messages {
+            write!(f, "\n  {}", m.message)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectHeaderPrefix {
+    messages: Vec<HeaderMessage>,
+    object_reference_count: u32,
+    object_header_size: u32,
+    creation_order_tracked: bool,
+}
+impl ObjectHeaderPrefix {
+    pub fn from_reader<R: Read>(mut reader: R, options: &ReaderOptions) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        if version == b'O' {
+            // Version 2 object headers start with the "OHDR" signature.
+            let mut signature = [0; 3];
+            track!(reader.read_bytes(&mut signature))?;
+            track_assert_eq!(&signature, b"HDR", ErrorKind::InvalidFile);
+            return track!(Self::from_reader_v2(reader, options));
+        }
+        track_assert_eq!(version, 1, ErrorKind::InvalidFile);
+
+        let _reserved = track!(reader.read_u8())?;
+        track_assert_eq!(_reserved, 0, ErrorKind::InvalidFile);
+
+        let header_message_count = track!(reader.read_u16())?;
+        let object_reference_count = track!(reader.read_u32())?;
+        let object_header_size = track!(reader.read_u32())?;
+
+        // Header messages are aligned on 8-byte boundaries for version 1 object headers.
+        track!(reader.skip(4))?;
+
+        // The header is read as a whole so that a truncated one can be reported as such (instead of as an I/O error).
+        let mut block = Vec::new();
+        track!(reader
+            .take(u64::from(object_header_size))
+            .read_to_end(&mut block)
+            .map_err(Error::from))?;
+        let available = block.len();
+        let truncated = available < object_header_size as usize;
+
+        let mut reader = &block[..];
+        let mut messages = Vec::new();
+        for i in 0..header_message_count {
+            match HeaderMessage::from_reader(&mut reader, options) {
+                Ok(message) => messages.push(message),
+                Err(_) if truncated => track_panic!(
+                    ErrorKind::InvalidFile,
+                    "Truncated object header: only {} of {} messages could be read ({} of {} bytes available)",
+                    i,
+                    header_message_count,
+                    available,
+                    object_header_size
+                ),
+                Err(e) => return Err(track!(e)),
+            }
+        }
+        track_assert!(
+            !truncated,
+            ErrorKind::InvalidFile,
+            "Truncated object header: {} of {} bytes available",
+            available,
+            object_header_size
+        );
+        track_assert_eq!(reader.len(), 0, ErrorKind::InvalidFile; object_header_size, messages);
+
+        Ok(Self {
+            messages,
+            object_reference_count,
+            object_header_size,
+            creation_order_tracked: false,
+        })
+    }
+
+    /// Reads the rest of a version 2 object header (following the signature).
+    ///
+    /// The optional fields are present only if the corresponding flags are set,
+    /// so "minimized" headers (without times and attribute storage thresholds) are read correctly.
+    ///
+    /// https://support.hdfgroup.org/HDF5/doc/H5.format.html#V2ObjectHeaderPrefix
+    fn from_reader_v2<R: Read>(reader: R, options: &ReaderOptions) -> Result<Self> {
+        // The checksum covers everything from the signature.
+        let mut reader = RecordingReader::new(reader, b"OHDR".to_vec());
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 2, ErrorKind::InvalidFile);
+
+        let flags = track!(reader.read_u8())?;
+        if flags & OBJECT_HEADER_TIMES_STORED != 0 {
+            // Access, modification, change and birth times.
+            track!(reader.skip(16))?;
+        }
+        if flags & OBJECT_HEADER_ATTRIBUTE_PHASE_CHANGE_STORED != 0 {
+            // Maximum number of compact attributes and minimum number of dense attributes.
+            track!(reader.skip(4))?;
+        }
+        let chunk_size = track!(reader.read_uint(1 << (flags & 0b0000_0011)))?;
+        track_assert!(chunk_size <= u64::from(u32::MAX), ErrorKind::InvalidFile; chunk_size);
+
+        let creation_order_tracked = flags & OBJECT_HEADER_CREATION_ORDER_TRACKED != 0;
+        let message_prefix_size = if creation_order_tracked { 6 } else { 4 };
+        let mut chunk = reader.by_ref().take(chunk_size);
+        let mut messages = Vec::new();
+        // The space at the end of the chunk which is too small for a message is a gap.
+        while chunk.limit() >= message_prefix_size {
+            messages.push(track!(HeaderMessage::from_reader_v2(
+                &mut chunk,
+                creation_order_tracked,
+                options
+            ))?);
+        }
+        track!(chunk.skip(chunk.limit() as usize))?;
+        let checksum = track!(reader.read_u32())?;
+        if options.is_verifying_checksums() {
+            let bytes = reader.recorded();
+            track!(verify_checksum(&bytes[..bytes.len() - 4], checksum))?;
+        }
+
+        let object_reference_count = messages
+            .iter()
+            .find_map(|m| match &m.message {
+                Message::ObjectReferenceCount(m) => Some(m.reference_count),
+                _ => None,
+            })
+            .unwrap_or(1);
+        Ok(Self {
+            messages,
+            object_reference_count,
+            object_header_size: chunk_size as u32,
+            creation_order_tracked,
+        })
+    }
+}
+
+const OBJECT_HEADER_CREATION_ORDER_TRACKED: u8 = 0b0000_0100;
+const OBJECT_HEADER_ATTRIBUTE_PHASE_CHANGE_STORED: u8 = 0b0001_0000;
+const OBJECT_HEADER_TIMES_STORED: u8 = 0b0010_0000;
+
+/// Maximum depth of shared messages that refer to object headers containing shared messages.
+const MAX_SHARED_MESSAGE_DEPTH: usize = 8;
+
+bitflags! {
+    struct HeaderMessageFlags: u8 {
+        const CONSTANT = 0b0000_0001;
+        const SHARED = 0b0000_0010;
+        const UNSHARABLE = 0b0000_0100;
+        const CANNOT_WRITE_IF_UNKNOWN = 0b0000_1000;
+        const SET_5_BIT_IF_UNKNOWN = 0b0001_0000;
+        const UNKNOWN_BUT_MODIFIED = 0b0010_0000;
+        const SHARABLE = 0b0100_0000;
+        const FAIL_IF_UNKNOWN = 0b0100_0000;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderMessage {
+    #[allow(dead_code)] // Only shown in the `Debug` output.
+    flags: HeaderMessageFlags,
+    message: Message,
+    raw: Vec<u8>,
+}
+impl HeaderMessage {
+    /// Reads a header message.
+    ///
+    /// If `options` is lenient, messages of unknown or unsupported types are kept as `Message::Unknown`.
+    pub fn from_reader<R: Read>(mut reader: R, options: &ReaderOptions) -> Result<Self> {
+        let mut prefix = [0; 8];
+        track!(reader.read_bytes(&mut prefix))?;
+        let mut prefix_reader = &prefix[..];
+        let kind = track!(prefix_reader.read_u16())?;
+        let data_len = track!(prefix_reader.read_u16())?;
+        let flags = track!(prefix_reader.read_u8())?;
+
+        // The size includes the padding to the next 8-byte boundary,
+        // so an unaligned size would misplace all the following messages (the HDF5 library rejects it too).
+        track_assert!(
+            data_len % 8 == 0,
+            ErrorKind::InvalidFile,
+            "Unaligned message size: {}",
+            data_len
+        );
+        let raw = track!(reader.read_vec(usize::from(data_len)))?;
+        track!(Self::decode(kind, flags, &prefix, raw, options))
+    }
+
+    /// Reads a header message of a version 2 object header.
+    ///
+    /// Unlike version 1, the type is a single byte, there are no reserved bytes and the data isn't padded.
+    pub fn from_reader_v2<R: Read>(
+        mut reader: R,
+        creation_order_tracked: bool,
+        options: &ReaderOptions,
+    ) -> Result<Self> {
+        let mut prefix = [0; 6];
+        let prefix = if creation_order_tracked {
+            &mut prefix[..]
+        } else {
+            &mut prefix[..4]
+        };
+        track!(reader.read_bytes(prefix))?;
+        let mut prefix_reader = &prefix[..];
+        let kind = u16::from(track!(prefix_reader.read_u8())?);
+        let data_len = track!(prefix_reader.read_u16())?;
+        let flags = track!(prefix_reader.read_u8())?;
+        let raw = track!(reader.read_vec(usize::from(data_len)))?;
+        track!(Self::decode(kind, flags, prefix, raw, options))
+    }
+
+    fn decode(
+        kind: u16,
+        flags: u8,
+        prefix: &[u8],
+        raw: Vec<u8>,
+        options: &ReaderOptions,
+    ) -> Result<Self> {
+        let flags = HeaderMessageFlags::from_bits_truncate(flags);
+        let on_disk = [prefix, &raw[..]].concat();
+
+        let mut data = &raw[..];
+        if flags.contains(HeaderMessageFlags::SHARED) {
+            let address = track!(read_shared_message_address(&mut data); kind)?;
+            return Ok(Self {
+                flags,
+                message: Message::Shared { kind, address },
+                raw: on_disk,
+            });
+        }
+        let message = match Self::read_message(kind, &mut data) {
+            Err(e) if options.is_lenient() && *e.kind() == ErrorKind::Unsupported => {
+                Message::Unknown { kind, raw }
+            }
+            result => {
+                let message = track!(result, "while reading {} message", message_type_name(kind))?;
+                track_assert_eq!(data, b"", ErrorKind::Other);
+                message
+            }
+        };
+
+        Ok(Self {
+            flags,
+            message,
+            raw: on_disk,
+        })
+    }
+
+    /// Returns the parsed message.
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// Returns the bytes of the message as stored in the file.
+    ///
+    /// This includes the message prefix (type, size, flags and, for version 1 object headers, reserved bytes)
+    /// followed by the (padded) message data.
+    /// Shared messages keep the bytes of the reference, not those of the referenced message.
+    pub fn raw_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    fn read_message<R: Read>(kind: u16, mut reader: R) -> Result<Message> {
+        match kind {
+            0x00 => track!(NilMessage::from_reader(&mut reader)).map(Message::Nil),
+            0x01 => track!(DataspaceMessage::from_reader(&mut reader)).map(Message::Dataspace),
+            0x02 => {
+                let link_info = track!(LinkInfoMessage::from_reader(&mut reader))?;
+                let _padding = track!(reader.read_all())?;
+                Ok(Message::LinkInfo(link_info))
+            }
+            0x03 => {
+                let datatype = track!(DatatypeMessage::from_reader(&mut reader))?;
+                let _padding = track!(reader.read_all())?;
+                Ok(Message::Datatype(datatype))
+            }
+            0x04 => {
+                let fill_value = track!(OldFillValueMessage::from_reader(&mut reader))?;
+                let _padding = track!(reader.read_all())?;
+                Ok(Message::OldFillValue(fill_value))
+            }
+            0x05 => {
+                let fill_value = track!(FillValueMessage::from_reader(&mut reader))?;
+                let _padding = track!(reader.read_all())?;
+                Ok(Message::FillValue(fill_value))
+            }
+            0x06 => {
+                let link = track!(LinkMessage::from_reader(&mut reader))?;
+                let _padding = track!(reader.read_all())?;
+                Ok(Message::Link(link))
+            }
+            0x08 => track!(DataLayoutMessage::from_reader(&mut reader)).map(Message::DataLayout),
+            0x0A => {
+                let group_info = track!(GroupInfoMessage::from_reader(&mut reader))?;
+                let _padding = track!(reader.read_all())?;
+                Ok(Message::GroupInfo(group_info))
+            }
+            0x0B => {
+                track!(FilterPipelineMessage::from_reader(&mut reader)).map(Message::FilterPipeline)
+            }
+            0x0C => track!(AttributeMessage::from_reader(&mut reader)).map(Message::Attribute),
+            0x11 => track!(SymbolTableMessage::from_reader(&mut reader)).map(Message::SymbolTable),
+            0x12 => track!(ObjectModificationTimeMessage::from_reader(&mut reader))
+                .map(Message::ObjectModificationTime),
+            0x15 => {
+                let attribute_info = track!(AttributeInfoMessage::from_reader(&mut reader))?;
+                let _padding = track!(reader.read_all())?;
+                Ok(Message::AttributeInfo(attribute_info))
+            }
+            0x16 => track!(ObjectReferenceCountMessage::from_reader(&mut reader))
+                .map(Message::ObjectReferenceCount),
+            _ => track_panic!(ErrorKind::Unsupported, "Message type: {}", kind),
+        }
+    }
+}
+
+/// Reads a shared message (i.e., a reference to a message stored in another object header)
+/// and returns the address of the object header containing the actual message.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#SharedMessages
+fn read_shared_message_address<R: Read>(mut reader: R) -> Result<u64> {
+    let version = track!(reader.read_u8())?;
+    let kind = track!(reader.read_u8())?;
+    match version {
+        1 => {
+            track!(reader.skip(6))?;
+        }
+        2 => {}
+        3 => track_assert_eq!(
+            kind,
+            2,
+            ErrorKind::Unsupported,
+            "Messages shared in the shared object header message heap"
+        ),
+        _ => track_panic!(
+            ErrorKind::Unsupported,
+            "Shared message version: {}",
+            version
+        ),
+    }
+    let address = track!(reader.read_u64())?;
+    let _padding = track!(reader.read_all())?;
+    Ok(address)
+}
+
+fn message_type_name(kind: u16) -> &'static str {
+    match kind {
+        0x00 => "NIL",
+        0x01 => "Dataspace",
+        0x02 => "Link Info",
+        0x03 => "Datatype",
+        0x04 => "Fill Value (old)",
+        0x05 => "Fill Value",
+        0x06 => "Link",
+        0x07 => "External Data Files",
+        0x08 => "Data Layout",
+        0x09 => "Bogus",
+        0x0A => "Group Info",
+        0x0B => "Data Storage - Filter Pipeline",
+        0x0C => "Attribute",
+        0x0D => "Object Comment",
+        0x0E => "Object Modification Time (old)",
+        0x0F => "Shared Message Table",
+        0x10 => "Object Header Continuation",
+        0x11 => "Symbol Table",
+        0x12 => "Object Modification Time",
+        0x13 => "B-tree 'K' Values",
+        0x14 => "Driver Info",
+        0x15 => "Attribute Info",
+        0x16 => "Object Reference Count",
+        _ => "unknown",
+    }
+}
+
+/// type=0x00
+#[derive(Debug, Clone)]
+pub struct NilMessage {}
+impl NilMessage {
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let _ = track!(reader.read_all())?;
+        Ok(Self {})
+    }
+}
+
+/// type=0x01
+#[derive(Debug, Clone)]
+pub struct DataspaceMessage {
+    dimension_sizes: Vec<u64>,
+
+    // `None` elements are unlimited dimensions.
+    dimension_max_sizes: Option<Vec<Option<u64>>>,
+}
+impl DataspaceMessage {
+    /// Returns the maximum sizes of the dimensions, if they are stored in the message.
+    ///
+    /// `None` elements stand for unlimited dimensions.
+    pub fn max_dimensions(&self) -> Option<&[Option<u64>]> {
+        self.dimension_max_sizes.as_deref()
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert!(version == 1 || version == 2, ErrorKind::Unsupported; version);
+
+        let dimensionality = track!(reader.read_u8())?;
+        let flags = track!(reader.read_u8())?;
+        if version == 1 {
+            track!(reader.skip(5))?;
+        } else {
+            // Dataspace type: 0=scalar, 1=simple, 2=null.
+            let _kind = track!(reader.read_u8())?;
+        }
+
+        let dimension_sizes = (0..dimensionality)
+            .map(|_| track!(reader.read_u64()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let dimension_max_sizes = if (flags & DATASPACE_MAX_DIMENSIONS_PRESENT) != 0 {
+            Some(
+                (0..dimensionality)
+                    .map(|_| {
+                        let size = track!(reader.read_u64())?;
+                        Ok(Some(size).filter(|&size| size != UNLIMITED_SIZE))
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            )
+        } else {
+            None
+        };
+
+        // Permutation indices are only defined for version 1, and the reference library never wrote them.
+        // The identity permutation is harmless, but any other one would reorder the dimensions.
+        if (flags & DATASPACE_PERMUTATION_INDEX_PRESENT) != 0 {
+            track_assert_eq!(
+                version,
+                1,
+                ErrorKind::InvalidFile,
+                "Permutation index flag (0b0000_0010) is set in a version 2 dataspace"
+            );
+            for i in 0..u64::from(dimensionality) {
+                let index = track!(reader.read_u64())?;
+                track_assert_eq!(
+                    index,
+                    i,
+                    ErrorKind::Unsupported,
+                    "Dataspace permutation index flag (0b0000_0010) with a non-identity permutation"
+                );
+            }
+        }
+
+        Ok(Self {
+            dimension_sizes,
+            dimension_max_sizes,
+        })
+    }
+}
+
+const DATASPACE_MAX_DIMENSIONS_PRESENT: u8 = 0b0000_0001;
+const DATASPACE_PERMUTATION_INDEX_PRESENT: u8 = 0b0000_0010;
+
+/// Datatype class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DatatypeClass {
+    /// Fixed-point numbers (integers).
+    FixedPoint,
+
+    /// Floating-point numbers.
+    FloatingPoint,
+
+    /// Time.
+    Time,
+
+    /// Strings.
+    String,
+
+    /// Bit fields.
+    BitField,
+
+    /// Opaque data.
+    Opaque,
+
+    /// Compound data.
+    Compound,
+
+    /// References.
+    Reference,
+
+    /// Enumerations.
+    Enumerated,
+
+    /// Variable-length sequences.
+    VariableLength,
+
+    /// Arrays.
+    Array,
+}
+impl TryFrom<u8> for DatatypeClass {
+    type Error = Error;
+
+    fn try_from(f: u8) -> Result<Self> {
+        Ok(match f {
+            0 => DatatypeClass::FixedPoint,
+            1 => DatatypeClass::FloatingPoint,
+            2 => DatatypeClass::Time,
+            3 => DatatypeClass::String,
+            4 => DatatypeClass::BitField,
+            5 => DatatypeClass::Opaque,
+            6 => DatatypeClass::Compound,
+            7 => DatatypeClass::Reference,
+            8 => DatatypeClass::Enumerated,
+            9 => DatatypeClass::VariableLength,
+            10 => DatatypeClass::Array,
+            _ => track_panic!(ErrorKind::InvalidFile, "Unknown datatype class: {}", f),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MantissaNorm {
+    None,
+    AlwaysSet,
+    ImpliedToBeSet,
+}
+impl TryFrom<u8> for MantissaNorm {
+    type Error = Error;
+
+    fn try_from(f: u8) -> Result<Self> {
+        match f {
+            0 => Ok(MantissaNorm::None),
+            1 => Ok(MantissaNorm::AlwaysSet),
+            2 => Ok(MantissaNorm::ImpliedToBeSet),
+            3 => track_panic!(ErrorKind::InvalidFile, "Reserved value"),
+            _ => track_panic!(ErrorKind::InvalidInput),
+        }
+    }
+}
+
+/// Byte order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endian {
+    /// Little-endian.
+    Little,
+
+    /// Big-endian.
+    Big,
+
+    /// VAX mixed-endian.
+    Vax,
+}
+impl TryFrom<u8> for Endian {
+    type Error = Error;
+
+    fn try_from(f: u8) -> Result<Self> {
+        match f {
+            0b0000_0000 => Ok(Endian::Little),
+            0b0000_0001 => Ok(Endian::Big),
+            0b0100_0000 => track_panic!(ErrorKind::InvalidFile, "Reserved endian bits"),
+            0b0100_0001 => Ok(Endian::Vax),
+            _ => track_panic!(ErrorKind::InvalidInput),
+        }
+    }
+}
+
+/// Floating-point datatype.
+#[derive(Debug, Clone)]
+pub struct FloatingPointDatatype {
+    size: u32,
+
+    endian: Endian,
+    low_padding_bit: u8,
+    high_padding_bit: u8,
+    internal_padding_bit: u8,
+    mantissa_norm: MantissaNorm,
+    sign_location: u8,
+
+    bit_offset: u16,
+    bit_precision: u16,
+    exponent_location: u8,
+    exponent_size: u8,
+    mantissa_location: u8,
+    mantissa_size: u8,
+    exponent_bias: u32,
+}
+impl FloatingPointDatatype {
+    /// Decodes the given row-major elements of this type into an array of the given shape.
+    ///
+    /// Native `f32` values are returned as `DataObject::Float32`, and the others as `DataObject::Float`.
+    pub fn decode_array(&self, bytes: Vec<u8>, dimensions: Vec<usize>) -> Result<DataObject> {
+        let count = dimensions.iter().try_fold(1usize, |n, &d| n.checked_mul(d));
+        let count = track_assert_some!(count, ErrorKind::InvalidFile; dimensions);
+        if self.is_standard_ieee32() {
+            track_assert_eq!(bytes.len(), count * 4, ErrorKind::InvalidFile);
+            let items = bytes
+                .chunks_exact(4)
+                .map(|b| {
+                    let b = [b[0], b[1], b[2], b[3]];
+                    if self.endian == Endian::Big {
+                        f32::from_be_bytes(b)
+                    } else {
+                        f32::from_le_bytes(b)
+                    }
+                })
+                .collect();
+            return track!(array::from_shape_vec(dimensions, items)).map(DataObject::Float32);
+        }
+
+        let mut reader = &bytes[..];
+        let items = (0..count)
+            .map(|i| track!(self.decode(&mut reader); i))
+            .collect::<Result<Vec<_>>>()?;
+        track_assert_eq!(reader, b"", ErrorKind::InvalidFile);
+        track!(array::from_shape_vec(dimensions, items)).map(DataObject::Float)
+    }
+
+    /// Decodes a value of this type.
+    ///
+    /// Standard IEEE 754 values are reinterpreted directly.
+    /// The others are reconstructed arithmetically from their sign, exponent and mantissa fields,
+    /// so non-standard layouts and exponent biases are supported.
+    pub fn decode<R: Read>(&self, mut reader: R) -> Result<f64> {
+        if self.is_standard_ieee64() {
+            let mut bytes = [0; 8];
+            track!(reader.read_bytes(&mut bytes))?;
+            return Ok(if self.endian == Endian::Big {
+                f64::from_be_bytes(bytes)
+            } else {
+                f64::from_le_bytes(bytes)
+            });
+        }
+        if self.is_standard_ieee32() {
+            let mut bytes = [0; 4];
+            track!(reader.read_bytes(&mut bytes))?;
+            return Ok(f64::from(if self.endian == Endian::Big {
+                f32::from_be_bytes(bytes)
+            } else {
+                f32::from_le_bytes(bytes)
+            }));
+        }
+
+        track_assert_eq!(self.endian, Endian::Little, ErrorKind::Unsupported);
+        track_assert_eq!(self.low_padding_bit, 0, ErrorKind::Unsupported);
+        track_assert_eq!(self.high_padding_bit, 0, ErrorKind::Unsupported);
+        track_assert_eq!(self.internal_padding_bit, 0, ErrorKind::Unsupported);
+        track_assert_eq!(
+            self.mantissa_norm,
+            MantissaNorm::ImpliedToBeSet,
+            ErrorKind::Unsupported
+        );
+        track_assert!((1..=8).contains(&self.size), ErrorKind::Unsupported; self.size);
+        track_assert!(
+            u32::from(self.sign_location) < self.size * 8
+                && u32::from(self.exponent_location) + u32::from(self.exponent_size)
+                    <= self.size * 8
+                && u32::from(self.mantissa_location) + u32::from(self.mantissa_size)
+                    <= self.size * 8
+                && (1..64).contains(&self.exponent_size)
+                && self.mantissa_size < 64,
+            ErrorKind::InvalidFile;
+            self
+        );
+
+        let mut bytes = [0; 8];
+        track!(reader
+            .read_exact(&mut bytes[..self.size as usize])
+            .map_err(Error::from))?;
+        let bits = u64::from_le_bytes(bytes);
+        let field = |location: u8, size: u8| {
+            let mask = if size == 64 {
+                u64::MAX
+            } else {
+                (1 << size) - 1
+            };
+            bits.checked_shr(u32::from(location)).unwrap_or(0) & mask
+        };
+
+        let sign = if field(self.sign_location, 1) == 0 {
+            1.0
+        } else {
+            -1.0
+        };
+        let exponent = field(self.exponent_location, self.exponent_size);
+        let mantissa = field(self.mantissa_location, self.mantissa_size);
+        let max_exponent = (1u64 << self.exponent_size) - 1;
+        let fraction = mantissa as f64 / 2f64.powi(i32::from(self.mantissa_size));
+        let bias = i64::from(self.exponent_bias);
+
+        let value = if exponent == max_exponent {
+            if mantissa == 0 {
+                f64::INFINITY
+            } else {
+                f64::NAN
+            }
+        } else if exponent == 0 {
+            // Subnormal numbers.
+            fraction * pow2(1 - bias)
+        } else {
+            (1.0 + fraction) * pow2(exponent as i64 - bias)
+        };
+        Ok(sign * value)
+    }
+
+    /// Returns `true` if the values are IEEE 754 single-precision numbers (in either byte order),
+    /// which can be reinterpreted as `f32` as-is.
+    pub fn is_standard_ieee32(&self) -> bool {
+        self.has_standard_layout(4, 8, 23, 127)
+    }
+
+    /// Returns `true` if the values are IEEE 754 double-precision numbers (in either byte order),
+    /// which can be reinterpreted as `f64` as-is.
+    pub fn is_standard_ieee64(&self) -> bool {
+        self.has_standard_layout(8, 11, 52, 1023)
+    }
+
+    fn has_standard_layout(
+        &self,
+        size: u32,
+        exponent_size: u8,
+        mantissa_size: u8,
+        exponent_bias: u32,
+    ) -> bool {
+        let bits = size * 8;
+        (self.endian == Endian::Little || self.endian == Endian::Big)
+            && self.size == size
+            && self.low_padding_bit == 0
+            && self.high_padding_bit == 0
+            && self.internal_padding_bit == 0
+            && self.mantissa_norm == MantissaNorm::ImpliedToBeSet
+            && u32::from(self.sign_location) == bits - 1
+            && self.bit_offset == 0
+            && u32::from(self.bit_precision) == bits
+            && self.exponent_location == mantissa_size
+            && self.exponent_size == exponent_size
+            && self.mantissa_location == 0
+            && self.mantissa_size == mantissa_size
+            && self.exponent_bias == exponent_bias
+    }
+
+    /// Reads the class-specific properties of the datatype.
+    pub fn from_reader<R: Read>(bit_field: u32, size: u32, mut reader: R) -> Result<Self> {
+        let bit_offset = track!(reader.read_u16())?;
+        let bit_precision = track!(reader.read_u16())?;
+        let exponent_location = track!(reader.read_u8())?;
+        let exponent_size = track!(reader.read_u8())?;
+        let mantissa_location = track!(reader.read_u8())?;
+        let mantissa_size = track!(reader.read_u8())?;
+        let exponent_bias = track!(reader.read_u32())?;
+
+        Ok(Self {
+            size,
+
+            endian: track!(Endian::try_from((bit_field & 0b0100_0001) as u8))?,
+            low_padding_bit: ((bit_field >> 1) & 1) as u8,
+            high_padding_bit: ((bit_field >> 2) & 1) as u8,
+            internal_padding_bit: ((bit_field >> 3) & 1) as u8,
+            mantissa_norm: track!(MantissaNorm::try_from(((bit_field >> 4) & 0b11) as u8))?,
+            sign_location: (bit_field >> 8) as u8,
+
+            bit_offset,
+            bit_precision,
+            exponent_location,
+            exponent_size,
+            mantissa_location,
+            mantissa_size,
+            exponent_bias,
+        })
+    }
+}
+
+/// Returns `2^exponent` (saturating to zero or infinity if it is out of the range of `f64`).
+fn pow2(exponent: i64) -> f64 {
+    let exponent = exponent.clamp(-2048, 2048) as i32;
+    if exponent < -1022 {
+        // Splits the calculation so that subnormal results don't underflow prematurely.
+        2f64.powi(-1022) * 2f64.powi(exponent + 1022)
+    } else {
+        2f64.powi(exponent)
+    }
+}
+
+// Bits 4-23 of the fixed-point bit field are reserved (bit 3 selects two's complement).
+const FIXED_POINT_RESERVED_BITS: u32 = 0x00FF_FFF0;
+
+/// Fixed-point datatype.
+#[derive(Debug, Clone)]
+pub struct FixedPointDatatype {
+    bit_field: u32,
+    size: u32,
+
+    bit_offset: u16,
+    bit_precision: u16,
+}
+impl FixedPointDatatype {
+    /// Decodes the given row-major elements of this type into an array of the given shape.
+    ///
+    /// Native 8-bit and 16-bit unsigned integers are returned as `DataObject::U8` and `DataObject::U16`,
+    /// and the others as `DataObject::I64` or `DataObject::U64`.
+    pub fn decode_array(&self, bytes: Vec<u8>, dimensions: Vec<usize>) -> Result<DataObject> {
+        let count = dimensions.iter().try_fold(1usize, |n, &d| n.checked_mul(d));
+        let count = track_assert_some!(count, ErrorKind::InvalidFile; dimensions);
+        if !self.is_native_unsigned() {
+            let mut reader = &bytes[..];
+            let items = (0..count)
+                .map(|i| track!(self.decode(&mut reader); i))
+                .collect::<Result<Vec<_>>>()?;
+            track_assert_eq!(reader, b"", ErrorKind::InvalidFile);
+
+            // `decode` extracts at most 64 bits, so the values always fit in `i64` (or `u64`).
+            return if self.is_signed() {
+                let items = items.into_iter().map(|v| v as i64).collect();
+                track!(array::from_shape_vec(dimensions, items)).map(DataObject::I64)
+            } else {
+                let items = items.into_iter().map(|v| v as u64).collect();
+                track!(array::from_shape_vec(dimensions, items)).map(DataObject::U64)
+            };
+        }
+
+        track_assert_eq!(
+            bytes.len(),
+            count * self.size as usize,
+            ErrorKind::InvalidFile
+        );
+        match self.size {
+            1 => track!(array::from_shape_vec(dimensions, bytes)).map(DataObject::U8),
+            2 => {
+                let items = bytes
+                    .chunks_exact(2)
+                    .map(|b| {
+                        let b = [b[0], b[1]];
+                        if self.endian() == Endian::Big {
+                            u16::from_be_bytes(b)
+                        } else {
+                            u16::from_le_bytes(b)
+                        }
+                    })
+                    .collect();
+                track!(array::from_shape_vec(dimensions, items)).map(DataObject::U16)
+            }
+            _ => track_panic!(ErrorKind::Unsupported, "Fixed-point size: {}", self.size),
+        }
+    }
+
+    /// Returns the byte order of the values.
+    pub fn endian(&self) -> Endian {
+        if (self.bit_field & 0b0000_0001) == 0 {
+            Endian::Little
+        } else {
+            Endian::Big
+        }
+    }
+
+    /// Returns `true` if the values are signed (two's complement).
+    pub fn is_signed(&self) -> bool {
+        (self.bit_field & 0b0000_1000) != 0
+    }
+
+    /// Decodes a value of this type.
+    ///
+    /// The `bit_precision` bits starting at `bit_offset` are extracted,
+    /// and then sign-extended if the type is signed.
+    /// The padding bits outside of them are masked out whatever the low/high padding flags say,
+    /// since some writers leave garbage in them.
+    ///
+    /// Two's complement is the only signed representation defined by the format,
+    /// so a bit field with any of the reserved bits (4-23) set results in an `ErrorKind::Unsupported` error
+    /// instead of a silently misdecoded value.
+    pub fn decode<R: Read>(&self, mut reader: R) -> Result<i128> {
+        track_assert_eq!(
+            self.bit_field & FIXED_POINT_RESERVED_BITS,
+            0,
+            ErrorKind::Unsupported,
+            "Unknown fixed-point representation (bit field: {:#08b})",
+            self.bit_field
+        );
+        track_assert!((1..=8).contains(&self.size), ErrorKind::Unsupported; self.size);
+        track_assert!(
+            self.bit_precision > 0
+                && u32::from(self.bit_offset) + u32::from(self.bit_precision) <= self.size * 8,
+            ErrorKind::InvalidFile;
+            self
+        );
+
+        let size = self.size as usize;
+        if self.endian() == Endian::Big
+            && self.is_signed()
+            && self.bit_offset == 0
+            && u32::from(self.bit_precision) == self.size * 8
+        {
+            match size {
+                2 => return Ok(i128::from(track!(reader.read_i16_be())?)),
+                4 => return Ok(i128::from(track!(reader.read_i32_be())?)),
+                8 => return Ok(i128::from(track!(reader.read_i64_be())?)),
+                _ => {}
+            }
+        }
+        let raw = match (self.endian(), size) {
+            (Endian::Big, 2) => u64::from(track!(reader.read_u16_be())?),
+            (Endian::Big, 4) => u64::from(track!(reader.read_u32_be())?),
+            (Endian::Big, 8) => track!(reader.read_u64_be())?,
+            (endian, _) => {
+                let mut bytes = [0; 8];
+                track!(reader.read_exact(&mut bytes[..size]).map_err(Error::from))?;
+                if endian == Endian::Big {
+                    bytes[..size].reverse();
+                }
+                u64::from_le_bytes(bytes)
+            }
+        };
+        let bits = raw >> self.bit_offset;
+        let precision = u32::from(self.bit_precision);
+        let value = if precision == 64 {
+            bits
+        } else {
+            bits & ((1 << precision) - 1)
+        };
+
+        if self.is_signed() && (value >> (precision - 1)) & 1 == 1 {
+            Ok(i128::from(value) - (1i128 << precision))
+        } else {
+            Ok(i128::from(value))
+        }
+    }
+
+    /// Returns `true` if the values can be read as-is into `u8` or `u16`.
+    fn is_native_unsigned(&self) -> bool {
+        self.bit_field & FIXED_POINT_RESERVED_BITS == 0
+            && !self.is_signed()
+            && (self.size == 1 || self.size == 2)
+            && self.bit_offset == 0
+            && u32::from(self.bit_precision) == self.size * 8
+    }
+
+    /// Reads the class-specific properties of the datatype.
+    pub fn from_reader<R: Read>(bit_field: u32, size: u32, mut reader: R) -> Result<Self> {
+        let bit_offset = track!(reader.read_u16())?;
+        let bit_precision = track!(reader.read_u16())?;
+
+        Ok(Self {
+            bit_field,
+            size,
+
+            bit_offset,
+            bit_precision,
+        })
+    }
+}
+
+/// Datatype message (type=0x03).
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#DatatypeMessage
+#[derive(Debug, Clone)]
+pub enum DatatypeMessage {
+    /// Fixed-point numbers.
+    FixedPoint(FixedPointDatatype),
+
+    /// Floating-point numbers.
+    FloatingPoint(FloatingPointDatatype),
+
+    /// Enumerations.
+    Enumerated(EnumeratedDatatype),
+
+    /// Compound datatypes.
+    Compound(CompoundDatatype),
+
+    /// Fixed-length strings.
+    String(StringDatatype),
+
+    /// Opaque data.
+    Opaque(OpaqueDatatype),
+
+    /// References.
+    Reference(ReferenceDatatype),
+
+    /// Variable-length sequences and strings.
+    VariableLength(VariableLengthDatatype),
+    // Time,
+    // BitField,
+    // Array,
+}
+impl DatatypeMessage {
+    /// Decodes a single value of this datatype.
+    ///
+    /// References and variable-length values can't be decoded without access to the file,
+    /// so an `ErrorKind::Unsupported` error will be returned for them.
+    pub fn decode_scalar<R: Read>(&self, mut reader: R) -> Result<ScalarValue> {
+        match self {
+            DatatypeMessage::FloatingPoint(t) if t.is_standard_ieee32() => {
+                let mut bytes = [0; 4];
+                track!(reader.read_bytes(&mut bytes))?;
+                Ok(ScalarValue::F32(if t.endian == Endian::Big {
+                    f32::from_be_bytes(bytes)
+                } else {
+                    f32::from_le_bytes(bytes)
+                }))
+            }
+            DatatypeMessage::FloatingPoint(t) => {
+                track!(t.decode(&mut reader)).map(ScalarValue::F64)
+            }
+            DatatypeMessage::FixedPoint(t) => {
+                let v = track!(t.decode(&mut reader))?;
+                // `decode` guarantees that the value fits in the type.
+                Ok(match (t.is_signed(), t.size) {
+                    (true, 1) => ScalarValue::I8(v as i8),
+                    (true, 2) => ScalarValue::I16(v as i16),
+                    (true, 3..=4) => ScalarValue::I32(v as i32),
+                    (true, _) => ScalarValue::I64(v as i64),
+                    (false, 1) => ScalarValue::U8(v as u8),
+                    (false, 2) => ScalarValue::U16(v as u16),
+                    (false, 3..=4) => ScalarValue::U32(v as u32),
+                    (false, _) => ScalarValue::U64(v as u64),
+                })
+            }
+            DatatypeMessage::Enumerated(t) if t.is_bool() => match track!(reader.read_u8())? {
+                0 => Ok(ScalarValue::Bool(false)),
+                1 => Ok(ScalarValue::Bool(true)),
+                b => track_panic!(ErrorKind::InvalidFile, "Not a boolean value: {}", b),
+            },
+            DatatypeMessage::Enumerated(t) => {
+                // Other enumerations are decoded as the integers of their base type.
+                track!(t.base_integer())?;
+                track!(t.base.decode_scalar(reader))
+            }
+            DatatypeMessage::String(t) => track!(t.decode(reader)).map(ScalarValue::Str),
+            DatatypeMessage::Opaque(t) => {
+                track!(reader.read_vec(t.size as usize)).map(ScalarValue::Bytes)
+            }
+            DatatypeMessage::Reference(_) | DatatypeMessage::VariableLength(_) => {
+                track_panic!(ErrorKind::Unsupported, "Can't decode values of {}", self)
+            }
+            DatatypeMessage::Compound(t) => track!(t.decode(reader)).map(ScalarValue::Compound),
+        }
+    }
+
+    /// Returns the class of the datatype.
+    pub fn class(&self) -> DatatypeClass {
+        match self {
+            DatatypeMessage::FixedPoint(_) => DatatypeClass::FixedPoint,
+            DatatypeMessage::FloatingPoint(_) => DatatypeClass::FloatingPoint,
+            DatatypeMessage::Enumerated(_) => DatatypeClass::Enumerated,
+            DatatypeMessage::Compound(_) => DatatypeClass::Compound,
+            DatatypeMessage::String(_) => DatatypeClass::String,
+            DatatypeMessage::Opaque(_) => DatatypeClass::Opaque,
+            DatatypeMessage::Reference(_) => DatatypeClass::Reference,
+            DatatypeMessage::VariableLength(_) => DatatypeClass::VariableLength,
+        }
+    }
+
+    /// Returns the size of an element of the datatype in bytes.
+    pub fn size(&self) -> u32 {
+        match self {
+            DatatypeMessage::FixedPoint(t) => t.size,
+            DatatypeMessage::FloatingPoint(t) => t.size,
+            DatatypeMessage::Enumerated(t) => t.size,
+            DatatypeMessage::Compound(t) => t.size,
+            DatatypeMessage::String(t) => t.size,
+            DatatypeMessage::Opaque(t) => t.size,
+            DatatypeMessage::Reference(t) => t.size,
+            DatatypeMessage::VariableLength(t) => t.size,
+        }
+    }
+
+    /// Decodes the given row-major elements of this type into an array of the given shape.
+    pub fn decode_array(&self, bytes: Vec<u8>, dimensions: Vec<usize>) -> Result<DataObject> {
+        match self {
+            DatatypeMessage::FixedPoint(t) => track!(t.decode_array(bytes, dimensions)),
+            DatatypeMessage::FloatingPoint(t) => track!(t.decode_array(bytes, dimensions)),
+            DatatypeMessage::Enumerated(t) => track!(t.decode_array(bytes, dimensions)),
+            DatatypeMessage::Compound(t) => track!(t.decode_array(bytes, dimensions)),
+            DatatypeMessage::String(_) => {
+                track_panic!(
+                    ErrorKind::Unsupported,
+                    "String datasets can't be read as arrays"
+                )
+            }
+            DatatypeMessage::Opaque(_)
+            | DatatypeMessage::Reference(_)
+            | DatatypeMessage::VariableLength(_) => {
+                track_panic!(
+                    ErrorKind::Unsupported,
+                    "Datasets of {} can't be read as arrays",
+                    self
+                )
+            }
+        }
+    }
+
+    /// Reads a datatype message (version 1, 2 or 3).
+    ///
+    /// The versions differ only in how the members of compound and enumerated datatypes are encoded.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let class_and_version = track!(reader.read_u8())?;
+        let version = class_and_version >> 4;
+        let class = track!(DatatypeClass::try_from(class_and_version & 0b0000_1111))?;
+        track_assert!((1..=3).contains(&version), ErrorKind::Unsupported; version);
+
+        let bit_field = track!(reader.read_u24())?;
+        let size = track!(reader.read_u32())?;
+        track_assert_ne!(size, 0, ErrorKind::InvalidFile);
+
+        match class {
+            DatatypeClass::FixedPoint => {
+                track!(FixedPointDatatype::from_reader(bit_field, size, reader))
+                    .map(DatatypeMessage::FixedPoint)
+            }
+            DatatypeClass::FloatingPoint => {
+                track!(FloatingPointDatatype::from_reader(bit_field, size, reader))
+                    .map(DatatypeMessage::FloatingPoint)
+            }
+            DatatypeClass::Enumerated => track!(EnumeratedDatatype::from_reader_with_version(
+                version, bit_field, size, reader
+            ))
+            .map(DatatypeMessage::Enumerated),
+            DatatypeClass::Compound => track!(CompoundDatatype::from_reader_with_version(
+                version, bit_field, size, reader
+            ))
+            .map(DatatypeMessage::Compound),
+            DatatypeClass::String => {
+                track!(StringDatatype::from_bit_field(bit_field, size)).map(DatatypeMessage::String)
+            }
+            DatatypeClass::Opaque => track!(OpaqueDatatype::from_reader(bit_field, size, reader))
+                .map(DatatypeMessage::Opaque),
+            DatatypeClass::Reference => track!(ReferenceDatatype::from_bit_field(bit_field, size))
+                .map(DatatypeMessage::Reference),
+            DatatypeClass::VariableLength => {
+                track!(VariableLengthDatatype::from_reader(bit_field, size, reader))
+                    .map(DatatypeMessage::VariableLength)
+            }
+            _ => track_panic!(ErrorKind::Unsupported; class),
+        }
+    }
+}
+
+impl fmt::Display for DatatypeMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DatatypeMessage::FixedPoint(t) => write!(
+                f,
+                "{} integer ({} bytes, {:?} endian)",
+                if t.is_signed() { "signed" } else { "unsigned" },
+                t.size,
+                t.endian()
+            ),
+            DatatypeMessage::FloatingPoint(t) => {
+                write!(f, "float ({} bytes, {:?} endian)", t.size, t.endian)
+            }
+            DatatypeMessage::Enumerated(t) => {
+                write!(f, "enum of {} members ({})", t.members.len(), t.base)
+            }
+            DatatypeMessage::Compound(t) => write!(
+                f,
+                "compound of {} members ({} bytes)",
+                t.members.len(),
+                t.size
+            ),
+            DatatypeMessage::String(t) => write!(f, "string ({} bytes)", t.size),
+            DatatypeMessage::Opaque(t) => write!(f, "opaque ({} bytes, tag={:?})", t.size, t.tag),
+            DatatypeMessage::Reference(t) if t.is_object_reference() => {
+                write!(f, "object reference ({} bytes)", t.size)
+            }
+            DatatypeMessage::Reference(t) => write!(f, "region reference ({} bytes)", t.size),
+            DatatypeMessage::VariableLength(t) if t.is_string() => {
+                write!(f, "variable-length string")
+            }
+            DatatypeMessage::VariableLength(t) => {
+                write!(f, "variable-length sequence of {}", t.base)
+            }
+        }
+    }
+}
+
+/// Compound datatype.
+#[derive(Debug, Clone)]
+pub struct CompoundDatatype {
+    size: u32,
+    members: Vec<CompoundMember>,
+}
+impl CompoundDatatype {
+    /// Returns the members of the compound datatype.
+    pub fn members(&self) -> &[CompoundMember] {
+        &self.members
+    }
+
+    /// Decodes a value of this type into the named members in declaration order.
+    ///
+    /// Each member is read from its stored byte offset, so alignment padding between members
+    /// (as produced for `H5T_NATIVE`-style layouts) is skipped regardless of its content.
+    pub fn decode<R: Read>(&self, mut reader: R) -> Result<Vec<(String, ScalarValue)>> {
+        let bytes = track!(reader.read_vec(self.size as usize))?;
+        self.members
+            .iter()
+            .map(|m| Ok((m.name.clone(), track!(m.decode(&bytes); m.name)?)))
+            .collect()
+    }
+
+    /// Decodes the given row-major elements of this type into a table of the given shape.
+    ///
+    /// Each member is decoded into its own column (see `CompoundMember::decode_column`).
+    pub fn decode_array(&self, bytes: Vec<u8>, dimensions: Vec<usize>) -> Result<DataObject> {
+        let size = dimensions
+            .iter()
+            .try_fold(self.size as usize, |n, &d| n.checked_mul(d));
+        track_assert_eq!(Some(bytes.len()), size, ErrorKind::InvalidFile; dimensions);
+        let columns = self
+            .members
+            .iter()
+            .map(|m| {
+                let column =
+                    track!(m.decode_column(&bytes, self.size as usize, &dimensions); m.name)?;
+                Ok((m.name.clone(), column))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        track!(Table::new(dimensions, columns)).map(DataObject::Compound)
+    }
+
+    /// Reads the class-specific properties of the datatype (version 1).
+    pub fn from_reader<R: Read>(bit_field: u32, size: u32, reader: R) -> Result<Self> {
+        track!(Self::from_reader_with_version(1, bit_field, size, reader))
+    }
+
+    /// Reads the class-specific properties of the datatype of the given version.
+    pub(crate) fn from_reader_with_version<R: Read>(
+        version: u8,
+        bit_field: u32,
+        size: u32,
+        mut reader: R,
+    ) -> Result<Self> {
+        let member_count = (bit_field & 0xFFFF) as usize;
+        let members = (0..member_count)
+            .map(|i| {
+                track!(CompoundMember::from_reader(
+                    &mut reader as &mut dyn Read,
+                    version,
+                    size
+                ); i)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for m in &members {
+            let end = u64::from(m.offset).saturating_add(m.byte_size());
+            track_assert!(
+                end <= u64::from(size),
+                ErrorKind::InvalidFile,
+                "The member {:?} (offset={}, end={}) exceeds the compound size {}",
+                m.name,
+                m.offset,
+                end,
+                size
+            );
+        }
+        Ok(Self { size, members })
+    }
+}
+
+/// Member of a compound datatype.
+#[derive(Debug, Clone)]
+pub struct CompoundMember {
+    name: String,
+    offset: u32,
+    dimensions: Vec<u32>,
+    datatype: DatatypeMessage,
+}
+impl CompoundMember {
+    /// Returns the name of the member.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the byte offset of the member within a compound value.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the dimensions of the member if it is an array, otherwise an empty slice.
+    pub fn dimensions(&self) -> &[u32] {
+        &self.dimensions
+    }
+
+    /// Returns the datatype of the member (or of the elements, if the member is an array).
+    pub fn datatype(&self) -> &DatatypeMessage {
+        &self.datatype
+    }
+
+    fn byte_size(&self) -> u64 {
+        // Saturates so that bogus dimensions are rejected by the bounds check of the compound datatype.
+        self.dimensions
+            .iter()
+            .fold(u64::from(self.datatype.size()), |size, &d| {
+                size.saturating_mul(u64::from(d))
+            })
+    }
+
+    /// Decodes the member of each of the compound values in `records` into a column of the given shape.
+    ///
+    /// Numbers, enumerations and nested compound values are decoded into typed columns
+    /// like datasets of their datatypes, and the others (e.g., strings and arrays) into `DataObject::Values`.
+    pub fn decode_column(
+        &self,
+        records: &[u8],
+        record_size: usize,
+        dimensions: &[usize],
+    ) -> Result<DataObject> {
+        let count = dimensions.iter().try_fold(1usize, |n, &d| n.checked_mul(d));
+        let count = track_assert_some!(count, ErrorKind::InvalidFile; dimensions);
+        track_assert_eq!(
+            Some(records.len()),
+            count.checked_mul(record_size),
+            ErrorKind::InvalidInput
+        );
+        let records = (0..count).map(|i| &records[i * record_size..][..record_size]);
+
+        let typed = match self.datatype.class() {
+            DatatypeClass::FixedPoint
+            | DatatypeClass::FloatingPoint
+            | DatatypeClass::Enumerated
+            | DatatypeClass::Compound => self.dimensions.is_empty(),
+            _ => false,
+        };
+        if typed {
+            let start = self.offset as usize;
+            let size = self.datatype.size() as usize;
+            let mut bytes = Vec::with_capacity(count * size);
+            for record in records {
+                bytes.extend_from_slice(&record[start..start + size]);
+            }
+            return track!(self.datatype.decode_array(bytes, dimensions.to_vec()));
+        }
+        let values = records
+            .enumerate()
+            .map(|(i, record)| track!(self.decode(record); i))
+            .collect::<Result<Vec<_>>>()?;
+        track!(array::from_shape_vec(dimensions.to_vec(), values)).map(DataObject::Values)
+    }
+
+    fn decode(&self, compound: &[u8]) -> Result<ScalarValue> {
+        let start = self.offset as usize;
+        let mut bytes = &compound[start..start + self.byte_size() as usize];
+        if self.dimensions.is_empty() {
+            return track!(self.datatype.decode_scalar(bytes));
+        }
+        let count = self
+            .dimensions
+            .iter()
+            .map(|&d| d as usize)
+            .product::<usize>();
+        let items = (0..count)
+            .map(|i| track!(self.datatype.decode_scalar(&mut bytes); i))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ScalarValue::Array(items))
+    }
+
+    /// Reads a member of a compound datatype of the given version and size.
+    ///
+    /// Version 1 members may be arrays; later versions use the array datatype for them instead.
+    /// Version 3 names aren't padded, and offsets take only as many bytes as the size of the compound needs.
+    fn from_reader(mut reader: &mut dyn Read, version: u8, compound_size: u32) -> Result<Self> {
+        if version >= 3 {
+            let name = track!(reader.read_null_terminated_string())?;
+            let offset_size = ((32 - compound_size.leading_zeros() + 7) / 8).max(1);
+            let offset = track!(reader.read_uint(offset_size as usize))? as u32;
+            let datatype = track!(DatatypeMessage::from_reader(reader))?;
+            return Ok(Self {
+                name,
+                offset,
+                dimensions: Vec::new(),
+                datatype,
+            });
+        }
+
+        let name = track!(reader.read_padded_null_terminated_string(8))?;
+        let offset = track!(reader.read_u32())?;
+        if version == 2 {
+            let datatype = track!(DatatypeMessage::from_reader(reader))?;
+            return Ok(Self {
+                name,
+                offset,
+                dimensions: Vec::new(),
+                datatype,
+            });
+        }
+        let dimensionality = track!(reader.read_u8())?;
+        track_assert!(dimensionality <= 4, ErrorKind::InvalidFile; dimensionality);
+        track!(reader.skip(3))?;
+        let _permutation = track!(reader.read_u32())?;
+        track!(reader.skip(4))?;
+        let dimensions = (0..4)
+            .map(|_| track!(reader.read_u32()))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .take(usize::from(dimensionality))
+            .collect();
+        let datatype = track!(DatatypeMessage::from_reader(reader))?;
+        Ok(Self {
+            name,
+            offset,
+            dimensions,
+            datatype,
+        })
+    }
+}
+
+/// Character set of strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CharacterSet {
+    /// US-ASCII (only 7-bit bytes).
+    Ascii,
+
+    /// UTF-8.
+    Utf8,
+}
+
+/// Fixed-length string datatype.
+#[derive(Debug, Clone)]
+pub struct StringDatatype {
+    size: u32,
+    padding_type: u8,
+    character_set: CharacterSet,
+}
+impl StringDatatype {
+    /// Returns the character set of the strings.
+    pub fn character_set(&self) -> CharacterSet {
+        self.character_set
+    }
+
+    /// Makes a string datatype from the class bit field (string datatypes have no properties).
+    pub fn from_bit_field(bit_field: u32, size: u32) -> Result<Self> {
+        let character_set = match (bit_field >> 4) & 0b1111 {
+            0 => CharacterSet::Ascii,
+            1 => CharacterSet::Utf8,
+            n => track_panic!(ErrorKind::Unsupported, "Unknown character set: {}", n),
+        };
+        Ok(Self {
+            size,
+            padding_type: (bit_field & 0b1111) as u8,
+            character_set,
+        })
+    }
+
+    /// Decodes a value of this type.
+    ///
+    /// Null-terminated and null-padded strings end at the first null byte,
+    /// and trailing spaces are removed from space-padded strings.
+    /// ASCII strings containing bytes other than 7-bit ones are rejected as `ErrorKind::InvalidFile`.
+    pub fn decode<R: Read>(&self, mut reader: R) -> Result<String> {
+        let mut bytes = track!(reader.read_vec(self.size as usize))?;
+        if self.padding_type == 2 {
+            let len = bytes.iter().rposition(|&b| b != b' ').map_or(0, |i| i + 1);
+            bytes.truncate(len);
+        } else if let Some(end) = bytes.iter().position(|&b| b == 0) {
+            bytes.truncate(end);
+        }
+        if self.character_set == CharacterSet::Ascii {
+            track_assert!(bytes.is_ascii(), ErrorKind::InvalidFile; bytes);
+        }
+        track!(String::from_utf8(bytes).map_err(Error::from))
+    }
+}
+
+/// Opaque datatype.
+#[derive(Debug, Clone)]
+pub struct OpaqueDatatype {
+    size: u32,
+    tag: String,
+}
+impl OpaqueDatatype {
+    /// Returns the ASCII tag describing the opaque data.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Reads the class-specific properties of the datatype.
+    pub fn from_reader<R: Read>(bit_field: u32, size: u32, mut reader: R) -> Result<Self> {
+        // The tag is null-terminated and padded to a multiple of eight bytes.
+        let tag_size = (bit_field & 0xFF) as usize;
+        let tag = track!(reader.read_vec(tag_size))?;
+        let tag = tag.split(|&b| b == 0).next().unwrap_or(&[]);
+        track_assert!(tag.is_ascii(), ErrorKind::InvalidFile; tag);
+        let tag = track!(String::from_utf8(tag.to_vec()).map_err(Error::from))?;
+        Ok(Self { size, tag })
+    }
+}
+
+/// Reference datatype.
+#[derive(Debug, Clone)]
+pub struct ReferenceDatatype {
+    size: u32,
+    kind: u8,
+}
+impl ReferenceDatatype {
+    /// Returns `true` if this is an object reference (i.e., the address of an object header).
+    pub fn is_object_reference(&self) -> bool {
+        self.kind == 0
+    }
+
+    /// Returns `true` if this is a dataset region reference.
+    pub fn is_region_reference(&self) -> bool {
+        self.kind == 1
+    }
+
+    /// Makes a reference datatype from the class bit field (reference datatypes have no properties).
+    pub fn from_bit_field(bit_field: u32, size: u32) -> Result<Self> {
+        let kind = (bit_field & 0b1111) as u8;
+        track_assert!(
+            kind <= 1,
+            ErrorKind::InvalidFile,
+            "Unknown reference type: {}",
+            kind
+        );
+        Ok(Self { size, kind })
+    }
+}
+
+/// Variable-length datatype.
+///
+/// Each element is stored as a length followed by the global heap ID of its data.
+#[derive(Debug, Clone)]
+pub struct VariableLengthDatatype {
+    size: u32,
+    kind: u8,
+    base: Box<DatatypeMessage>,
+}
+impl VariableLengthDatatype {
+    /// Returns `true` if this is a variable-length string (rather than a sequence).
+    pub fn is_string(&self) -> bool {
+        self.kind == 1
+    }
+
+    /// Returns the datatype of the items of the sequence.
+    pub fn base(&self) -> &DatatypeMessage {
+        &self.base
+    }
+
+    /// Reads the class-specific properties of the datatype.
+    pub fn from_reader<R: Read>(bit_field: u32, size: u32, mut reader: R) -> Result<Self> {
+        let kind = (bit_field & 0b1111) as u8;
+        track_assert!(
+            kind <= 1,
+            ErrorKind::InvalidFile,
+            "Unknown variable-length type: {}",
+            kind
+        );
+        // `dyn Read` prevents infinite instantiation of this recursive generic function.
+        let base = track!(DatatypeMessage::from_reader(&mut reader as &mut dyn Read))?;
+        Ok(Self {
+            size,
+            kind,
+            base: Box::new(base),
+        })
+    }
+}
+
+/// Enumeration datatype.
+#[derive(Debug, Clone)]
+pub struct EnumeratedDatatype {
+    size: u32,
+    base: Box<DatatypeMessage>,
+    members: Vec<(String, Vec<u8>)>,
+}
+impl EnumeratedDatatype {
+    /// Returns the base datatype of the enumeration.
+    pub fn base(&self) -> &DatatypeMessage {
+        &self.base
+    }
+
+    /// Returns the names and the (raw) values of the members of the enumeration.
+    pub fn members(&self) -> &[(String, Vec<u8>)] {
+        &self.members
+    }
+
+    /// Decodes the given row-major elements of this type into an array of the given shape.
+    ///
+    /// Booleans (see `is_bool`) are returned as `DataObject::Bool`,
+    /// and the other enumerations as `DataObject::Enum`.
+    ///
+    /// If a value doesn't fit in `i64`, an `ErrorKind::Unsupported` error will be returned.
+    pub fn decode_array(&self, bytes: Vec<u8>, dimensions: Vec<usize>) -> Result<DataObject> {
+        let count = dimensions.iter().try_fold(1usize, |n, &d| n.checked_mul(d));
+        let count = track_assert_some!(count, ErrorKind::InvalidFile; dimensions);
+        if self.is_bool() {
+            track_assert_eq!(bytes.len(), count, ErrorKind::InvalidFile);
+            let items = bytes
+                .into_iter()
+                .map(|b| match b {
+                    0 => Ok(false),
+                    1 => Ok(true),
+                    _ => track_panic!(ErrorKind::InvalidFile, "Not a boolean value: {}", b),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return track!(array::from_shape_vec(dimensions, items)).map(DataObject::Bool);
+        }
+
+        let base = track!(self.base_integer())?;
+        let mut reader = &bytes[..];
+        let items = (0..count)
+            .map(|i| track!(base.decode(&mut reader); i))
+            .collect::<Result<Vec<_>>>()?;
+        track_assert_eq!(reader, b"", ErrorKind::InvalidFile);
+        let to_i64 = |v: i128| {
+            let v = track_assert_some!(i64::try_from(v).ok(), ErrorKind::Unsupported; v);
+            Ok(v)
+        };
+        let codes = items.into_iter().map(to_i64).collect::<Result<Vec<_>>>()?;
+        let mapping = track!(self.values())?
+            .into_iter()
+            .map(|(name, v)| Ok((name.to_owned(), track!(to_i64(v))?)))
+            .collect::<Result<Vec<_>>>()?;
+        let codes = track!(array::from_shape_vec(dimensions, codes))?;
+        Ok(DataObject::Enum { codes, mapping })
+    }
+
+    /// Returns `true` if this is the enumeration used by h5py to store booleans
+    /// (i.e., `FALSE = 0` and `TRUE = 1` over a 1-byte integer).
+    pub fn is_bool(&self) -> bool {
+        self.size == 1
+            && matches!(*self.base, DatatypeMessage::FixedPoint(_))
+            && self.members.len() == 2
+            && self
+                .members
+                .iter()
+                .all(|(name, value)| match name.as_str() {
+                    "FALSE" => value[..] == [0],
+                    "TRUE" => value[..] == [1],
+                    _ => false,
+                })
+    }
+
+    /// Returns the names and the values of the members of the enumeration.
+    ///
+    /// The values are decoded with the base datatype, so negative values of signed bases are preserved.
+    pub fn values(&self) -> Result<Vec<(&str, i128)>> {
+        let base = track!(self.base_integer())?;
+        self.members
+            .iter()
+            .map(|(name, value)| Ok((name.as_str(), track!(base.decode(&value[..]); name)?)))
+            .collect()
+    }
+
+    /// Decodes a value of this type.
+    ///
+    /// The value is returned together with the name of the matching member, if any.
+    pub fn decode<R: Read>(&self, reader: R) -> Result<(i128, Option<&str>)> {
+        let value = track!(track!(self.base_integer())?.decode(reader))?;
+        let name = track!(self.values())?
+            .into_iter()
+            .find(|&(_, v)| v == value)
+            .map(|(name, _)| name);
+        Ok((value, name))
+    }
+
+    fn base_integer(&self) -> Result<&FixedPointDatatype> {
+        if let DatatypeMessage::FixedPoint(ref t) = *self.base {
+            Ok(t)
+        } else {
+            track_panic!(
+                ErrorKind::Unsupported,
+                "Unsupported base datatype of enumeration: {}",
+                self.base
+            )
+        }
+    }
+
+    /// Reads the class-specific properties of the datatype (version 1).
+    pub fn from_reader<R: Read>(bit_field: u32, size: u32, reader: R) -> Result<Self> {
+        track!(Self::from_reader_with_version(1, bit_field, size, reader))
+    }
+
+    /// Reads the class-specific properties of the datatype of the given version.
+    pub(crate) fn from_reader_with_version<R: Read>(
+        version: u8,
+        bit_field: u32,
+        size: u32,
+        mut reader: R,
+    ) -> Result<Self> {
+        let member_count = (bit_field & 0xFFFF) as usize;
+        // `dyn Read` prevents infinite instantiation of this recursive generic function.
+        let base = track!(DatatypeMessage::from_reader(&mut reader as &mut dyn Read))?;
+        track_assert_eq!(base.size(), size, ErrorKind::InvalidFile);
+
+        // Member names are padded to multiples of eight bytes before datatype version 3.
+        let alignment = if version >= 3 { 1 } else { 8 };
+        let names = (0..member_count)
+            .map(|_| track!(reader.read_padded_null_terminated_string(alignment)))
+            .collect::<Result<Vec<_>>>()?;
+        let members = names
+            .into_iter()
+            .map(|name| Ok((name, track!(reader.read_vec(size as usize))?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            size,
+            base: Box::new(base),
+            members,
+        })
+    }
+}
+
+/// type=0x04
+#[derive(Debug, Clone)]
+pub struct OldFillValueMessage {
+    fill_value: Vec<u8>,
+}
+impl OldFillValueMessage {
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let size = track!(reader.read_u32())?;
+        let fill_value = track!(reader.read_vec(size as usize))?;
+        Ok(Self { fill_value })
+    }
+}
+
+/// type=0x05
+#[derive(Debug, Clone)]
+pub struct FillValueMessage {
+    fill_value: Option<Vec<u8>>,
+}
+impl FillValueMessage {
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 2, ErrorKind::Unsupported);
+
+        // The allocation and write times only matter when writing the data.
+        let _space_allocation_time = track!(reader.read_u8())?;
+        let _fill_value_write_time = track!(reader.read_u8())?;
+        let fill_value_defined = track!(reader.read_u8())?;
+        let fill_value = if fill_value_defined == 1 {
+            let size = track!(reader.read_u32())?;
+            let fill_value = track!(reader.read_vec(size as usize))?;
+            Some(fill_value)
+        } else {
+            None
+        };
+        Ok(Self { fill_value })
+    }
+}
+
+/// Layout class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LayoutClass {
+    /// The data is stored in the object header.
+    Compact,
+
+    /// The data is stored in a single contiguous block.
+    Contiguous,
+
+    /// The data is split into chunks.
+    Chunked,
+
+    /// The data is mapped from other datasets (layout version 4 only).
+    Virtual,
+}
+impl TryFrom<u8> for LayoutClass {
+    type Error = Error;
+
+    fn try_from(f: u8) -> Result<Self> {
+        Ok(match f {
+            0 => LayoutClass::Compact,
+            1 => LayoutClass::Contiguous,
+            2 => LayoutClass::Chunked,
+            3 => LayoutClass::Virtual,
+            _ => track_panic!(ErrorKind::InvalidFile, "Unknown layout class: {}", f),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Layout {
+    Compact {
+        data: Vec<u8>,
+    },
+    Contiguous {
+        address: u64,
+        size: u64,
+    },
+    Chunked {
+        address: u64,
+        chunk_dimensions: Vec<u32>,
+        element_size: u32,
+        index: ChunkIndex,
+    },
+    Virtual {
+        heap_address: u64,
+        index: u32,
+    },
+}
+impl Layout {
+    pub fn from_reader<R: Read>(class: LayoutClass, mut reader: R) -> Result<Self> {
+        match class {
+            LayoutClass::Compact => {
+                let size = track!(reader.read_u16())?;
+                let data = track!(reader.read_vec(usize::from(size)))?;
+                Ok(Layout::Compact { data })
+            }
+            LayoutClass::Contiguous => {
+                let address = track!(reader.read_u64())?;
+                let size = track!(reader.read_u64())?;
+                Ok(Layout::Contiguous { address, size })
+            }
+            LayoutClass::Chunked => {
+                let dimensionality = track!(reader.read_u8())?;
+                track_assert!(dimensionality >= 2, ErrorKind::InvalidFile; dimensionality);
+
+                let address = track!(reader.read_u64())?;
+                let mut chunk_dimensions = (0..dimensionality)
+                    .map(|_| track!(reader.read_u32()))
+                    .collect::<Result<Vec<_>>>()?;
+                let element_size =
+                    track_assert_some!(chunk_dimensions.pop(), ErrorKind::InvalidFile);
+                Ok(Layout::Chunked {
+                    address,
+                    chunk_dimensions,
+                    element_size,
+                    index: ChunkIndex::BTreeV1,
+                })
+            }
+            LayoutClass::Virtual => {
+                // The mappings are stored in a global heap object.
+                let heap_address = track!(reader.read_u64())?;
+                let index = track!(reader.read_u32())?;
+                Ok(Layout::Virtual {
+                    heap_address,
+                    index,
+                })
+            }
+        }
+    }
+
+    /// Reads the properties of a version 4 layout.
+    ///
+    /// Only chunked layouts differ from version 3.
+    pub fn from_reader_v4<R: Read>(class: LayoutClass, mut reader: R) -> Result<Self> {
+        if class != LayoutClass::Chunked {
+            return track!(Self::from_reader(class, reader));
+        }
+
+        let flags = track!(reader.read_u8())?;
+        let dimensionality = track!(reader.read_u8())?;
+        track_assert!(dimensionality >= 2, ErrorKind::InvalidFile; dimensionality);
+        let encoded_size = track!(reader.read_u8())?;
+        track_assert!((1..=8).contains(&encoded_size), ErrorKind::InvalidFile; encoded_size);
+
+        let mut chunk_dimensions = (0..dimensionality)
+            .map(|_| {
+                let d = track!(reader.read_uint(usize::from(encoded_size)))?;
+                track_assert!(d <= u64::from(u32::MAX), ErrorKind::Unsupported; d);
+                Ok(d as u32)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let element_size = track_assert_some!(chunk_dimensions.pop(), ErrorKind::InvalidFile);
+
+        let index_type = track!(reader.read_u8())?;
+        let index = match index_type {
+            1 => {
+                if (flags & LAYOUT_SINGLE_INDEX_WITH_FILTER) != 0 {
+                    let filtered_size = track!(reader.read_u64())?;
+                    let filter_mask = track!(reader.read_u32())?;
+                    ChunkIndex::SingleChunk {
+                        filtered_size: Some(filtered_size),
+                        filter_mask,
+                    }
+                } else {
+                    ChunkIndex::SingleChunk {
+                        filtered_size: None,
+                        filter_mask: 0,
+                    }
+                }
+            }
+            2 => ChunkIndex::Implicit,
+            _ => track_panic!(ErrorKind::Unsupported, "Chunk index type: {}", index_type),
+        };
+        let address = track!(reader.read_u64())?;
+        Ok(Layout::Chunked {
+            address,
+            chunk_dimensions,
+            element_size,
+            index,
+        })
+    }
+}
+
+// The single chunk of a version 4 layout is filtered (i.e., its size and filter mask are stored).
+const LAYOUT_SINGLE_INDEX_WITH_FILTER: u8 = 0b0000_0010;
+
+/// Index used to look up the chunks of a chunked layout.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#LayoutMessage
+#[derive(Debug, Clone)]
+pub enum ChunkIndex {
+    /// Version 1 B-tree (the only index of layout versions 1 to 3).
+    BTreeV1,
+
+    /// The dataset consists of a single chunk located at the address of the layout.
+    SingleChunk {
+        /// Size of the chunk after filtering (`None` if the dataset isn't filtered).
+        filtered_size: Option<u64>,
+
+        /// Bit mask indicating which filters were skipped for the chunk.
+        filter_mask: u32,
+    },
+
+    /// The chunks are stored in row-major order of their positions from the address of the layout without an index.
+    ///
+    /// This is only used for early-allocated, unfiltered datasets whose dimensions are fixed.
+    Implicit,
+}
+
+/// A mapping from (a selection of) a source dataset to a selection of a virtual dataset.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#VDSGlobalHeapBlock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualMapping {
+    source_file_name: String,
+    source_dataset_name: String,
+    source_selection: Vec<u8>,
+    virtual_selection: Vec<u8>,
+}
+impl VirtualMapping {
+    /// Returns the name of the file containing the source dataset (`"."` means the same file).
+    pub fn source_file_name(&self) -> &str {
+        &self.source_file_name
+    }
+
+    /// Returns the path of the source dataset.
+    pub fn source_dataset_name(&self) -> &str {
+        &self.source_dataset_name
+    }
+
+    /// Returns the encoded selection of the source dataset.
+    pub fn source_selection(&self) -> &[u8] {
+        &self.source_selection
+    }
+
+    /// Returns the encoded selection of the virtual dataset.
+    pub fn virtual_selection(&self) -> &[u8] {
+        &self.virtual_selection
+    }
+
+    /// Reads the mappings stored in a global heap object.
+    ///
+    /// The trailing checksum is verified unless `options` disables checksum verification.
+    pub fn read_all(object: &[u8], options: &ReaderOptions) -> Result<Vec<Self>> {
+        let mut bytes = object;
+        let version = track!(bytes.read_u8())?;
+        track_assert_eq!(version, 0, ErrorKind::Unsupported);
+
+        let count = track!(bytes.read_u64())?;
+        // Each mapping occupies at least 34 bytes (two null terminators and two 16-byte selections).
+        track_assert!(count <= bytes.len() as u64 / 34, ErrorKind::InvalidFile; count);
+        let mut mappings = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let source_file_name = track!(bytes.read_null_terminated_string())?;
+            let source_dataset_name = track!(bytes.read_null_terminated_string())?;
+            let source_selection = track!(read_selection(&mut bytes))?;
+            let virtual_selection = track!(read_selection(&mut bytes))?;
+            mappings.push(Self {
+                source_file_name,
+                source_dataset_name,
+                source_selection,
+                virtual_selection,
+            });
+        }
+        let encoded_size = object.len() - bytes.len();
+        let checksum = track!(bytes.read_u32())?;
+        if options.is_verifying_checksums() {
+            track!(verify_checksum(&object[..encoded_size], checksum))?;
+        }
+        Ok(mappings)
+    }
+}
+
+/// A dataset region reference (a dataset and a selection of its elements).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionReference {
+    dataset_address: u64,
+    selection: Selection,
+}
+impl RegionReference {
+    /// Returns the address of the object header of the referenced dataset.
+    ///
+    /// The dataset can be opened with `Hdf5File::dataset_at`.
+    pub fn dataset_address(&self) -> u64 {
+        self.dataset_address
+    }
+
+    /// Returns the selected region of the dataset.
+    pub fn selection(&self) -> &Selection {
+        &self.selection
+    }
+
+    /// Reads a region reference from the global heap object that stores it.
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self> {
+        let dataset_address = track!(bytes.read_u64())?;
+        let encoded = track!(read_selection(&mut bytes))?;
+        let selection = track!(Selection::decode(&encoded))?;
+        Ok(Self {
+            dataset_address,
+            selection,
+        })
+    }
+}
+
+/// A selection of the elements of a dataspace.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#DatasetRegionReference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// No elements are selected.
+    None,
+
+    /// All elements are selected.
+    All,
+
+    /// The elements at the given coordinates are selected.
+    Points(Vec<Vec<u64>>),
+
+    /// The elements in the given blocks (pairs of the start and the inclusive end coordinates) are selected.
+    Blocks(Vec<(Vec<u64>, Vec<u64>)>),
+
+    /// A selection whose encoding (e.g., a later version) isn't interpreted by this crate.
+    Encoded(Vec<u8>),
+}
+impl Selection {
+    fn decode(encoded: &[u8]) -> Result<Self> {
+        let mut bytes = encoded;
+        let kind = track!(bytes.read_u32())?;
+        let version = track!(bytes.read_u32())?;
+        if version != 1 {
+            return Ok(Selection::Encoded(encoded.to_vec()));
+        }
+        track!(bytes.skip(8))?; // Reserved and the length.
+        if kind == 0 || kind == 3 {
+            return Ok(if kind == 0 {
+                Selection::None
+            } else {
+                Selection::All
+            });
+        }
+        track_assert!(
+            kind == 1 || kind == 2,
+            ErrorKind::InvalidFile,
+            "Unknown selection type: {}",
+            kind
+        );
+
+        let rank = track!(bytes.read_u32())?;
+        let count = track!(bytes.read_u32())?;
+        track_assert_ne!(rank, 0, ErrorKind::InvalidFile);
+        // A point has a set of coordinates, and a block has two (the start and the end).
+        let coordinates_per_item = if kind == 1 { 1 } else { 2 };
+        let len = u64::from(count) * u64::from(rank) * 4 * coordinates_per_item;
+        track_assert!(
+            len <= bytes.len() as u64,
+            ErrorKind::InvalidFile,
+            "Truncated selection: {} coordinates",
+            len / 4
+        );
+        let mut coordinates = (0..u64::from(count) * coordinates_per_item)
+            .map(|_| {
+                (0..rank)
+                    .map(|_| track!(bytes.read_u32()).map(u64::from))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter();
+        if kind == 1 {
+            return Ok(Selection::Points(coordinates.collect()));
+        }
+        let mut blocks = Vec::new();
+        while let (Some(start), Some(end)) = (coordinates.next(), coordinates.next()) {
+            blocks.push((start, end));
+        }
+        Ok(Selection::Blocks(blocks))
+    }
+}
+
+/// Reads an encoded dataspace selection and returns its bytes.
+///
+/// The selection isn't interpreted, but its size depends on its type and version.
+fn read_selection(bytes: &mut &[u8]) -> Result<Vec<u8>> {
+    let start = *bytes;
+    let kind = track!(bytes.read_u32())?;
+    let version = track!(bytes.read_u32())?;
+    match (kind, version) {
+        // None, point (version 1), hyperslab (version 1) or all: the size of the rest is stored.
+        (0, 1) | (1, 1) | (2, 1) | (3, 1) => {
+            track!(bytes.skip(4))?;
+            let len = track!(bytes.read_u32())?;
+            track!(bytes.skip(len as usize))?;
+        }
+        (1, 2) => {
+            let encoded_size = track!(bytes.read_u8())?;
+            let rank = track!(bytes.read_u32())?;
+            let count = track!(bytes.read_uint(usize::from(encoded_size)))?;
+            let len = track_assert_some!(
+                count
+                    .checked_mul(u64::from(rank))
+                    .and_then(|n| n.checked_mul(u64::from(encoded_size))),
+                ErrorKind::InvalidFile;
+                count, rank, encoded_size
+            );
+            track_assert!(len <= bytes.len() as u64, ErrorKind::InvalidFile; len);
+            track!(bytes.skip(len as usize))?;
+        }
+        (2, 2) => {
+            let _flags = track!(bytes.read_u8())?;
+            let len = track!(bytes.read_u32())?;
+            track!(bytes.skip(len as usize))?;
+        }
+        (2, 3) => {
+            let flags = track!(bytes.read_u8())?;
+            let encoded_size = u64::from(track!(bytes.read_u8())?);
+            let rank = u64::from(track!(bytes.read_u32())?);
+            let len = if flags & 0b0000_0001 != 0 {
+                // Regular hyperslab: start, stride, count and block.
+                4 * rank * encoded_size
+            } else {
+                let count = track!(bytes.read_uint(encoded_size as usize))?;
+                track_assert_some!(
+                    (2 * rank * encoded_size).checked_mul(count),
+                    ErrorKind::InvalidFile;
+                    count, rank, encoded_size
+                )
+            };
+            track_assert!(len <= bytes.len() as u64, ErrorKind::InvalidFile; len);
+            track!(bytes.skip(len as usize))?;
+        }
+        _ => track_panic!(
+            ErrorKind::Unsupported,
+            "Selection type {} (version {})",
+            kind,
+            version
+        ),
+    }
+    Ok(start[..start.len() - bytes.len()].to_vec())
+}
+
+/// type=0x08
+#[derive(Debug, Clone)]
+pub struct DataLayoutMessage {
+    layout: Layout,
+}
+impl DataLayoutMessage {
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert!(version == 3 || version == 4, ErrorKind::Unsupported; version);
+
+        let layout_class = track!(reader.read_u8().and_then(LayoutClass::try_from))?;
+        track_assert!(
+            version == 4 || layout_class != LayoutClass::Virtual,
+            ErrorKind::InvalidFile,
+            "Virtual layout in a version {} layout message",
+            version
+        );
+        let layout = if version == 3 {
+            track!(Layout::from_reader(layout_class, &mut reader))?
+        } else {
+            track!(Layout::from_reader_v4(layout_class, &mut reader))?
+        };
+        let _padding = track!(reader.read_all())?;
+        Ok(Self { layout })
+    }
+}
+
+/// A filter in a filter pipeline.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    id: u16,
+    name: Option<String>,
+    flags: u16,
+    client_data: Vec<u32>,
+}
+impl Filter {
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn is_optional(&self) -> bool {
+        (self.flags & 0b0000_0001) != 0
+    }
+
+    /// Reverts the filter applied to `data`.
+    ///
+    /// `decoded_size` is the size of the data after all filters of the pipeline have been reverted.
+    pub fn decode(
+        &self,
+        registry: &FilterRegistry,
+        data: Vec<u8>,
+        decoded_size: usize,
+    ) -> Result<Vec<u8>> {
+        track!(
+            registry.decode(self.id, &self.client_data, data, decoded_size),
+            "Filter: id={}, name={:?}",
+            self.id,
+            self.name
+        )
+    }
+
+    fn from_reader<R: Read>(version: u8, mut reader: R) -> Result<Self> {
+        let id = track!(reader.read_u16())?;
+        let name_len = if version == 1 || id >= 256 {
+            track!(reader.read_u16())?
+        } else {
+            0
+        };
+        let flags = track!(reader.read_u16())?;
+        let client_data_len = track!(reader.read_u16())?;
+
+        let name = if name_len == 0 {
+            None
+        } else {
+            // In version 1, the name is padded to a multiple of eight bytes.
+            let padded_len = if version == 1 {
+                (name_len as usize + 7) / 8 * 8
+            } else {
+                name_len as usize
+            };
+            let bytes = track!(reader.read_vec(padded_len))?;
+            let name = bytes.split(|&b| b == 0).next().unwrap_or(&[]);
+            Some(track!(
+                String::from_utf8(name.to_vec()).map_err(Error::from)
+            )?)
+        };
+
+        let client_data = (0..client_data_len)
+            .map(|_| track!(reader.read_u32()))
+            .collect::<Result<Vec<_>>>()?;
+        if version == 1 && client_data_len % 2 == 1 {
+            track!(reader.skip(4))?;
+        }
+
+        Ok(Self {
+            id,
+            name,
+            flags,
+            client_data,
+        })
+    }
+}
+
+/// type=0x0B
+#[derive(Debug, Clone)]
+pub struct FilterPipelineMessage {
+    filters: Vec<Filter>,
+}
+impl FilterPipelineMessage {
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    /// Reverts the filters applied to `data`.
+    ///
+    /// If the i-th bit of `filter_mask` is set, the i-th filter is regarded as skipped.
+    /// `decoded_size` is the expected size of the decoded data, which bounds the output of some filters.
+    pub fn decode(
+        &self,
+        registry: &FilterRegistry,
+        mut data: Vec<u8>,
+        filter_mask: u32,
+        decoded_size: usize,
+    ) -> Result<Vec<u8>> {
+        for (i, filter) in self.filters.iter().enumerate().rev() {
+            if i < 32 && (filter_mask & (1 << i)) != 0 {
+                continue;
+            }
+            data = track!(filter.decode(registry, data, decoded_size); i)?;
+        }
+        Ok(data)
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert!(version == 1 || version == 2, ErrorKind::Unsupported; version);
+
+        let filter_count = track!(reader.read_u8())?;
+        if version == 1 {
+            track!(reader.skip(6))?;
+        }
+
+        let filters = (0..filter_count)
+            .map(|_| track!(Filter::from_reader(version, &mut reader)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { filters })
+    }
+}
+
+/// type=0x0C
+#[derive(Debug, Clone)]
+pub struct AttributeMessage {
+    name: String,
+    datatype: DatatypeMessage,
+    dataspace: DataspaceMessage,
+    data: Vec<u8>,
+}
+impl AttributeMessage {
+    /// Returns the name of the attribute.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the datatype of the attribute.
+    pub fn datatype(&self) -> &DatatypeMessage {
+        &self.datatype
+    }
+
+    /// Returns the dimensions of the dataspace of the attribute.
+    pub fn dimensions(&self) -> &[u64] {
+        &self.dataspace.dimension_sizes
+    }
+
+    /// Returns the raw (undecoded) value of the attribute.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert!((1..=3).contains(&version), ErrorKind::Unsupported; version);
+
+        let flags = track!(reader.read_u8())?;
+        track_assert_eq!(
+            flags & 0b0000_0011,
+            0,
+            ErrorKind::Unsupported,
+            "Shared attribute datatype or dataspace"
+        );
+
+        let name_size = track!(reader.read_u16())?;
+        let datatype_size = track!(reader.read_u16())?;
+        let dataspace_size = track!(reader.read_u16())?;
+        if version == 3 {
+            let _encoding = track!(reader.read_u8())?;
+        }
+
+        // In version 1, the name, datatype and dataspace are padded to multiples of eight bytes.
+        let padded = |size: u16| {
+            if version == 1 {
+                (u64::from(size) + 7) / 8 * 8
+            } else {
+                u64::from(size)
+            }
+        };
+
+        let name = track!(reader.read_vec(padded(name_size) as usize))?;
+        let name = name.split(|&b| b == 0).next().unwrap_or(&[]);
+        let name = track!(String::from_utf8(name.to_vec()).map_err(Error::from))?;
+
+        let mut sub = (&mut reader).take(padded(datatype_size));
+        let datatype = track!(DatatypeMessage::from_reader(&mut sub))?;
+        let _padding = track!(sub.read_all())?;
+
+        let mut sub = (&mut reader).take(padded(dataspace_size));
+        let dataspace = track!(DataspaceMessage::from_reader(&mut sub))?;
+        let _padding = track!(sub.read_all())?;
+
+        let data = track!(reader.read_all())?;
+        Ok(Self {
+            name,
+            datatype,
+            dataspace,
+            data,
+        })
+    }
+}
+
+/// Link info message (type=0x02).
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#LinkInfoMessage
+#[derive(Debug, Clone)]
+pub struct LinkInfoMessage {
+    flags: u8,
+    max_creation_index: Option<u64>,
+    fractal_heap_address: u64,
+    name_index_b_tree_address: u64,
+    creation_order_index_b_tree_address: Option<u64>,
+}
+impl LinkInfoMessage {
+    /// Returns `true` if the creation order of the links is tracked.
+    pub fn is_creation_order_tracked(&self) -> bool {
+        self.flags & 0b0000_0001 != 0
+    }
+
+    /// Returns `true` if the links are indexed by creation order.
+    pub fn is_creation_order_indexed(&self) -> bool {
+        self.flags & 0b0000_0010 != 0
+    }
+
+    /// Returns the maximum creation index of the links if the creation order is tracked.
+    pub fn max_creation_index(&self) -> Option<u64> {
+        self.max_creation_index
+    }
+
+    /// Returns `true` if the links are stored in a fractal heap instead of link messages.
+    pub fn is_dense(&self) -> bool {
+        self.fractal_heap_address != UNDEFINED_ADDRESS
+    }
+
+    /// Returns the address of the fractal heap storing the links (if the links are stored densely).
+    pub fn fractal_heap_address(&self) -> Option<u64> {
+        Some(self.fractal_heap_address).filter(|&a| a != UNDEFINED_ADDRESS)
+    }
+
+    /// Returns the address of the v2 B-tree indexing the densely stored links by name.
+    pub fn name_index_b_tree_address(&self) -> Option<u64> {
+        Some(self.name_index_b_tree_address).filter(|&a| a != UNDEFINED_ADDRESS)
+    }
+
+    /// Returns the address of the v2 B-tree indexing the densely stored links by creation order.
+    pub fn creation_order_index_b_tree_address(&self) -> Option<u64> {
+        self.creation_order_index_b_tree_address
+            .filter(|&a| a != UNDEFINED_ADDRESS)
+    }
+
+    /// Reads a link info message.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 0, ErrorKind::Unsupported);
+
+        let flags = track!(reader.read_u8())?;
+        let max_creation_index = if flags & 0b0000_0001 != 0 {
+            Some(track!(reader.read_u64())?)
+        } else {
+            None
+        };
+        let fractal_heap_address = track!(reader.read_u64())?;
+        let name_index_b_tree_address = track!(reader.read_u64())?;
+        let creation_order_index_b_tree_address = if flags & 0b0000_0010 != 0 {
+            Some(track!(reader.read_u64())?)
+        } else {
+            None
+        };
+        Ok(Self {
+            flags,
+            max_creation_index,
+            fractal_heap_address,
+            name_index_b_tree_address,
+            creation_order_index_b_tree_address,
+        })
+    }
+}
+
+/// Attribute info message (type=0x15).
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#AttributeInfoMessage
+#[derive(Debug, Clone)]
+pub struct AttributeInfoMessage {
+    flags: u8,
+    max_creation_index: Option<u16>,
+    fractal_heap_address: u64,
+    name_index_b_tree_address: u64,
+    creation_order_index_b_tree_address: Option<u64>,
+}
+impl AttributeInfoMessage {
+    /// Returns `true` if the creation order of the attributes is tracked.
+    pub fn is_creation_order_tracked(&self) -> bool {
+        self.flags & 0b0000_0001 != 0
+    }
+
+    /// Returns the maximum creation index of the attributes if the creation order is tracked.
+    pub fn max_creation_index(&self) -> Option<u16> {
+        self.max_creation_index
+    }
+
+    /// Returns the address of the fractal heap storing the attributes (if the attributes are stored densely).
+    pub fn fractal_heap_address(&self) -> Option<u64> {
+        Some(self.fractal_heap_address).filter(|&a| a != UNDEFINED_ADDRESS)
+    }
+
+    /// Returns the address of the v2 B-tree indexing the densely stored attributes by name.
+    pub fn name_index_b_tree_address(&self) -> Option<u64> {
+        Some(self.name_index_b_tree_address).filter(|&a| a != UNDEFINED_ADDRESS)
+    }
+
+    /// Returns the address of the v2 B-tree indexing the densely stored attributes by creation order.
+    pub fn creation_order_index_b_tree_address(&self) -> Option<u64> {
+        self.creation_order_index_b_tree_address
+            .filter(|&a| a != UNDEFINED_ADDRESS)
+    }
+
+    /// Reads an attribute info message.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 0, ErrorKind::Unsupported);
+
+        let flags = track!(reader.read_u8())?;
+        let max_creation_index = if flags & 0b0000_0001 != 0 {
+            Some(track!(reader.read_u16())?)
+        } else {
+            None
+        };
+        let fractal_heap_address = track!(reader.read_u64())?;
+        let name_index_b_tree_address = track!(reader.read_u64())?;
+        let creation_order_index_b_tree_address = if flags & 0b0000_0010 != 0 {
+            Some(track!(reader.read_u64())?)
+        } else {
+            None
+        };
+        Ok(Self {
+            flags,
+            max_creation_index,
+            fractal_heap_address,
+            name_index_b_tree_address,
+            creation_order_index_b_tree_address,
+        })
+    }
+}
+
+/// Group info message (type=0x0A).
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#GroupInfoMessage
+#[derive(Debug, Clone)]
+pub struct GroupInfoMessage {
+    link_phase_change: Option<(u16, u16)>,
+    estimates: Option<(u16, u16)>,
+}
+impl GroupInfoMessage {
+    /// Returns the maximum number of links stored compactly (i.e., in the object header).
+    pub fn max_compact_links(&self) -> Option<u16> {
+        self.link_phase_change.map(|(max, _)| max)
+    }
+
+    /// Returns the minimum number of links stored densely (i.e., in a fractal heap).
+    pub fn min_dense_links(&self) -> Option<u16> {
+        self.link_phase_change.map(|(_, min)| min)
+    }
+
+    /// Returns the estimated number of entries in the group.
+    pub fn estimated_entry_count(&self) -> Option<u16> {
+        self.estimates.map(|(count, _)| count)
+    }
+
+    /// Returns the estimated length of the link names of the group.
+    pub fn estimated_name_length(&self) -> Option<u16> {
+        self.estimates.map(|(_, len)| len)
+    }
+
+    /// Reads a group info message.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 0, ErrorKind::Unsupported);
+
+        let flags = track!(reader.read_u8())?;
+        let link_phase_change = if flags & 0b0000_0001 != 0 {
+            Some((track!(reader.read_u16())?, track!(reader.read_u16())?))
+        } else {
+            None
+        };
+        let estimates = if flags & 0b0000_0010 != 0 {
+            Some((track!(reader.read_u16())?, track!(reader.read_u16())?))
+        } else {
+            None
+        };
+        Ok(Self {
+            link_phase_change,
+            estimates,
+        })
+    }
+}
+
+/// Link message (type=0x06).
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#LinkMessage
+#[derive(Debug, Clone)]
+pub struct LinkMessage {
+    name: String,
+    target: LinkTarget,
+    creation_order: Option<u64>,
+}
+impl LinkMessage {
+    /// Returns the name of the link.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the creation order of the link if it is tracked.
+    pub fn creation_order(&self) -> Option<u64> {
+        self.creation_order
+    }
+
+    /// Returns the object that the link points to.
+    pub fn target(&self) -> &LinkTarget {
+        &self.target
+    }
+
+    /// Reads a link message.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 1, ErrorKind::Unsupported);
+
+        let flags = track!(reader.read_u8())?;
+        let link_type = if flags & 0b0000_1000 != 0 {
+            track!(reader.read_u8())?
+        } else {
+            0
+        };
+        let creation_order = if flags & 0b0000_0100 != 0 {
+            Some(track!(reader.read_u64())?)
+        } else {
+            None
+        };
+        if flags & 0b0001_0000 != 0 {
+            let _charset = track!(reader.read_u8())?;
+        }
+
+        let name_len = match flags & 0b0000_0011 {
+            0 => u64::from(track!(reader.read_u8())?),
+            1 => u64::from(track!(reader.read_u16())?),
+            2 => u64::from(track!(reader.read_u32())?),
+            _ => track!(reader.read_u64())?,
+        };
+        let name = track!(reader.read_vec(name_len as usize))?;
+        let name = track!(String::from_utf8(name).map_err(Error::from))?;
+
+        let target = match link_type {
+            0 => LinkTarget::Hard {
+                address: track!(reader.read_u64())?,
+            },
+            1 => {
+                let len = track!(reader.read_u16())?;
+                let path = track!(reader.read_vec(usize::from(len)))?;
+                let path = track!(String::from_utf8(path).map_err(Error::from))?;
+                LinkTarget::Soft { path }
+            }
+            64 => {
+                let len = track!(reader.read_u16())?;
+                let mut info = &track!(reader.read_vec(usize::from(len)))?[..];
+                let version_and_flags = track!(info.read_u8())?;
+                track_assert_eq!(version_and_flags >> 4, 0, ErrorKind::Unsupported);
+                let file_name = track!(info.read_null_terminated_string())?;
+                let object_path = track!(info.read_null_terminated_string())?;
+                LinkTarget::External {
+                    file_name,
+                    object_path,
+                }
+            }
+            _ => track_panic!(ErrorKind::Unsupported, "Link type: {}", link_type),
+        };
+        Ok(Self {
+            name,
+            target,
+            creation_order,
+        })
+    }
+}
+
+/// Object that a link points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// Object header in the same file.
+    Hard {
+        /// Address of the object header.
+        address: u64,
+    },
+
+    /// Path of an object in the same file.
+    Soft {
+        /// Absolute path, or path relative to the group containing the link.
+        path: String,
+    },
+
+    /// Object in another file.
+    External {
+        /// Name of the file containing the object.
+        file_name: String,
+
+        /// Absolute path of the object in the file.
+        object_path: String,
+    },
+}
+
+/// type=0x11
+#[derive(Debug, Clone)]
+pub struct SymbolTableMessage {
+    pub b_tree_address: u64,
+    pub local_heap_address: u64,
+}
+impl SymbolTableMessage {
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        Ok(Self {
+            b_tree_address: track!(reader.read_u64())?,
+            local_heap_address: track!(reader.read_u64())?,
+        })
+    }
+}
+
+/// type=0x12
+#[derive(Debug, Clone)]
+pub struct ObjectModificationTimeMessage {
+    unixtime_seconds: u32,
+}
+impl ObjectModificationTimeMessage {
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 1, ErrorKind::Unsupported);
+        track!(reader.skip(3))?;
+
+        let unixtime_seconds = track!(reader.read_u32())?;
+        Ok(Self { unixtime_seconds })
+    }
+}
+
+/// type=0x16
+#[derive(Debug, Clone)]
+pub struct ObjectReferenceCountMessage {
+    reference_count: u32,
+}
+impl ObjectReferenceCountMessage {
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = track!(reader.read_u8())?;
+        track_assert_eq!(version, 0, ErrorKind::Unsupported);
+
+        let reference_count = track!(reader.read_u32())?;
+        Ok(Self { reference_count })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Nil(NilMessage),
+    Dataspace(DataspaceMessage),
+    LinkInfo(LinkInfoMessage),
+    Datatype(DatatypeMessage),
+    OldFillValue(OldFillValueMessage),
+    FillValue(FillValueMessage),
+    Link(LinkMessage),
+    // ExternalDataFile,
+    DataLayout(DataLayoutMessage),
+    // Bogus,
+    GroupInfo(GroupInfoMessage),
+    FilterPipeline(FilterPipelineMessage),
+    Attribute(AttributeMessage),
+    // ObjectComment,
+    // ObjectModificationTimeOld,
+    // SharedMessageTable,
+    // ObjectHeaderContinuation,
+    SymbolTable(SymbolTableMessage),
+    ObjectModificationTime(ObjectModificationTimeMessage),
+    // BTreeKValues,
+    // DriverInfo,
+    AttributeInfo(AttributeInfoMessage),
+    ObjectReferenceCount(ObjectReferenceCountMessage),
+    /// A shared message that hasn't been resolved yet.
+    Shared {
+        kind: u16,
+        address: u64,
+    },
+    /// A message of unknown or unsupported type (only produced in lenient mode).
+    Unknown {
+        kind: u16,
+        raw: Vec<u8>,
+    },
+}
+impl Message {
+    /// Returns the type code of the message.
+    pub fn kind(&self) -> u16 {
+        match self {
+            Message::Nil(_) => 0x00,
+            Message::Dataspace(_) => 0x01,
+            Message::LinkInfo(_) => 0x02,
+            Message::Datatype(_) => 0x03,
+            Message::OldFillValue(_) => 0x04,
+            Message::FillValue(_) => 0x05,
+            Message::Link(_) => 0x06,
+            Message::DataLayout(_) => 0x08,
+            Message::GroupInfo(_) => 0x0A,
+            Message::FilterPipeline(_) => 0x0B,
+            Message::Attribute(_) => 0x0C,
+            Message::SymbolTable(_) => 0x11,
+            Message::ObjectModificationTime(_) => 0x12,
+            Message::AttributeInfo(_) => 0x15,
+            Message::ObjectReferenceCount(_) => 0x16,
+            Message::Shared { kind, .. } => *kind,
+            Message::Unknown { kind, .. } => *kind,
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", message_type_name(self.kind()))?;
+        match self {
+            Message::Nil(_) => Ok(()),
+            Message::Dataspace(m) => {
+                write!(f, ": shape={:?}", m.dimension_sizes)?;
+                if let Some(max) = &m.dimension_max_sizes {
+                    let max = max
+                        .iter()
+                        .map(|d| d.map_or_else(|| "unlimited".to_owned(), |d| d.to_string()))
+                        .collect::<Vec<_>>();
+                    write!(f, ", max_shape=[{}]", max.join(", "))?;
+                }
+                Ok(())
+            }
+            Message::LinkInfo(m) => write!(
+                f,
+                ": creation order {}, {}",
+                if m.is_creation_order_tracked() {
+                    "tracked"
+                } else {
+                    "not tracked"
+                },
+                if m.is_dense() { "dense" } else { "compact" }
+            ),
+            Message::Datatype(t) => write!(f, ": {}", t),
+            Message::OldFillValue(m) => write!(f, ": {} bytes", m.fill_value.len()),
+            Message::FillValue(m) => match &m.fill_value {
+                Some(value) => write!(f, ": {} bytes", value.len()),
+                None => write!(f, ": undefined"),
+            },
+            Message::Link(m) => match &m.target {
+                LinkTarget::Hard { address } => {
+                    write!(f, ": {:?} -> object header at {:#x}", m.name, address)
+                }
+                LinkTarget::Soft { path } => write!(f, ": {:?} -> {:?}", m.name, path),
+                LinkTarget::External {
+                    file_name,
+                    object_path,
+                } => write!(f, ": {:?} -> {:?} in {:?}", m.name, object_path, file_name),
+            },
+            Message::DataLayout(m) => match &m.layout {
+                Layout::Compact { data } => write!(f, ": compact, {} bytes", data.len()),
+                Layout::Contiguous { address, size } => {
+                    write!(f, ": contiguous, {} bytes at {:#x}", size, address)
+                }
+                Layout::Chunked {
+                    address,
+                    chunk_dimensions,
+                    ..
+                } => write!(
+                    f,
+                    ": chunked {:?}, index at {:#x}",
+                    chunk_dimensions, address
+                ),
+                Layout::Virtual { heap_address, .. } => write!(
+                    f,
+                    ": virtual, mappings in the global heap at {:#x}",
+                    heap_address
+                ),
+            },
+            Message::GroupInfo(m) => match m.estimated_entry_count() {
+                Some(n) => write!(f, ": about {} entries", n),
+                None => Ok(()),
+            },
+            Message::FilterPipeline(m) => {
+                let ids = m.filters.iter().map(|x| x.id).collect::<Vec<_>>();
+                write!(f, ": filters={:?}", ids)
+            }
+            Message::Attribute(m) => write!(
+                f,
+                ": {:?}, {}, shape={:?}",
+                m.name, m.datatype, m.dataspace.dimension_sizes
+            ),
+            Message::SymbolTable(m) => write!(
+                f,
+                ": B-tree at {:#x}, local heap at {:#x}",
+                m.b_tree_address, m.local_heap_address
+            ),
+            Message::ObjectModificationTime(m) => {
+                write!(f, ": {} (UNIX time)", m.unixtime_seconds)
+            }
+            Message::AttributeInfo(m) => write!(
+                f,
+                ": {}",
+                if m.fractal_heap_address().is_some() {
+                    "dense"
+                } else {
+                    "compact"
+                }
+            ),
+            Message::ObjectReferenceCount(m) => write!(f, ": {}", m.reference_count),
+            Message::Shared { address, .. } => {
+                write!(f, " (shared): object header at {:#x}", address)
+            }
+            Message::Unknown { kind, raw } => {
+                write!(
+                    f,
+                    " (type={:#04x}): {} bytes, not interpreted",
+                    kind,
+                    raw.len()
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lowlevel::level1::lookup3_hash;
+    use std::io::Cursor;
+    use trackable::result::TopLevelResult;
+
+    fn dataset_header(datatype: DatatypeMessage, dims: &[u64], data_size: u64) -> ObjectHeader {
+        let messages = vec![
+            Message::Dataspace(DataspaceMessage {
+                dimension_sizes: dims.to_vec(),
+                dimension_max_sizes: None,
+            }),
+            Message::Datatype(datatype),
+            Message::DataLayout(DataLayoutMessage {
+                layout: Layout::Contiguous {
+                    address: 0,
+                    size: data_size,
+                },
+            }),
+        ];
+        ObjectHeader {
+            dense_attributes: Vec::new(),
+            prefix: ObjectHeaderPrefix {
+                messages: messages
+                    .into_iter()
+                    .map(|message| HeaderMessage {
+                        flags: HeaderMessageFlags::empty(),
+                        raw: Vec::new(),
+                        message,
+                    })
+                    .collect(),
+                object_reference_count: 1,
+                object_header_size: 0,
+                creation_order_tracked: false,
+            },
+        }
+    }
+
+    #[test]
+    fn narrow_unsigned_fixed_point_works() -> TopLevelResult {
+        let datatype = FixedPointDatatype {
+            bit_field: 0b0000_0001, // big-endian, unsigned
+            size: 2,
+            bit_offset: 0,
+            bit_precision: 16,
+        };
+        let header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[2, 2], 8);
+        let data = [0, 1, 0, 2, 1, 0, 255, 255];
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        let array = track!(object.into_array::<u16>())?;
+        assert_eq!(array.shape(), &[2, 2]);
+        assert_eq!(array.as_slice(), Some(&[1, 2, 256, 65535][..]));
+        Ok(())
+    }
+
+    #[test]
+    fn empty_dataset_works() -> TopLevelResult {
+        let datatype = FixedPointDatatype {
+            bit_field: 0b0000_1000, // little-endian, signed
+            size: 4,
+            bit_offset: 0,
+            bit_precision: 32,
+        };
+        let mut header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[0, 5], 0);
+        header.prefix.messages[2].message = Message::DataLayout(DataLayoutMessage {
+            layout: Layout::Contiguous {
+                address: UNDEFINED_ADDRESS,
+                size: 0,
+            },
+        });
+        let object = track!(header.get_data_object(Cursor::new(&[][..]), &ReaderOptions::new()))?;
+        let array = track!(object.into_array::<i32>())?;
+        assert_eq!(array.shape(), &[0, 5]);
+        assert_eq!(array.len(), 0);
+
+        header.prefix.messages[2].message = Message::DataLayout(DataLayoutMessage {
+            layout: Layout::Chunked {
+                address: UNDEFINED_ADDRESS,
+                chunk_dimensions: vec![4, 5],
+                element_size: 4,
+                index: ChunkIndex::BTreeV1,
+            },
+        });
+        let object = track!(header.get_data_object(Cursor::new(&[][..]), &ReaderOptions::new()))?;
+        let (items, shape) = track!(object.into_vec::<i32>())?;
+        assert!(items.is_empty());
+        assert_eq!(shape, [0, 5]);
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_chunks_can_be_skipped() -> TopLevelResult {
+        let datatype = FixedPointDatatype {
+            bit_field: 0,
+            size: 2,
+            bit_offset: 0,
+            bit_precision: 16,
+        };
+        let mut header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[4], 0);
+        header.prefix.messages[2].message = Message::DataLayout(DataLayoutMessage {
+            layout: Layout::Chunked {
+                address: 0,
+                chunk_dimensions: vec![2],
+                element_size: 2,
+                index: ChunkIndex::BTreeV1,
+            },
+        });
+
+        // A chunk B-tree node referring to two chunks, the second of which is truncated.
+        let mut bytes = b"TREE\x01\x00".to_vec();
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+        bytes.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+        for &(size, offset, address) in &[(4u32, 0u64, 112u64), (2, 2, 116), (0, 4, 0)] {
+            bytes.extend_from_slice(&size.to_le_bytes());
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&0u64.to_le_bytes());
+            if address != 0 {
+                bytes.extend_from_slice(&address.to_le_bytes());
+            }
+        }
+        assert_eq!(bytes.len(), 112);
+        bytes.extend_from_slice(&[1, 0, 2, 0, 3, 0]);
+
+        let mut options = ReaderOptions::new();
+        assert!(header
+            .get_data_object(Cursor::new(&bytes), &options)
+            .is_err());
+
+        options.skip_corrupt_chunks(true);
+        let mut corrupt_chunks = Vec::new();
+        let object = track!(header.get_data_object_with(
+            Cursor::new(&bytes),
+            &options,
+            &mut corrupt_chunks
+        ))?;
+        let array = track!(object.into_array::<u16>())?;
+        assert_eq!(array.as_slice(), Some(&[1, 2, 0, 0][..]));
+        assert_eq!(corrupt_chunks, [vec![2]]);
+
+        // Reading a single element only reads its own chunk.
+        let options = ReaderOptions::new();
+        let element = track!(header.read_element(Cursor::new(&bytes), &options, 1))?;
+        assert_eq!(element, ScalarValue::U16(2));
+        assert!(header
+            .read_element(Cursor::new(&bytes), &options, 3)
+            .is_err());
+        let error = header
+            .read_element(Cursor::new(&bytes), &options, 4)
+            .unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    #[test]
+    fn layout_class_works() -> TopLevelResult {
+        assert_eq!(track!(LayoutClass::try_from(2))?, LayoutClass::Chunked);
+
+        let error = DataLayoutMessage::from_reader(&[3, 4][..]).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+
+        // Virtual layouts only exist in version 4.
+        let error = DataLayoutMessage::from_reader(&[3, 3][..]).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        let mut bytes = vec![4, 3];
+        bytes.extend_from_slice(&256u64.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        let m = track!(DataLayoutMessage::from_reader(&bytes[..]))?;
+        assert!(matches!(
+            m.layout,
+            Layout::Virtual {
+                heap_address: 256,
+                index: 2
+            }
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn single_chunk_layout_works() -> TopLevelResult {
+        let datatype = FixedPointDatatype {
+            bit_field: 0,
+            size: 2,
+            bit_offset: 0,
+            bit_precision: 16,
+        };
+        let mut header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[2, 3], 0);
+
+        // Version 4, chunked, three 1-byte dimensions (the last one is the element size), single chunk index.
+        let mut bytes = vec![4, 2, 0, 3, 1, 2, 3, 2, 1];
+        bytes.extend_from_slice(&64u64.to_le_bytes());
+        let layout = track!(DataLayoutMessage::from_reader(&bytes[..]))?;
+        header.prefix.messages[2].message = Message::DataLayout(layout);
+
+        let mut data = vec![0; 64];
+        for v in 1..=6u16 {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        let chunks = track!(header.chunks(Cursor::new(&data), &ReaderOptions::new()))?;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!((chunks[0].offset.clone(), chunks[0].size), (vec![0, 0], 12));
+        let object = track!(header.get_data_object(Cursor::new(&data), &ReaderOptions::new()))?;
+        let array = track!(object.into_array::<u16>())?;
+        assert_eq!(array.as_slice(), Some(&[1, 2, 3, 4, 5, 6][..]));
+
+        // A filtered single chunk stores its size and filter mask.
+        let mut bytes = vec![4, 2, 0b0000_0010, 3, 1, 2, 3, 2, 1];
+        bytes.extend_from_slice(&10u64.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&64u64.to_le_bytes());
+        let layout = track!(DataLayoutMessage::from_reader(&bytes[..]))?;
+        header.prefix.messages[2].message = Message::DataLayout(layout);
+        let chunks = track!(header.chunks(Cursor::new(&data), &ReaderOptions::new()))?;
+        assert_eq!((chunks[0].size, chunks[0].filter_mask), (10, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn implicit_chunk_index_works() -> TopLevelResult {
+        let datatype = FixedPointDatatype {
+            bit_field: 0,
+            size: 1,
+            bit_offset: 0,
+            bit_precision: 8,
+        };
+        let mut header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[3, 3], 0);
+
+        // Version 4, chunked, 2x2 chunks of 1-byte elements, implicit index.
+        let mut bytes = vec![4, 2, 0, 3, 1, 2, 2, 1, 2];
+        bytes.extend_from_slice(&8u64.to_le_bytes());
+        let layout = track!(DataLayoutMessage::from_reader(&bytes[..]))?;
+        header.prefix.messages[2].message = Message::DataLayout(layout);
+
+        // Four chunks in row-major order; the edge chunks are padded.
+        let mut data = vec![0; 8];
+        data.extend_from_slice(&[1, 2, 4, 5]);
+        data.extend_from_slice(&[3, 0, 6, 0]);
+        data.extend_from_slice(&[7, 8, 0, 0]);
+        data.extend_from_slice(&[9, 0, 0, 0]);
+
+        let chunks = track!(header.chunks(Cursor::new(&data), &ReaderOptions::new()))?;
+        let offsets = chunks.iter().map(|c| c.offset.clone()).collect::<Vec<_>>();
+        assert_eq!(offsets, [[0, 0], [0, 2], [2, 0], [2, 2]]);
+        assert_eq!(chunks[3].address, 20);
+
+        let object = track!(header.get_data_object(Cursor::new(&data), &ReaderOptions::new()))?;
+        let array = track!(object.into_array::<u8>())?;
+        assert_eq!(array.as_slice(), Some(&[1, 2, 3, 4, 5, 6, 7, 8, 9][..]));
+
+        // A huge grid of chunks is rejected before it is enumerated.
+        if let Message::Dataspace(ref mut m) = header.prefix.messages[0].message {
+            m.dimension_sizes = vec![1 << 20, 1 << 20];
+        }
+        let mut options = ReaderOptions::new();
+        options.max_allocation_size(1 << 20);
+        let error = header.chunks(Cursor::new(&data), &options).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    #[test]
+    fn object_header_display_works() {
+        let datatype = FixedPointDatatype {
+            bit_field: 0b0000_1000, // little-endian, signed
+            size: 4,
+            bit_offset: 0,
+            bit_precision: 32,
+        };
+        let header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[2, 3], 24);
+        assert_eq!(
+            header.to_string(),
+            "object header (3 messages, reference count 1)\n  \
+             Dataspace: shape=[2, 3]\n  \
+             Datatype: signed integer (4 bytes, Little endian)\n  \
+             Data Layout: contiguous, 24 bytes at 0x0"
+        );
+    }
+
+    #[test]
+    fn dataspace_permutation_index_works() -> TopLevelResult {
+        let mut bytes = vec![1, 2, 0b0000_0010, 0, 0, 0, 0, 0];
+        for &n in &[3u64, 4, 0, 1] {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+        let m = track!(DataspaceMessage::from_reader(&bytes[..]))?;
+        assert_eq!(m.dimension_sizes, [3, 4]);
+
+        // Swaps the dimensions.
+        bytes[24] = 1;
+        bytes[32] = 0;
+        let error = DataspaceMessage::from_reader(&bytes[..]).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::Unsupported);
+        assert!(error.to_string().contains("permutation"));
+        Ok(())
+    }
+
+    #[test]
+    fn as_slice_f64_works() -> TopLevelResult {
+        let object = DataObject::Float(track!(array::from_shape_vec(
+            vec![2, 2],
+            vec![1.0, 2.0, 3.0, 4.0]
+        ))?);
+        assert_eq!(object.as_slice_f64(), Some(&[1.0, 2.0, 3.0, 4.0][..]));
+
+        let object = DataObject::U8(track!(array::from_shape_vec(vec![1], vec![1]))?);
+        assert_eq!(object.as_slice_f64(), None);
+
+        #[cfg(feature = "ndarray")]
+        {
+            let transposed = track!(array::from_shape_vec(vec![2, 2], vec![1.0, 2.0, 3.0, 4.0]))?;
+            let object = DataObject::Float(transposed.reversed_axes());
+            assert_eq!(object.as_slice_f64(), None);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn to_le_bytes_works() -> TopLevelResult {
+        let object = DataObject::U16(track!(array::from_shape_vec(vec![2], vec![1, 0x0203]))?);
+        assert_eq!(track!(object.to_le_bytes())?, [1, 0, 3, 2]);
+
+        let object = DataObject::Bool(track!(array::from_shape_vec(vec![2], vec![true, false]))?);
+        assert_eq!(track!(object.to_le_bytes())?, [1, 0]);
+
+        let object = DataObject::Float32(track!(array::from_shape_vec(vec![1], vec![1.5]))?);
+        assert_eq!(track!(object.to_le_bytes())?, 1.5f32.to_le_bytes());
+
+        #[cfg(feature = "ndarray")]
+        {
+            let transposed = track!(array::from_shape_vec(vec![2, 2], vec![1u8, 2, 3, 4]))?;
+            let object = DataObject::U8(transposed.reversed_axes());
+            assert_eq!(track!(object.to_le_bytes())?, [1, 3, 2, 4]);
+        }
+
+        let object = DataObject::Compound(track!(Table::new(vec![0], Vec::new()))?);
+        assert!(object.to_le_bytes().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn column_works() -> TopLevelResult {
+        let x = DataObject::I64(track!(array::from_shape_vec(vec![3], vec![1, 2, 3]))?);
+        let y = DataObject::Float(track!(array::from_shape_vec(vec![3], vec![0.5, 1.5, 2.5]))?);
+        let columns = vec![("x".to_owned(), x), ("y".to_owned(), y)];
+        let object = DataObject::Compound(track!(Table::new(vec![3], columns))?);
+        assert_eq!(object.shape(), [3]);
+
+        let column = track_assert_some!(object.column("y"), ErrorKind::Other);
+        assert_eq!(column.shape(), [3]);
+        if let DataObject::Float(a) = column {
+            assert_eq!(a.iter().cloned().collect::<Vec<_>>(), [0.5, 1.5, 2.5]);
+        } else {
+            panic!("Unexpected column: {:?}", column);
+        }
+        assert!(object.column("z").is_none());
+
+        // Columns must have the shape of the table, and their names must be unique.
+        let column = || DataObject::U8(array::from_shape_vec(vec![2], vec![0, 1]).unwrap());
+        let e = Table::new(vec![3], vec![("x".to_owned(), column())]).unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        let columns = vec![("x".to_owned(), column()), ("x".to_owned(), column())];
+        assert!(Table::new(vec![2], columns).is_err());
+
+        let object = DataObject::U8(track!(array::from_shape_vec(vec![1], vec![0]))?);
+        assert!(object.column("x").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn permuted_axes_works() -> TopLevelResult {
+        let items = |object: &DataObject| match object {
+            DataObject::I64(a) => a.iter().map(|&v| ScalarValue::I64(v)).collect::<Vec<_>>(),
+            DataObject::Values(a) => a.iter().cloned().collect(),
+            _ => panic!("Unexpected object: {:?}", object),
+        };
+        let x = DataObject::I64(track!(array::from_shape_vec(vec![2, 3], (0..6).collect()))?);
+        let y = DataObject::Values(track!(array::from_shape_vec(
+            vec![2, 3],
+            (0..6).map(|i| ScalarValue::U8(i as u8)).collect()
+        ))?);
+        let columns = vec![("x".to_owned(), x), ("y".to_owned(), y)];
+        let object = DataObject::Compound(track!(Table::new(vec![2, 3], columns))?);
+
+        let object = track!(object.permuted_axes(&[1, 0]))?;
+        assert_eq!(object.shape(), [3, 2]);
+        let x = track_assert_some!(object.column("x"), ErrorKind::Other);
+        assert_eq!(x.shape(), [3, 2]);
+        let expected = [0, 3, 1, 4, 2, 5];
+        assert_eq!(
+            items(x),
+            expected
+                .iter()
+                .map(|&v| ScalarValue::I64(v))
+                .collect::<Vec<_>>()
+        );
+        let y = track_assert_some!(object.column("y"), ErrorKind::Other);
+        assert_eq!(
+            items(y),
+            expected
+                .iter()
+                .map(|&v| ScalarValue::U8(v as u8))
+                .collect::<Vec<_>>()
+        );
+
+        let error = object.permuted_axes(&[0]).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    #[test]
+    fn unlimited_max_dimensions_work() -> TopLevelResult {
+        let mut bytes = vec![1, 2, 0b0000_0001, 0, 0, 0, 0, 0];
+        for &n in &[3u64, 4, 3, UNLIMITED_SIZE] {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+        let m = track!(DataspaceMessage::from_reader(&bytes[..]))?;
+        assert_eq!(m.max_dimensions(), Some(&[Some(3), None][..]));
+        assert_eq!(
+            Message::Dataspace(m).to_string(),
+            "Dataspace: shape=[3, 4], max_shape=[3, unlimited]"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn v2_object_header_works() -> TopLevelResult {
+        fn encode(flags: u8, optional_fields: &[u8], message_prefix: &[u8]) -> Vec<u8> {
+            let mut dataspace = vec![2, 1, 0, 1];
+            dataspace.extend_from_slice(&7u64.to_le_bytes());
+            let mut chunk = message_prefix.to_vec();
+            chunk.extend_from_slice(&dataspace);
+            chunk.extend_from_slice(&[0, 0]); // gap
+
+            let mut bytes = b"OHDR\x02".to_vec();
+            bytes.push(flags);
+            bytes.extend_from_slice(optional_fields);
+            let chunk_size = (chunk.len() as u64).to_le_bytes();
+            bytes.extend_from_slice(&chunk_size[..1 << (flags & 0b11)]);
+            bytes.extend_from_slice(&chunk);
+            let checksum = lookup3_hash(&bytes);
+            bytes.extend_from_slice(&checksum.to_le_bytes());
+            bytes.push(0xFF); // the following data
+            bytes
+        }
+
+        // Minimized: no times, no attribute phase change values and a 1-byte chunk size.
+        let bytes = encode(0, &[], &[0x01, 12, 0, 0]);
+        let mut reader = &bytes[..];
+        let header = track!(ObjectHeader::from_reader(
+            &mut reader,
+            &ReaderOptions::new()
+        ))?;
+        assert_eq!(track!(header.dimensions())?, [7]);
+        assert_eq!(header.header_size(), 18);
+        assert_eq!(header.reference_count(), 1);
+        assert!(!header.is_attribute_creation_order_tracked());
+        assert_eq!(reader, [0xFF]);
+
+        // With times, attribute phase change values, tracked creation order and a 2-byte chunk size.
+        let bytes = encode(0b0011_0101, &[0x11; 20], &[0x01, 12, 0, 0, 3, 0]);
+        let mut reader = &bytes[..];
+        let header = track!(ObjectHeader::from_reader(
+            &mut reader,
+            &ReaderOptions::new()
+        ))?;
+        assert_eq!(track!(header.dimensions())?, [7]);
+        assert_eq!(header.header_messages()[0].raw_bytes().len(), 18);
+        assert!(header.is_attribute_creation_order_tracked());
+        assert_eq!(reader, [0xFF]);
+
+        // With an object reference count message preceding the dataspace message.
+        let prefix = [0x16, 5, 0, 0, 0, 3, 0, 0, 0, 0x01, 12, 0, 0];
+        let bytes = encode(0, &[], &prefix);
+        let mut reader = &bytes[..];
+        let header = track!(ObjectHeader::from_reader(
+            &mut reader,
+            &ReaderOptions::new()
+        ))?;
+        assert_eq!(track!(header.dimensions())?, [7]);
+        assert_eq!(header.reference_count(), 3);
+        assert_eq!(
+            header.header_messages()[0].message().to_string(),
+            "Object Reference Count: 3"
+        );
+        assert_eq!(reader, [0xFF]);
+
+        // Corrupted headers are rejected unless checksums aren't verified.
+        let mut bytes = encode(0, &[], &[0x01, 12, 0, 0]);
+        bytes[6] ^= 0x01;
+        let error = ObjectHeader::from_reader(&bytes[..], &ReaderOptions::new()).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        let mut options = ReaderOptions::new();
+        options.verify_checksums(false);
+        track!(ObjectHeader::from_reader(&bytes[..], &options))?;
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_region_references_are_rejected() {
+        let encode = |rank: u32, count: u32| {
+            let mut bytes = 0x1234u64.to_le_bytes().to_vec();
+            for &n in &[1, 1, 0, 8, rank, count] {
+                bytes.extend_from_slice(&n.to_le_bytes());
+            }
+            bytes
+        };
+        // A point selection of zero dimensions.
+        let error = RegionReference::from_bytes(&encode(0, 5)).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+
+        // A point selection whose coordinates are missing.
+        let error = RegionReference::from_bytes(&encode(1, u32::MAX)).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+    }
+
+    #[test]
+    fn read_selection_rejects_overflowing_sizes() {
+        // A point selection (version 2) and an irregular hyperslab selection (version 3)
+        // with 8-byte counts and coordinates.
+        let mut points = vec![1, 0, 0, 0, 2, 0, 0, 0, 8];
+        points.extend_from_slice(&u32::MAX.to_le_bytes());
+        points.extend_from_slice(&u64::MAX.to_le_bytes());
+        let mut hyperslab = vec![2, 0, 0, 0, 3, 0, 0, 0, 0, 8];
+        hyperslab.extend_from_slice(&u32::MAX.to_le_bytes());
+        hyperslab.extend_from_slice(&u64::MAX.to_le_bytes());
+        for bytes in &[points, hyperslab] {
+            let error = read_selection(&mut &bytes[..]).unwrap_err();
+            assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        }
+    }
+
+    #[test]
+    fn v1_object_header_prefix_works() -> TopLevelResult {
+        let mut bytes = vec![1, 0, 1, 0];
+        bytes.extend_from_slice(&3u32.to_le_bytes()); // reference count
+        bytes.extend_from_slice(&24u32.to_le_bytes()); // header size
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&[0x01, 0x00, 16, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&[1, 1, 0, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&5u64.to_le_bytes());
+
+        let header = track!(ObjectHeader::from_reader(&bytes[..], &ReaderOptions::new()))?;
+        assert_eq!(header.header_size(), 24);
+        assert_eq!(header.reference_count(), 3);
+
+        // Truncated headers are reported with the number of messages read.
+        bytes[2] = 2;
+        bytes[8..12].copy_from_slice(&48u32.to_le_bytes());
+        let error = ObjectHeader::from_reader(&bytes[..], &ReaderOptions::new()).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        assert!(
+            error.to_string().contains("only 1 of 2 messages"),
+            "{}",
+            error
+        );
+        bytes[2] = 1;
+
+        // The header size must match the messages.
+        bytes[8..12].copy_from_slice(&32u32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 8]);
+        let error = ObjectHeader::from_reader(&bytes[..], &ReaderOptions::new()).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        Ok(())
+    }
+
+    #[test]
+    fn v1_message_alignment_works() -> TopLevelResult {
+        // The datatype (12 bytes) and the group info (4 bytes) messages are padded to 16 and 8 bytes.
+        let datatype = [0x10, 0, 0, 0, 4, 0, 0, 0, 0, 0, 32, 0];
+        let mut messages = vec![0x03, 0, 16, 0, 0, 0, 0, 0];
+        messages.extend_from_slice(&datatype);
+        messages.extend_from_slice(&[0; 4]);
+        messages.extend_from_slice(&[0x0A, 0, 8, 0, 0, 0, 0, 0, 0, 0b10, 9, 0, 0, 0, 0, 0]);
+        messages.extend_from_slice(&[0x01, 0, 16, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0]);
+        messages.extend_from_slice(&3u64.to_le_bytes());
+
+        let mut bytes = vec![1, 0, 3, 0, 1, 0, 0, 0];
+        bytes.extend_from_slice(&(messages.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&messages);
+        let header = track!(ObjectHeader::from_reader(&bytes[..], &ReaderOptions::new()))?;
+        assert_eq!(track!(header.datatype())?.size(), 4);
+        assert_eq!(
+            header.group_info().and_then(|m| m.estimated_entry_count()),
+            Some(9)
+        );
+        assert_eq!(track!(header.dimensions())?, [3]);
+
+        // A size without the padding is rejected instead of misaligning the following messages.
+        let mut unaligned = vec![0x03, 0, 12, 0, 0, 0, 0, 0];
+        unaligned.extend_from_slice(&datatype);
+        let error = HeaderMessage::from_reader(&unaligned[..], &ReaderOptions::new()).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+        Ok(())
+    }
+
+    #[test]
+    fn group_info_works() -> TopLevelResult {
+        let m = track!(GroupInfoMessage::from_reader(
+            &[0, 0b11, 8, 0, 6, 0, 100, 0, 12, 0][..]
+        ))?;
+        assert_eq!(m.max_compact_links(), Some(8));
+        assert_eq!(m.min_dense_links(), Some(6));
+        assert_eq!(m.estimated_entry_count(), Some(100));
+        assert_eq!(m.estimated_name_length(), Some(12));
+
+        // Only the estimates are stored, padded as in a version 1 object header.
+        let bytes = [0x0A, 0, 8, 0, 0, 0, 0, 0, 0, 0b10, 100, 0, 12, 0, 0, 0];
+        let message = track!(HeaderMessage::from_reader(
+            &bytes[..],
+            &ReaderOptions::new()
+        ))?;
+        match message.message() {
+            Message::GroupInfo(m) => {
+                assert_eq!(m.max_compact_links(), None);
+                assert_eq!(m.estimated_entry_count(), Some(100));
+            }
+            m => panic!("{:?}", m),
+        }
+        assert_eq!(
+            message.message().to_string(),
+            "Group Info: about 100 entries"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn raw_message_bytes_are_kept() -> TopLevelResult {
+        let mut bytes = vec![0x01, 0x00, 16, 0, 0b0000_0001, 0xAA, 0xBB, 0xCC];
+        bytes.extend_from_slice(&[1, 1, 0, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&5u64.to_le_bytes());
+
+        let message = track!(HeaderMessage::from_reader(
+            &bytes[..],
+            &ReaderOptions::new()
+        ))?;
+        assert_eq!(message.raw_bytes(), &bytes[..]);
+        match message.message() {
+            Message::Dataspace(m) => assert_eq!(m.dimension_sizes, [5]),
+            m => panic!("{:?}", m),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn filtered_contiguous_data_works() -> TopLevelResult {
+        let datatype = FixedPointDatatype {
+            bit_field: 0,
+            size: 2,
+            bit_offset: 0,
+            bit_precision: 16,
+        };
+        let mut header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[3], 6);
+        header.prefix.messages.push(HeaderMessage {
+            flags: HeaderMessageFlags::empty(),
+            raw: Vec::new(),
+            message: Message::FilterPipeline(FilterPipelineMessage {
+                filters: vec![Filter {
+                    id: 2,
+                    name: None,
+                    flags: 0,
+                    client_data: vec![2],
+                }],
+            }),
+        });
+
+        // The values 1, 2 and 256 shuffled byte-wise.
+        let data = [1, 2, 0, 0, 0, 1];
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        let (items, _) = track!(object.into_vec::<u16>())?;
+        assert_eq!(items, [1, 2, 256]);
+        Ok(())
+    }
+
+    #[test]
+    fn compact_layout_works() -> TopLevelResult {
+        let bytes = [3, 0, 3, 0, 7, 8, 9, 0, 0, 0];
+        let layout = track!(DataLayoutMessage::from_reader(&bytes[..]))?;
+        let datatype = FixedPointDatatype {
+            bit_field: 0,
+            size: 1,
+            bit_offset: 0,
+            bit_precision: 8,
+        };
+        let mut header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[3], 0);
+        header.prefix.messages[2].message = Message::DataLayout(layout);
+
+        let object = track!(header.get_data_object(Cursor::new(&[][..]), &ReaderOptions::new()))?;
+        let (items, _) = track!(object.into_vec::<u8>())?;
+        assert_eq!(items, [7, 8, 9]);
+        Ok(())
+    }
+
+    #[test]
+    fn floating_point_decode_works() -> TopLevelResult {
+        let datatype = FloatingPointDatatype {
+            size: 4,
+            endian: Endian::Little,
+            low_padding_bit: 0,
+            high_padding_bit: 0,
+            internal_padding_bit: 0,
+            mantissa_norm: MantissaNorm::ImpliedToBeSet,
+            sign_location: 31,
+            bit_offset: 0,
+            bit_precision: 32,
+            exponent_location: 23,
+            exponent_size: 8,
+            mantissa_location: 0,
+            mantissa_size: 23,
+            exponent_bias: 127,
+        };
+        let bytes = [166, 73, 90, 67];
+        assert!(datatype.is_standard_ieee32());
+        assert!(!datatype.is_standard_ieee64());
+
+        let item = track!(datatype.decode(&bytes[..]))?;
+        assert_eq!(item, 218.28768920898438);
+        Ok(())
+    }
+
+    #[test]
+    fn single_precision_float_is_read_as_f32() -> TopLevelResult {
+        let mut datatype = FloatingPointDatatype {
+            size: 4,
+            endian: Endian::Little,
+            low_padding_bit: 0,
+            high_padding_bit: 0,
+            internal_padding_bit: 0,
+            mantissa_norm: MantissaNorm::ImpliedToBeSet,
+            sign_location: 31,
+            bit_offset: 0,
+            bit_precision: 32,
+            exponent_location: 23,
+            exponent_size: 8,
+            mantissa_location: 0,
+            mantissa_size: 23,
+            exponent_bias: 127,
+        };
+        let header = dataset_header(DatatypeMessage::FloatingPoint(datatype.clone()), &[2], 8);
+        let data = [166, 73, 90, 67, 0, 0, 192, 191];
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        assert!(matches!(object, DataObject::Float32(_)));
+        let (items, _) = track!(object.into_vec::<f32>())?;
+        assert_eq!(items, [218.28769f32, -1.5]);
+
+        datatype.endian = Endian::Big;
+        let header = dataset_header(DatatypeMessage::FloatingPoint(datatype.clone()), &[2], 8);
+        let data = [67, 90, 73, 166, 191, 192, 0, 0];
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        let (items, _) = track!(object.into_vec::<f64>())?;
+        assert_eq!(items, [218.28768920898438, -1.5]);
+
+        let datatype = DatatypeMessage::FloatingPoint(datatype);
+        assert_eq!(
+            track!(datatype.decode_scalar(&data[4..]))?,
+            ScalarValue::F32(-1.5)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn into_ndarray_works() -> TopLevelResult {
+        let float = |items: &[f64]| {
+            array::from_shape_vec(vec![items.len()], items.to_vec()).map(DataObject::Float)
+        };
+
+        let object = track!(float(&[0.5, 1.0, -2.0]))?;
+        let (items, shape) = track!(object.into_vec::<f32>())?;
+        assert_eq!(items, [0.5f32, 1.0, -2.0]);
+        assert_eq!(shape, [3]);
+
+        #[cfg(feature = "ndarray")]
+        {
+            let object = track!(float(&[0.5, 1.0, -2.0]))?;
+            let array = track!(object.into_ndarray::<f32>())?;
+            assert_eq!(array.as_slice(), Some(&[0.5f32, 1.0, -2.0][..]));
+        }
+
+        let object = track!(float(&[0.1]))?;
+        assert!(object.into_vec::<f32>().is_err());
+
+        let object = track!(float(&[1.0]))?;
+        assert!(object.into_vec::<i32>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn attribute_info_works() -> TopLevelResult {
+        let mut bytes = vec![1, 0, 6, 0, 12, 0, 16, 0];
+        bytes.extend_from_slice(b"scale\0\0\0");
+        bytes.extend_from_slice(&[0x10, 0, 0, 0, 2, 0, 0, 0, 0, 0, 16, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&[1, 1, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&[1, 0, 2, 0, 3, 0, 0, 0]);
+        let attribute = track!(AttributeMessage::from_reader(&bytes[..]))?;
+        assert_eq!(attribute.name, "scale");
+
+        let mut header = dataset_header(
+            DatatypeMessage::FloatingPoint(FloatingPointDatatype {
+                size: 4,
+                endian: Endian::Little,
+                low_padding_bit: 0,
+                high_padding_bit: 0,
+                internal_padding_bit: 0,
+                mantissa_norm: MantissaNorm::ImpliedToBeSet,
+                sign_location: 31,
+                bit_offset: 0,
+                bit_precision: 32,
+                exponent_location: 23,
+                exponent_size: 8,
+                mantissa_location: 0,
+                mantissa_size: 23,
+                exponent_bias: 127,
+            }),
+            &[4],
+            16,
+        );
+        header.prefix.messages.push(HeaderMessage {
+            flags: HeaderMessageFlags::empty(),
+            raw: Vec::new(),
+            message: Message::Attribute(attribute),
+        });
+
+        let (datatype, shape) = track!(header.attribute_info("scale"))?;
+        assert_eq!(datatype.class(), DatatypeClass::FixedPoint);
+        assert_eq!(shape, [3]);
+        assert!(header.attribute_info("offset").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn copy_chunk_works() -> TopLevelResult {
+        let mut bytes = vec![0; 16];
+        let chunks: [(&[u64], [u8; 4]); 4] = [
+            (&[0, 0], [0, 1, 4, 5]),
+            (&[0, 2], [2, 3, 6, 7]),
+            (&[2, 0], [8, 9, 12, 13]),
+            (&[2, 2], [10, 11, 14, 15]),
+        ];
+        for (offset, chunk) in &chunks {
+            track!(copy_chunk(&mut bytes, &[4, 4], &[2, 2], offset, chunk, 1))?;
+        }
+        assert_eq!(bytes, (0..16).collect::<Vec<u8>>());
+
+        assert!(copy_chunk(&mut bytes, &[4, 4], &[2, 2], &[1, 0], &[0; 4], 1).is_err());
+        assert!(copy_chunk(&mut bytes, &[4, 4], &[2, 2], &[4, 0], &[0; 4], 1).is_err());
+        assert!(copy_chunk(&mut bytes, &[4, 4], &[2, 2], &[0, 0], &[0; 3], 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn copy_chunk_clips_edge_chunks() -> TopLevelResult {
+        // A 10x10 dataset of 2-byte elements chunked 4x4, whose values are their row-major indices.
+        let mut bytes = vec![0; 200];
+        for row in (0..10).step_by(4) {
+            for column in (0..10).step_by(4) {
+                let mut chunk = Vec::new();
+                for i in row..row + 4 {
+                    for j in column..column + 4 {
+                        let value = if i < 10 && j < 10 { i * 10 + j } else { 0xFFFF };
+                        chunk.extend_from_slice(&(value as u16).to_le_bytes());
+                    }
+                }
+                let offset = [row as u64, column as u64];
+                track!(copy_chunk(
+                    &mut bytes,
+                    &[10, 10],
+                    &[4, 4],
+                    &offset,
+                    &chunk,
+                    2
+                ))?;
+            }
+        }
+        let values = bytes
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(values, (0..100).collect::<Vec<u16>>());
+        Ok(())
+    }
+
+    #[test]
+    fn messages_works() {
+        let datatype = FixedPointDatatype {
+            bit_field: 0,
+            size: 1,
+            bit_offset: 0,
+            bit_precision: 8,
+        };
+        let header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[3], 3);
+        let kinds = header.messages().map(|(kind, _)| kind).collect::<Vec<_>>();
+        assert_eq!(kinds, [0x01, 0x03, 0x08]);
+        assert!(matches!(
+            header.messages().nth(1),
+            Some((_, Message::Datatype(_)))
+        ));
+    }
+
+    #[test]
+    fn negative_fixed_point_works() -> TopLevelResult {
+        for &size in &[2u32, 4, 8] {
+            for &endian in &[0, 1] {
+                let datatype = FixedPointDatatype {
+                    bit_field: 0b0000_1000 | endian,
+                    size,
+                    bit_offset: 0,
+                    bit_precision: size as u16 * 8,
+                };
+                for &v in &[-1i16, -2, i16::MIN, i16::MAX] {
+                    let mut bytes = i64::from(v).to_le_bytes()[..size as usize].to_vec();
+                    if endian == 1 {
+                        bytes.reverse();
+                    }
+                    assert_eq!(track!(datatype.decode(&bytes[..]))?, i128::from(v));
+                }
+            }
+        }
+
+        // 12-bit values at bit offset 4 within two bytes.
+        let datatype = FixedPointDatatype {
+            bit_field: 0b0000_1000,
+            size: 2,
+            bit_offset: 4,
+            bit_precision: 12,
+        };
+        assert_eq!(track!(datatype.decode(&0xFFF0u16.to_le_bytes()[..]))?, -1);
+        assert_eq!(
+            track!(datatype.decode(&0x8000u16.to_le_bytes()[..]))?,
+            -2048
+        );
+
+        // A reserved bit suggests an unknown representation.
+        let datatype = FixedPointDatatype {
+            bit_field: 0b0001_1000,
+            size: 2,
+            bit_offset: 0,
+            bit_precision: 16,
+        };
+        let error = datatype.decode(&[0xFF, 0xFF][..]).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::Unsupported);
+        Ok(())
+    }
+
+    #[test]
+    fn floating_point_decode_with_custom_bias_works() -> TopLevelResult {
+        let mut datatype = FloatingPointDatatype {
+            size: 8,
+            endian: Endian::Little,
+            low_padding_bit: 0,
+            high_padding_bit: 0,
+            internal_padding_bit: 0,
+            mantissa_norm: MantissaNorm::ImpliedToBeSet,
+            sign_location: 63,
+            bit_offset: 0,
+            bit_precision: 64,
+            exponent_location: 52,
+            exponent_size: 11,
+            mantissa_location: 0,
+            mantissa_size: 52,
+            exponent_bias: 1023,
+        };
+        assert!(datatype.is_standard_ieee64());
+        for &v in &[-1.5f64, 1e-310, 123.456, f64::INFINITY] {
+            assert_eq!(track!(datatype.decode(&v.to_le_bytes()[..]))?, v);
+        }
+
+        datatype.endian = Endian::Big;
+        assert!(datatype.is_standard_ieee64());
+        assert_eq!(
+            track!(datatype.decode(&123.456f64.to_be_bytes()[..]))?,
+            123.456
+        );
+
+        datatype.endian = Endian::Little;
+        datatype.exponent_bias = 1020;
+        assert!(!datatype.is_standard_ieee64());
+        let item = track!(datatype.decode(&(-1.5f64).to_le_bytes()[..]))?;
+        assert_eq!(item, -12.0);
+        Ok(())
+    }
+
+    #[test]
+    fn lenient_header_message_works() -> TopLevelResult {
+        // An "Object Comment" message, which isn't supported yet.
+        let bytes = [0x0D, 0, 8, 0, 0, 0, 0, 0, b'h', b'i', 0, 0, 0, 0, 0, 0];
+
+        let strict = ReaderOptions::new();
+        let e = track_assert_some!(
+            HeaderMessage::from_reader(&bytes[..], &strict).err(),
+            ErrorKind::Other
+        );
+        assert_eq!(*e.kind(), ErrorKind::Unsupported);
+
+        let mut lenient = ReaderOptions::new();
+        lenient.lenient(true);
+        let m = track!(HeaderMessage::from_reader(&bytes[..], &lenient))?;
+        assert_eq!(m.message.kind(), 0x0D);
+        if let Message::Unknown { raw, .. } = m.message {
+            assert_eq!(raw, &bytes[8..]);
+        } else {
+            panic!();
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bool_enum_works() -> TopLevelResult {
+        let mut bytes = vec![0x18, 2, 0, 0, 1, 0, 0, 0];
+        bytes.extend_from_slice(&[0x10, 0, 0, 0, 1, 0, 0, 0, 0, 0, 8, 0]);
+        bytes.extend_from_slice(b"FALSE\0\0\0TRUE\0\0\0\0");
+        bytes.extend_from_slice(&[0, 1]);
+        let datatype = track!(DatatypeMessage::from_reader(&bytes[..]))?;
+        assert_eq!(datatype.class(), DatatypeClass::Enumerated);
+
+        let header = dataset_header(datatype, &[3], 3);
+        let data = [1, 0, 1];
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        let array = track!(object.into_array::<bool>())?;
+        assert_eq!(array.as_slice(), Some(&[true, false, true][..]));
+        Ok(())
+    }
+
+    #[test]
+    fn fixed_length_string_works() -> TopLevelResult {
+        // Null-terminated, ASCII.
+        let datatype = track!(DatatypeMessage::from_reader(
+            &[0x13, 0, 0, 0, 6, 0, 0, 0][..]
+        ))?;
+        assert_eq!(datatype.class(), DatatypeClass::String);
+        let value = track!(datatype.decode_scalar(&b"m/s\0\0\0"[..]))?;
+        assert_eq!(value, ScalarValue::Str("m/s".to_owned()));
+
+        // Space-padded, UTF-8.
+        let datatype = track!(DatatypeMessage::from_reader(
+            &[0x13, 0x12, 0, 0, 6, 0, 0, 0][..]
+        ))?;
+        let value = track!(datatype.decode_scalar("µm   ".as_bytes()))?;
+        assert_eq!(value, ScalarValue::Str("µm".to_owned()));
+        match datatype {
+            DatatypeMessage::String(ref t) => assert_eq!(t.character_set(), CharacterSet::Utf8),
+            _ => panic!("{:?}", datatype),
+        }
+
+        // Non-ASCII bytes aren't allowed in ASCII strings.
+        let datatype = track!(DatatypeMessage::from_reader(
+            &[0x13, 0x02, 0, 0, 6, 0, 0, 0][..]
+        ))?;
+        let error = datatype.decode_scalar("µm   ".as_bytes()).unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidFile);
+
+        // Unknown character set.
+        assert!(DatatypeMessage::from_reader(&[0x13, 0x22, 0, 0, 6, 0, 0, 0][..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn opaque_works() -> TopLevelResult {
+        let mut bytes = vec![0x15, 8, 0, 0, 3, 0, 0, 0];
+        bytes.extend_from_slice(b"rgb\0\0\0\0\0");
+        let datatype = track!(DatatypeMessage::from_reader(&bytes[..]))?;
+        match datatype {
+            DatatypeMessage::Opaque(ref t) => assert_eq!(t.tag(), "rgb"),
+            _ => panic!("{:?}", datatype),
+        }
+        assert_eq!(datatype.to_string(), "opaque (3 bytes, tag=\"rgb\")");
+
+        let value = track!(datatype.decode_scalar(&[1, 2, 3][..]))?;
+        assert_eq!(value, ScalarValue::Bytes(vec![1, 2, 3]));
+        Ok(())
+    }
+
+    #[test]
+    fn signed_enum_works() -> TopLevelResult {
+        let mut bytes = vec![0x18, 3, 0, 0, 1, 0, 0, 0];
+        bytes.extend_from_slice(&[0x10, 0b0000_1000, 0, 0, 1, 0, 0, 0, 0, 0, 8, 0]);
+        bytes.extend_from_slice(b"MISSING\0OK\0\0\0\0\0\0BAD\0\0\0\0\0");
+        bytes.extend_from_slice(&[(-1i8) as u8, 0, (-128i8) as u8]);
+        let datatype = track!(DatatypeMessage::from_reader(&bytes[..]))?;
+        let t = match datatype {
+            DatatypeMessage::Enumerated(ref t) => t,
+            _ => panic!("{:?}", datatype),
+        };
+        assert_eq!(
+            track!(t.values())?,
+            [("MISSING", -1), ("OK", 0), ("BAD", -128)]
+        );
+        assert_eq!(track!(t.decode(&[0xFF][..]))?, (-1, Some("MISSING")));
+        assert_eq!(track!(t.decode(&[0x80][..]))?, (-128, Some("BAD")));
+        assert_eq!(track!(t.decode(&[0x05][..]))?, (5, None));
+        assert_eq!(
+            track!(datatype.decode_scalar(&[0xFF][..]))?,
+            ScalarValue::I8(-1)
+        );
+
+        let header = dataset_header(datatype, &[3], 3);
+        let data = [0, 0xFF, 0x80];
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        match object {
+            DataObject::Enum {
+                ref codes,
+                ref mapping,
+            } => {
+                assert_eq!(codes.as_slice(), Some(&[0, -1, -128][..]));
+                assert_eq!(mapping.len(), 3);
+                assert_eq!(mapping[0], ("MISSING".to_owned(), -1));
+                assert_eq!(mapping[2], ("BAD".to_owned(), -128));
+            }
+            _ => panic!("{:?}", object),
+        }
+        let array = track!(object.into_array::<i8>())?;
+        assert_eq!(array.as_slice(), Some(&[0, -1, -128][..]));
+        Ok(())
+    }
+
+    #[test]
+    fn fixed_point_decode_works() -> TopLevelResult {
+        let i32_le = FixedPointDatatype {
+            bit_field: 0b0000_1000, // little-endian, signed
+            size: 4,
+            bit_offset: 0,
+            bit_precision: 32,
+        };
+        for &v in &[0, 1, -1, i32::MIN, i32::MAX] {
+            assert_eq!(track!(i32_le.decode(&v.to_le_bytes()[..]))?, i128::from(v));
+        }
+
+        let i32_be = FixedPointDatatype {
+            bit_field: 0b0000_1001, // big-endian, signed
+            ..i32_le
+        };
+        assert_eq!(track!(i32_be.decode(&(-2i32).to_be_bytes()[..]))?, -2);
+
+        let u16_be = FixedPointDatatype {
+            bit_field: 0b0000_0001, // big-endian, unsigned
+            size: 2,
+            bit_offset: 0,
+            bit_precision: 16,
+        };
+        assert_eq!(track!(u16_be.decode(&[0xFF, 0xFE][..]))?, 0xFFFE);
+
+        let u16_le = FixedPointDatatype {
+            bit_field: 0,
+            ..u16_be
+        };
+        assert_eq!(track!(u16_le.decode(&[0xFF, 0xFE][..]))?, 0xFEFF);
+
+        // A signed 12-bit value at bit offset 2 with garbage in the padding bits.
+        let i12_padded = FixedPointDatatype {
+            bit_field: 0b0000_1110, // little-endian, low/high padding bits set, signed
+            size: 2,
+            bit_offset: 2,
+            bit_precision: 12,
+        };
+        assert_eq!(track!(i12_padded.decode(&0xFFF7u16.to_le_bytes()[..]))?, -3);
+        assert_eq!(track!(i12_padded.decode(&0x4016u16.to_le_bytes()[..]))?, 5);
+
+        let header = dataset_header(DatatypeMessage::FixedPoint(i32_be), &[2], 8);
+        let data = [0xFF, 0xFF, 0xFF, 0xFD, 0, 0, 0, 7];
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        let array = track!(object.into_array::<i16>())?;
+        assert_eq!(array.as_slice(), Some(&[-3, 7][..]));
+        Ok(())
+    }
+
+    #[test]
+    fn eight_byte_fixed_point_works() -> TopLevelResult {
+        let i64_le = FixedPointDatatype {
+            bit_field: 0b0000_1000, // little-endian, signed
+            size: 8,
+            bit_offset: 0,
+            bit_precision: 64,
+        };
+        let i64_be = FixedPointDatatype {
+            bit_field: 0b0000_1001, // big-endian, signed
+            ..i64_le
+        };
+        for &v in &[0, -1, i64::MIN, i64::MAX] {
+            assert_eq!(track!(i64_le.decode(&v.to_le_bytes()[..]))?, i128::from(v));
+            assert_eq!(track!(i64_be.decode(&v.to_be_bytes()[..]))?, i128::from(v));
+        }
+
+        let u64_le = FixedPointDatatype {
+            bit_field: 0, // little-endian, unsigned
+            ..i64_le
+        };
+        let u64_be = FixedPointDatatype {
+            bit_field: 0b0000_0001, // big-endian, unsigned
+            ..i64_le
+        };
+        for &v in &[0, 1 << 63, u64::MAX] {
+            assert_eq!(track!(u64_le.decode(&v.to_le_bytes()[..]))?, i128::from(v));
+            assert_eq!(track!(u64_be.decode(&v.to_be_bytes()[..]))?, i128::from(v));
+        }
+
+        let header = dataset_header(DatatypeMessage::FixedPoint(i64_be), &[2], 16);
+        let mut data = i64::MIN.to_be_bytes().to_vec();
+        data.extend_from_slice(&i64::MAX.to_be_bytes());
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        let array = track!(object.into_array::<i64>())?;
+        assert_eq!(array.as_slice(), Some(&[i64::MIN, i64::MAX][..]));
+
+        let header = dataset_header(DatatypeMessage::FixedPoint(u64_le), &[2], 16);
+        let mut data = u64::MAX.to_le_bytes().to_vec();
+        data.extend_from_slice(&42u64.to_le_bytes());
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        let array = track!(object.into_array::<u64>())?;
+        assert_eq!(array.as_slice(), Some(&[u64::MAX, 42][..]));
+        Ok(())
+    }
+
+    #[test]
+    fn fill_value_scalar_works() -> TopLevelResult {
+        let datatype = FixedPointDatatype {
+            bit_field: 0b0000_1000, // little-endian, signed
+            size: 2,
+            bit_offset: 0,
+            bit_precision: 16,
+        };
+        let mut header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[2], 4);
+        assert_eq!(track!(header.fill_value_scalar())?, None);
+
+        header.prefix.messages.push(HeaderMessage {
+            flags: HeaderMessageFlags::empty(),
+            raw: Vec::new(),
+            message: Message::FillValue(FillValueMessage {
+                fill_value: Some((-5i16).to_le_bytes().to_vec()),
+            }),
+        });
+        assert_eq!(
+            track!(header.fill_value_scalar())?,
+            Some(ScalarValue::I16(-5))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn resolved_fill_value_works() -> TopLevelResult {
+        let datatype = FixedPointDatatype {
+            bit_field: 0,
+            size: 2,
+            bit_offset: 0,
+            bit_precision: 16,
+        };
+        let mut header = dataset_header(DatatypeMessage::FixedPoint(datatype), &[3], 6);
+        header.prefix.messages.retain(|m| m.message.kind() != 0x08);
+        header.prefix.messages.push(HeaderMessage {
+            flags: HeaderMessageFlags::empty(),
+            raw: Vec::new(),
+            message: Message::DataLayout(DataLayoutMessage {
+                layout: Layout::Contiguous {
+                    address: UNDEFINED_ADDRESS,
+                    size: 6,
+                },
+            }),
+        });
+        assert_eq!(track!(header.resolved_fill_value())?, [0, 0]);
+
+        let mut bytes = vec![2, 0, 0, 0];
+        bytes.extend_from_slice(&7u16.to_le_bytes());
+        let message = track!(HeaderMessage::read_message(0x04, &bytes[..]))?;
+        assert_eq!(message.to_string(), "Fill Value (old): 2 bytes");
+        header.prefix.messages.push(HeaderMessage {
+            flags: HeaderMessageFlags::empty(),
+            raw: Vec::new(),
+            message,
+        });
+        assert_eq!(track!(header.resolved_fill_value())?, [7, 0]);
+
+        // The new message takes precedence over the old one.
+        header.prefix.messages.push(HeaderMessage {
+            flags: HeaderMessageFlags::empty(),
+            raw: Vec::new(),
+            message: Message::FillValue(FillValueMessage {
+                fill_value: Some(9u16.to_le_bytes().to_vec()),
+            }),
+        });
+        assert_eq!(track!(header.resolved_fill_value())?, [9, 0]);
+
+        // Unallocated data consists of the fill value.
+        let object = track!(header.get_data_object(Cursor::new(&[][..]), &ReaderOptions::new()))?;
+        assert_eq!(track!(object.into_vec::<u16>())?.0, [9, 9, 9]);
+        let value = track!(header.read_element(Cursor::new(&[][..]), &ReaderOptions::new(), 2))?;
+        assert_eq!(value, ScalarValue::U16(9));
+        Ok(())
+    }
+
+    #[test]
+    fn nested_compound_works() -> TopLevelResult {
+        fn fixed_point(size: u8, signed: bool) -> Vec<u8> {
+            let bit_field = if signed { 0b0000_1000 } else { 0 };
+            vec![0x10, bit_field, 0, 0, size, 0, 0, 0, 0, 0, size * 8, 0]
+        }
+        fn member(name: &str, offset: u8, dimensions: &[u8], datatype: Vec<u8>) -> Vec<u8> {
+            let mut bytes = name.as_bytes().to_vec();
+            bytes.resize((name.len() + 1 + 7) / 8 * 8, 0);
+            bytes.extend_from_slice(&[offset, 0, 0, 0, dimensions.len() as u8, 0, 0, 0]);
+            bytes.extend_from_slice(&[0; 8]);
+            for i in 0..4 {
+                let d = dimensions.get(i).cloned().unwrap_or(0);
+                bytes.extend_from_slice(&[d, 0, 0, 0]);
+            }
+            bytes.extend(datatype);
+            bytes
+        }
+        fn compound(size: u8, members: Vec<Vec<u8>>) -> Vec<u8> {
+            let mut bytes = vec![0x16, members.len() as u8, 0, 0, size, 0, 0, 0];
+            bytes.extend(members.into_iter().flatten());
+            bytes
+        }
+
+        let inner = compound(
+            4,
+            vec![
+                member("x", 0, &[], fixed_point(2, true)),
+                member("y", 2, &[2], fixed_point(1, false)),
+            ],
+        );
+        let outer = compound(
+            6,
+            vec![
+                member("a", 0, &[], fixed_point(1, false)),
+                member("inner", 2, &[], inner),
+            ],
+        );
+        let datatype = track!(DatatypeMessage::from_reader(&outer[..]))?;
+        assert_eq!(datatype.size(), 6);
+
+        let header = dataset_header(datatype, &[1], 6);
+        let data = [7, 0, 0xFE, 0xFF, 3, 4];
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        let a = track_assert_some!(object.column("a"), ErrorKind::Other);
+        assert_eq!(track!(a.to_le_bytes())?, [7]);
+        let inner = track_assert_some!(object.column("inner"), ErrorKind::Other);
+        let x = track_assert_some!(inner.column("x"), ErrorKind::Other);
+        assert_eq!(track!(x.to_le_bytes())?, (-2i64).to_le_bytes());
+
+        // The array member has no typed column.
+        let y = track_assert_some!(inner.column("y"), ErrorKind::Other);
+        if let DataObject::Values(a) = y {
+            let expected = ScalarValue::Array(vec![ScalarValue::U8(3), ScalarValue::U8(4)]);
+            assert_eq!(a.iter().cloned().collect::<Vec<_>>(), [expected]);
+        } else {
+            panic!("Unexpected column: {:?}", y);
+        }
+
+        let too_small = compound(3, vec![member("x", 2, &[], fixed_point(2, true))]);
+        assert!(DatatypeMessage::from_reader(&too_small[..]).is_err());
+
+        // An aligned layout: a 4-byte member at offset 0 and an 8-byte member at offset 8.
+        let aligned = compound(
+            16,
+            vec![
+                member("a", 0, &[], fixed_point(4, true)),
+                member("b", 8, &[], fixed_point(8, true)),
+            ],
+        );
+        let datatype = track!(DatatypeMessage::from_reader(&aligned[..]))?;
+        let header = dataset_header(datatype, &[2], 32);
+        let mut data = Vec::new();
+        for (a, b) in [(-1i32, 2i64), (3, -4)] {
+            data.extend_from_slice(&a.to_le_bytes());
+            data.extend_from_slice(&[0xAA; 4]);
+            data.extend_from_slice(&b.to_le_bytes());
+        }
+        let object = track!(header.get_data_object(Cursor::new(&data[..]), &ReaderOptions::new()))?;
+        let table = if let DataObject::Compound(table) = object {
+            table
+        } else {
+            panic!("Unexpected data object: {:?}", object);
+        };
+        let columns = table
+            .into_columns()
+            .into_iter()
+            .map(|(name, column)| Ok((name, track!(column.into_vec::<i64>())?.0)))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            columns,
+            [("a".to_owned(), vec![-1, 3]), ("b".to_owned(), vec![2, -4])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn later_datatype_versions_work() -> TopLevelResult {
+        let i32_type = [0x10, 0b0000_1000, 0, 0, 4, 0, 0, 0, 0, 0, 32, 0];
+        let i64_type = [0x10, 0b0000_1000, 0, 0, 8, 0, 0, 0, 0, 0, 64, 0];
+
+        let mut v2_fixed_point = i32_type;
+        v2_fixed_point[0] = 0x20;
+        let datatype = track!(DatatypeMessage::from_reader(&v2_fixed_point[..]))?;
+        assert_eq!(
+            track!(datatype.decode_scalar(&[0xFF; 4][..]))?,
+            ScalarValue::I32(-1)
+        );
+
+        // Version 2 compound: padded names and 4-byte offsets, but no array dimensions.
+        let mut v2_compound = vec![0x26, 2, 0, 0, 16, 0, 0, 0];
+        v2_compound.extend_from_slice(b"a\0\0\0\0\0\0\0");
+        v2_compound.extend_from_slice(&0u32.to_le_bytes());
+        v2_compound.extend_from_slice(&i32_type);
+        v2_compound.extend_from_slice(b"b\0\0\0\0\0\0\0");
+        v2_compound.extend_from_slice(&8u32.to_le_bytes());
+        v2_compound.extend_from_slice(&i64_type);
+
+        // Version 3 compound: unpadded names and 1-byte offsets (as the size is less than 256).
+        let mut v3_compound = vec![0x36, 2, 0, 0, 16, 0, 0, 0];
+        v3_compound.extend_from_slice(b"a\0\0");
+        v3_compound.extend_from_slice(&i32_type);
+        v3_compound.extend_from_slice(b"b\0\x08");
+        v3_compound.extend_from_slice(&i64_type);
+
+        let mut data = (-3i32).to_le_bytes().to_vec();
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&7i64.to_le_bytes());
+        for bytes in &[v2_compound, v3_compound] {
+            let mut reader = &bytes[..];
+            let datatype = track!(DatatypeMessage::from_reader(&mut reader))?;
+            assert_eq!(reader, b"");
+            assert_eq!(
+                track!(datatype.decode_scalar(&data[..]))?,
+                ScalarValue::Compound(vec![
+                    ("a".to_owned(), ScalarValue::I32(-3)),
+                    ("b".to_owned(), ScalarValue::I64(7)),
+                ])
+            );
+        }
+
+        // Version 3 enumeration: unpadded names.
+        let mut v3_enum = vec![0x38, 2, 0, 0, 1, 0, 0, 0];
+        v3_enum.extend_from_slice(&[0x10, 0, 0, 0, 1, 0, 0, 0, 0, 0, 8, 0]);
+        v3_enum.extend_from_slice(b"lo\0hi\0");
+        v3_enum.extend_from_slice(&[0, 1]);
+        let mut reader = &v3_enum[..];
+        let datatype = track!(DatatypeMessage::from_reader(&mut reader))?;
+        assert_eq!(reader, b"");
+        if let DatatypeMessage::Enumerated(t) = datatype {
+            assert_eq!(t.members()[1], ("hi".to_owned(), vec![1]));
+        } else {
+            panic!("Unexpected datatype: {:?}", datatype);
+        }
+
+        let mut v4_fixed_point = i32_type;
+        v4_fixed_point[0] = 0x40;
+        assert!(DatatypeMessage::from_reader(&v4_fixed_point[..]).is_err());
         Ok(())
     }
 }
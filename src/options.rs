@@ -0,0 +1,201 @@
+use crate::filter::{self, FilterRegistry};
+use crate::io::DEFAULT_PREALLOCATION_LIMIT;
+use crate::{ErrorKind, Result};
+
+/// Options for reading HDF5 files.
+///
+/// # Examples
+///
+/// ```no_run
+/// use hdf5file::{Hdf5File, ReaderOptions};
+///
+/// let mut options = ReaderOptions::new();
+/// options.verify_checksums(false).max_allocation_size(1 << 30);
+/// let file = Hdf5File::open_file_with("foo.h5", options)?;
+/// # Ok::<(), hdf5file::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReaderOptions {
+    pub(crate) filters: FilterRegistry,
+    verify_checksums: bool,
+    max_allocation_size: Option<u64>,
+    preallocation_limit: usize,
+    lenient: bool,
+    skip_corrupt_chunks: bool,
+    reject_inconsistent_files: bool,
+}
+impl ReaderOptions {
+    /// Makes a new `ReaderOptions` instance with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the registry of the filters used to decode chunks.
+    ///
+    /// The default value is `FilterRegistry::default()`.
+    pub fn filter_registry(&mut self, registry: FilterRegistry) -> &mut Self {
+        self.filters = registry;
+        self
+    }
+
+    /// Sets whether checksums (e.g., those of the Fletcher32 filter and of version 2 object headers and B-trees) are verified.
+    ///
+    /// If `false`, checksums are stripped from data without being verified.
+    /// Note that this replaces the Fletcher32 decoder of the filter registry.
+    ///
+    /// The default value is `true`.
+    pub fn verify_checksums(&mut self, verify: bool) -> &mut Self {
+        self.verify_checksums = verify;
+        self
+    }
+
+    pub(crate) fn is_verifying_checksums(&self) -> bool {
+        self.verify_checksums
+    }
+
+    /// Sets the maximum number of bytes that can be read at once as raw data (e.g., a chunk).
+    ///
+    /// This guards against huge allocations caused by corrupted or malicious files.
+    ///
+    /// The default value is `None` (unlimited).
+    pub fn max_allocation_size(&mut self, size: impl Into<Option<u64>>) -> &mut Self {
+        self.max_allocation_size = size.into();
+        self
+    }
+
+    /// Sets the maximum number of bytes allocated up front when reading raw data (e.g., a chunk).
+    ///
+    /// Larger data is still read, but its buffer grows as the bytes actually arrive,
+    /// so a bogus size field doesn't cause a huge allocation before the read fails.
+    ///
+    /// The default value is `1048576` (1 MiB).
+    pub fn preallocation_limit(&mut self, limit: usize) -> &mut Self {
+        self.preallocation_limit = limit;
+        self
+    }
+
+    pub(crate) fn get_preallocation_limit(&self) -> usize {
+        self.preallocation_limit
+    }
+
+    /// Sets whether object headers containing unknown or unsupported messages can be read.
+    ///
+    /// If `true`, such messages are kept as raw bytes instead of causing an error,
+    /// so the other messages of the header are still available.
+    /// If `false` (strict mode), an `ErrorKind::Unsupported` error will be returned.
+    ///
+    /// The default value is `false`.
+    pub fn lenient(&mut self, lenient: bool) -> &mut Self {
+        self.lenient = lenient;
+        self
+    }
+
+    pub(crate) fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    /// Sets whether chunks that fail to be read or decoded (e.g., due to checksum mismatches)
+    /// are replaced with the fill value (or zeros) instead of causing an error.
+    ///
+    /// The offsets of the replaced chunks are reported by `Dataset::read_with_corrupt_chunks`.
+    ///
+    /// The default value is `false`.
+    pub fn skip_corrupt_chunks(&mut self, skip: bool) -> &mut Self {
+        self.skip_corrupt_chunks = skip;
+        self
+    }
+
+    pub(crate) fn is_skipping_corrupt_chunks(&self) -> bool {
+        self.skip_corrupt_chunks
+    }
+
+    /// Sets whether files whose superblock has consistency flags set are rejected when they are opened.
+    ///
+    /// Such files may still be open for writing (or the writer may have crashed),
+    /// so an `ErrorKind::InvalidFile` error will be returned for them if `true`.
+    /// See `Superblock::is_consistent`.
+    ///
+    /// The default value is `false`.
+    pub fn reject_inconsistent_files(&mut self, reject: bool) -> &mut Self {
+        self.reject_inconsistent_files = reject;
+        self
+    }
+
+    pub(crate) fn is_rejecting_inconsistent_files(&self) -> bool {
+        self.reject_inconsistent_files
+    }
+
+    /// Returns an error if reading `size` bytes at once isn't allowed.
+    pub(crate) fn check_allocation_size(&self, size: u64) -> Result<()> {
+        if let Some(max) = self.max_allocation_size {
+            track_assert!(
+                size <= max,
+                ErrorKind::InvalidInput,
+                "Too large allocation: size={}, max={}",
+                size,
+                max
+            );
+        }
+        Ok(())
+    }
+
+    /// Applies the settings that affect the filter registry.
+    pub(crate) fn finalize(mut self) -> Self {
+        if !self.verify_checksums {
+            self.filters.register(filter::FILTER_FLETCHER32, |_, data| {
+                track!(filter::strip_fletcher32(data))
+            });
+        }
+        self
+    }
+}
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        Self {
+            filters: FilterRegistry::default(),
+            verify_checksums: true,
+            max_allocation_size: None,
+            preallocation_limit: DEFAULT_PREALLOCATION_LIMIT,
+            lenient: false,
+            skip_corrupt_chunks: false,
+            reject_inconsistent_files: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn verify_checksums_works() -> TopLevelResult {
+        let data = b"abcdef\0\0\0\0".to_vec();
+
+        let options = ReaderOptions::new().finalize();
+        assert!(options
+            .filters
+            .decode(filter::FILTER_FLETCHER32, &[], data.clone(), 6)
+            .is_err());
+
+        let options = ReaderOptions::new()
+            .verify_checksums(false)
+            .clone()
+            .finalize();
+        let decoded = track!(options
+            .filters
+            .decode(filter::FILTER_FLETCHER32, &[], data, 6))?;
+        assert_eq!(decoded, b"abcdef");
+        Ok(())
+    }
+
+    #[test]
+    fn max_allocation_size_works() {
+        let mut options = ReaderOptions::new();
+        assert!(options.check_allocation_size(u64::MAX).is_ok());
+
+        options.max_allocation_size(16);
+        assert!(options.check_allocation_size(16).is_ok());
+        assert!(options.check_allocation_size(17).is_err());
+    }
+}
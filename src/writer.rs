@@ -0,0 +1,813 @@
+//! Minimal support for writing HDF5 files.
+//!
+//! Only files whose root group contains contiguous datasets of little-endian numbers
+//! (`f32`, `f64`, `i8`, `u8`, `i16`, `u16`, `i32`, `u32`, `i64` and `u64`) can be written.
+//! Simple attributes can be attached to the root group and the datasets.
+//! This is mainly intended to produce small fixtures (e.g., for tests) without external tools.
+use crate::lowlevel::level0::UNDEFINED_ADDRESS;
+use crate::{Error, ErrorKind, Result, ScalarValue};
+use std::io::Write;
+
+const SUPERBLOCK_SIZE: u64 = 96;
+const GROUP_LEAF_NODE_K: usize = 4;
+const GROUP_INTERNAL_NODE_K: usize = 16;
+const SYMBOL_TABLE_ENTRY_SIZE: usize = 40;
+const LOCAL_HEAP_HEADER_SIZE: u64 = 32;
+const LOCAL_HEAP_FREE_NULL: u64 = 1;
+
+// The HDF5 library doesn't support datasets of more than 32 dimensions.
+const MAX_RANK: usize = 32;
+
+// Nodes are always written with room for all their entries, as the HDF5 library reads them as a whole.
+const B_TREE_NODE_SIZE: usize =
+    24 + (GROUP_INTERNAL_NODE_K * 2 + 1) * 8 + GROUP_INTERNAL_NODE_K * 2 * 8;
+const SYMBOL_TABLE_NODE_SIZE: usize = 8 + GROUP_LEAF_NODE_K * 2 * SYMBOL_TABLE_ENTRY_SIZE;
+
+/// Element type of the datasets that can be written by `Hdf5Writer`.
+pub trait Element: Copy {
+    /// Returns the body of the datatype message that describes this type.
+    fn datatype_message() -> Vec<u8>;
+
+    /// Appends the little-endian encoding of the value to `buf`.
+    fn encode(self, buf: &mut Vec<u8>);
+}
+impl Element for f32 {
+    fn datatype_message() -> Vec<u8> {
+        floating_point_datatype(4, 23, 8, 23, 127)
+    }
+
+    fn encode(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+}
+impl Element for f64 {
+    fn datatype_message() -> Vec<u8> {
+        floating_point_datatype(8, 52, 11, 52, 1023)
+    }
+
+    fn encode(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+macro_rules! impl_fixed_point_element {
+    ($t:ty, $signed:expr) => {
+        impl Element for $t {
+            fn datatype_message() -> Vec<u8> {
+                fixed_point_datatype(std::mem::size_of::<$t>() as u32, $signed)
+            }
+
+            fn encode(self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    };
+}
+impl_fixed_point_element!(i8, true);
+impl_fixed_point_element!(u8, false);
+impl_fixed_point_element!(i16, true);
+impl_fixed_point_element!(u16, false);
+impl_fixed_point_element!(i32, true);
+impl_fixed_point_element!(u32, false);
+impl_fixed_point_element!(i64, true);
+impl_fixed_point_element!(u64, false);
+
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#DatatypeMessage
+fn fixed_point_datatype(size: u32, signed: bool) -> Vec<u8> {
+    // Version 1, class 0; little-endian with zero padding.
+    let bit_field = if signed { 0b0000_1000 } else { 0 };
+    let mut message = vec![0x10, bit_field, 0, 0];
+    message.extend_from_slice(&size.to_le_bytes());
+    message.extend_from_slice(&0u16.to_le_bytes());
+    message.extend_from_slice(&(size as u16 * 8).to_le_bytes());
+    message
+}
+
+/// Returns the enumeration datatype used by h5py to store booleans.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#DatatypeMessage
+fn bool_datatype() -> Vec<u8> {
+    // Version 1, class 8 with two members over a 1-byte signed integer.
+    let mut message = vec![0x18, 2, 0, 0];
+    message.extend_from_slice(&1u32.to_le_bytes());
+    message.extend_from_slice(&fixed_point_datatype(1, true));
+    message.extend_from_slice(b"FALSE\0\0\0TRUE\0\0\0\0");
+    message.extend_from_slice(&[0, 1]);
+    message
+}
+
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#DatatypeMessage
+fn string_datatype(size: u32, ascii: bool) -> Vec<u8> {
+    // Version 1, class 3; null-terminated with the ASCII or UTF-8 character set.
+    let character_set = if ascii { 0 } else { 1 };
+    let mut message = vec![0x13, character_set << 4, 0, 0];
+    message.extend_from_slice(&size.to_le_bytes());
+    message
+}
+
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#DatatypeMessage
+fn floating_point_datatype(
+    size: u32,
+    exponent_location: u8,
+    exponent_size: u8,
+    mantissa_size: u8,
+    exponent_bias: u32,
+) -> Vec<u8> {
+    let precision = size * 8;
+
+    // Version 1, class 1; little-endian with an implied most significant mantissa bit.
+    let mut message = vec![0x11, 0b0010_0000, (precision - 1) as u8, 0];
+    message.extend_from_slice(&size.to_le_bytes());
+    message.extend_from_slice(&0u16.to_le_bytes());
+    message.extend_from_slice(&(precision as u16).to_le_bytes());
+    message.extend_from_slice(&[exponent_location, exponent_size, 0, mantissa_size]);
+    message.extend_from_slice(&exponent_bias.to_le_bytes());
+    message
+}
+
+/// Writer of HDF5 files.
+///
+/// Datasets are buffered in memory and the whole file is written by `finish`.
+///
+/// # Examples
+///
+/// ```
+/// use hdf5file::writer::Hdf5Writer;
+/// use hdf5file::Hdf5File;
+///
+/// let mut writer = Hdf5Writer::new(Vec::new());
+/// writer.add_dataset("values", &[2, 2], &[1.0f64, 2.0, 3.0, 4.0])?;
+/// let bytes = writer.finish()?;
+///
+/// let mut file = Hdf5File::from_bytes(bytes)?;
+/// let mut dataset = file.dataset("/values")?.expect("no such dataset");
+/// assert_eq!(dataset.shape()?, [2, 2]);
+/// # Ok::<(), hdf5file::Error>(())
+/// ```
+///
+/// Nothing is written until `finish` (or `close`) is called,
+/// because the superblock at the head of the file records the end-of-file address.
+/// A writer dropped without being finished leaves the output empty (and thus not a valid HDF5 file),
+/// and logs a warning about it.
+#[derive(Debug)]
+pub struct Hdf5Writer<W: Write> {
+    // `None` once the file has been written.
+    inner: Option<W>,
+    datasets: Vec<DatasetEntry>,
+    root_attributes: Vec<(String, Vec<u8>)>,
+}
+impl<W: Write> Hdf5Writer<W> {
+    /// Makes a new `Hdf5Writer` instance that writes a file to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner: Some(inner),
+            datasets: Vec::new(),
+            root_attributes: Vec::new(),
+        }
+    }
+
+    /// Adds a contiguous dataset to the root group.
+    ///
+    /// `data` holds the elements of the dataset in row-major order.
+    /// An empty `shape` makes a scalar dataset.
+    pub fn add_dataset<T: Element>(&mut self, name: &str, shape: &[u64], data: &[T]) -> Result<()> {
+        track_assert!(!name.is_empty(), ErrorKind::InvalidInput);
+        track_assert!(!name.contains('/'), ErrorKind::InvalidInput; name);
+        track_assert!(!name.contains('\0'), ErrorKind::InvalidInput; name);
+        track_assert!(shape.len() <= MAX_RANK, ErrorKind::InvalidInput; shape);
+        track_assert!(
+            self.datasets.iter().all(|d| d.name != name),
+            ErrorKind::InvalidInput,
+            "Duplicate dataset name: {:?}",
+            name
+        );
+        let count = track_assert_some!(
+            shape.iter().try_fold(1u64, |acc, &d| acc.checked_mul(d)),
+            ErrorKind::InvalidInput;
+            shape
+        );
+        track_assert_eq!(count, data.len() as u64, ErrorKind::InvalidInput; shape);
+
+        let mut bytes = Vec::with_capacity(std::mem::size_of_val(data));
+        for &v in data {
+            v.encode(&mut bytes);
+        }
+        self.datasets.push(DatasetEntry {
+            name: name.to_owned(),
+            shape: shape.to_vec(),
+            datatype: T::datatype_message(),
+            element_size: std::mem::size_of::<T>(),
+            data: bytes,
+            attributes: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Attaches an attribute to the root group (`"/"`) or a dataset added beforehand (e.g., `"/values"`).
+    ///
+    /// Numbers, booleans and strings are written as scalar attributes,
+    /// and `ScalarValue::Array`s of them as one-dimensional attributes.
+    /// The value can be read back as it is by `ObjectHeader::read_attribute` (or `Dataset::read_attribute`).
+    /// Other values result in an `ErrorKind::Unsupported` error.
+    pub fn attach_attribute(&mut self, path: &str, name: &str, value: ScalarValue) -> Result<()> {
+        track_assert!(!name.is_empty(), ErrorKind::InvalidInput);
+        track_assert!(!name.contains('\0'), ErrorKind::InvalidInput; name);
+        let message = track!(attribute_message(name, &value); name)?;
+        track!(message_size(&message); name)?;
+
+        let attributes = if path == "/" {
+            &mut self.root_attributes
+        } else {
+            let dataset_name = path.strip_prefix('/').unwrap_or(path);
+            let dataset = track_assert_some!(
+                self.datasets.iter_mut().find(|d| d.name == dataset_name),
+                ErrorKind::InvalidInput,
+                "No such dataset: {:?}",
+                path
+            );
+            &mut dataset.attributes
+        };
+        track_assert!(
+            attributes.iter().all(|(n, _)| n != name),
+            ErrorKind::InvalidInput,
+            "Duplicate attribute name: {:?}",
+            name
+        );
+        attributes.push((name.to_owned(), message));
+        Ok(())
+    }
+
+    /// Writes the file and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        let mut inner = track_assert_some!(self.inner.take(), ErrorKind::Other);
+        self.datasets.sort_by(|a, b| a.name.cmp(&b.name));
+        let node_count =
+            (self.datasets.len() + GROUP_LEAF_NODE_K * 2 - 1) / (GROUP_LEAF_NODE_K * 2);
+        track_assert!(
+            node_count <= GROUP_INTERNAL_NODE_K * 2,
+            ErrorKind::Unsupported,
+            "Too many datasets: {}",
+            self.datasets.len()
+        );
+
+        // The data segment of the local heap starts with the empty string (the name of the root group).
+        let mut heap_data = vec![0; 8];
+        let mut name_offsets = Vec::with_capacity(self.datasets.len());
+        for dataset in &self.datasets {
+            name_offsets.push(heap_data.len() as u64);
+            heap_data.extend_from_slice(dataset.name.as_bytes());
+            heap_data.push(0);
+            heap_data.resize((heap_data.len() + 7) / 8 * 8, 0);
+        }
+
+        let root_header_address = SUPERBLOCK_SIZE;
+        let root_header_size = track!(self.root_header(vec![0; 16]))?.len() as u64;
+        let heap_address = root_header_address + root_header_size;
+        let heap_data_address = heap_address + LOCAL_HEAP_HEADER_SIZE;
+        let b_tree_address = heap_data_address + heap_data.len() as u64;
+        let node_address = b_tree_address + B_TREE_NODE_SIZE as u64;
+
+        let mut header_address = node_address + (node_count * SYMBOL_TABLE_NODE_SIZE) as u64;
+        let mut header_addresses = Vec::with_capacity(self.datasets.len());
+        for dataset in &self.datasets {
+            header_addresses.push(header_address);
+            header_address += track!(dataset.object_header(0))?.len() as u64;
+        }
+        let mut data_address = header_address;
+        let mut headers = Vec::with_capacity(self.datasets.len());
+        for dataset in &self.datasets {
+            headers.push(track!(dataset.object_header(data_address))?);
+            data_address += dataset.data.len() as u64;
+        }
+        let end_of_file_address = data_address;
+
+        let mut symbol_table = b_tree_address.to_le_bytes().to_vec();
+        symbol_table.extend_from_slice(&heap_address.to_le_bytes());
+        let root_entry = symbol_table_entry(0, root_header_address, Some(&symbol_table));
+
+        let mut heap = b"HEAP\x00\x00\x00\x00".to_vec();
+        heap.extend_from_slice(&(heap_data.len() as u64).to_le_bytes());
+        heap.extend_from_slice(&LOCAL_HEAP_FREE_NULL.to_le_bytes());
+        heap.extend_from_slice(&heap_data_address.to_le_bytes());
+        heap.extend_from_slice(&heap_data);
+
+        // A single leaf of the group B-tree refers to all symbol table nodes.
+        // The key following each child is the name of the last entry of the child.
+        let mut b_tree = b"TREE\x00\x00".to_vec();
+        b_tree.extend_from_slice(&(node_count as u16).to_le_bytes());
+        b_tree.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+        b_tree.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+        b_tree.extend_from_slice(&0u64.to_le_bytes());
+        let mut nodes = Vec::with_capacity(node_count * SYMBOL_TABLE_NODE_SIZE);
+        let indices = (0..self.datasets.len()).collect::<Vec<_>>();
+        for (i, chunk) in indices.chunks(GROUP_LEAF_NODE_K * 2).enumerate() {
+            let address = node_address + (i * SYMBOL_TABLE_NODE_SIZE) as u64;
+            b_tree.extend_from_slice(&address.to_le_bytes());
+            b_tree.extend_from_slice(&name_offsets[chunk[chunk.len() - 1]].to_le_bytes());
+
+            let start = nodes.len();
+            nodes.extend_from_slice(b"SNOD\x01\x00");
+            nodes.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+            for &j in chunk {
+                nodes.extend_from_slice(&symbol_table_entry(
+                    name_offsets[j],
+                    header_addresses[j],
+                    None,
+                ));
+            }
+            nodes.resize(start + SYMBOL_TABLE_NODE_SIZE, 0);
+        }
+        b_tree.resize(B_TREE_NODE_SIZE, 0);
+
+        let mut bytes = superblock(end_of_file_address, &root_entry);
+        bytes.extend_from_slice(&track!(self.root_header(symbol_table))?);
+        bytes.extend_from_slice(&heap);
+        bytes.extend_from_slice(&b_tree);
+        bytes.extend_from_slice(&nodes);
+        for header in &headers {
+            bytes.extend_from_slice(header);
+        }
+        track!(inner.write_all(&bytes).map_err(Error::from))?;
+        for dataset in &self.datasets {
+            track!(inner.write_all(&dataset.data).map_err(Error::from))?;
+        }
+        track!(inner.flush().map_err(Error::from))?;
+        Ok(inner)
+    }
+
+    /// Writes the file and drops the underlying writer.
+    pub fn close(self) -> Result<()> {
+        track!(self.finish()).map(|_| ())
+    }
+
+    fn root_header(&self, symbol_table: Vec<u8>) -> Result<Vec<u8>> {
+        let mut messages = vec![(0x11, symbol_table)];
+        messages.extend(self.root_attributes.iter().map(|(_, m)| (0x0C, m.clone())));
+        track!(object_header(&messages))
+    }
+}
+impl<W: Write> Drop for Hdf5Writer<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() && !std::thread::panicking() {
+            log::warn!(
+                "`Hdf5Writer` was dropped without `finish` or `close`; no file has been written"
+            );
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DatasetEntry {
+    name: String,
+    shape: Vec<u64>,
+    datatype: Vec<u8>,
+    element_size: usize,
+    data: Vec<u8>,
+    attributes: Vec<(String, Vec<u8>)>,
+}
+impl DatasetEntry {
+    fn object_header(&self, data_address: u64) -> Result<Vec<u8>> {
+        // Version 2 fill value message: late allocation, written if set, and a zero fill value.
+        let mut fill_value = vec![2, 2, 2, 1];
+        fill_value.extend_from_slice(&(self.element_size as u32).to_le_bytes());
+        fill_value.resize(fill_value.len() + self.element_size, 0);
+
+        // Version 3 contiguous layout.
+        let mut layout = vec![3, 1];
+        layout.extend_from_slice(&data_address.to_le_bytes());
+        layout.extend_from_slice(&(self.data.len() as u64).to_le_bytes());
+
+        let mut messages = vec![
+            (0x01, dataspace(&self.shape)),
+            (0x03, self.datatype.clone()),
+            (0x05, fill_value),
+            (0x08, layout),
+        ];
+        messages.extend(self.attributes.iter().map(|(_, m)| (0x0C, m.clone())));
+        track!(object_header(&messages); self.name)
+    }
+}
+
+/// Builds a version 1 dataspace message without maximum dimensions.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#DataspaceMessage
+fn dataspace(shape: &[u64]) -> Vec<u8> {
+    let mut message = vec![1, shape.len() as u8, 0, 0, 0, 0, 0, 0];
+    for &d in shape {
+        message.extend_from_slice(&d.to_le_bytes());
+    }
+    message
+}
+
+/// Builds a version 1 attribute message.
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#AttributeMessage
+fn attribute_message(name: &str, value: &ScalarValue) -> Result<Vec<u8>> {
+    let (datatype, shape, data) = if let ScalarValue::Array(items) = value {
+        track_assert!(
+            !items.is_empty(),
+            ErrorKind::InvalidInput,
+            "Empty array attribute"
+        );
+        let (datatype, mut data) = track!(encode_scalar(&items[0]))?;
+        for item in &items[1..] {
+            let (item_datatype, item_data) = track!(encode_scalar(item))?;
+            track_assert_eq!(
+                item_datatype,
+                datatype,
+                ErrorKind::InvalidInput,
+                "Array attributes must consist of values of the same type"
+            );
+            data.extend_from_slice(&item_data);
+        }
+        (datatype, vec![items.len() as u64], data)
+    } else {
+        let (datatype, data) = track!(encode_scalar(value))?;
+        (datatype, Vec::new(), data)
+    };
+    let dataspace = dataspace(&shape);
+
+    let mut name = name.as_bytes().to_vec();
+    name.push(0);
+    let mut message = vec![1, 0];
+    for field in &[&name, &datatype, &dataspace] {
+        track_assert!(
+            field.len() <= usize::from(u16::MAX),
+            ErrorKind::InvalidInput,
+            "Too long attribute field: {} bytes",
+            field.len()
+        );
+        message.extend_from_slice(&(field.len() as u16).to_le_bytes());
+    }
+
+    // The name, datatype and dataspace are padded to multiples of eight bytes.
+    for field in &[name, datatype, dataspace] {
+        message.extend_from_slice(field);
+        message.resize((message.len() + 7) / 8 * 8, 0);
+    }
+    message.extend_from_slice(&data);
+    Ok(message)
+}
+
+/// Returns the datatype message and the encoded bytes of a scalar value.
+fn encode_scalar(value: &ScalarValue) -> Result<(Vec<u8>, Vec<u8>)> {
+    Ok(match *value {
+        ScalarValue::F32(v) => (f32::datatype_message(), v.to_le_bytes().to_vec()),
+        ScalarValue::F64(v) => (f64::datatype_message(), v.to_le_bytes().to_vec()),
+        ScalarValue::I8(v) => (fixed_point_datatype(1, true), v.to_le_bytes().to_vec()),
+        ScalarValue::I16(v) => (fixed_point_datatype(2, true), v.to_le_bytes().to_vec()),
+        ScalarValue::I32(v) => (fixed_point_datatype(4, true), v.to_le_bytes().to_vec()),
+        ScalarValue::I64(v) => (fixed_point_datatype(8, true), v.to_le_bytes().to_vec()),
+        ScalarValue::U8(v) => (fixed_point_datatype(1, false), v.to_le_bytes().to_vec()),
+        ScalarValue::U16(v) => (fixed_point_datatype(2, false), v.to_le_bytes().to_vec()),
+        ScalarValue::U32(v) => (fixed_point_datatype(4, false), v.to_le_bytes().to_vec()),
+        ScalarValue::U64(v) => (fixed_point_datatype(8, false), v.to_le_bytes().to_vec()),
+        ScalarValue::Bool(v) => (bool_datatype(), vec![u8::from(v)]),
+        ScalarValue::Str(ref v) => {
+            track_assert!(!v.contains('\0'), ErrorKind::InvalidInput; v);
+
+            // The terminator keeps the size of empty strings non-zero.
+            let mut data = v.as_bytes().to_vec();
+            data.push(0);
+            (string_datatype(data.len() as u32, v.is_ascii()), data)
+        }
+        _ => track_panic!(ErrorKind::Unsupported, "Attribute value: {:?}", value),
+    })
+}
+
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#SymbolTableEntry
+fn symbol_table_entry(
+    link_name_offset: u64,
+    object_header_address: u64,
+    scratch_pad: Option<&[u8]>,
+) -> Vec<u8> {
+    let mut entry = link_name_offset.to_le_bytes().to_vec();
+    entry.extend_from_slice(&object_header_address.to_le_bytes());
+    entry.extend_from_slice(&u32::from(scratch_pad.is_some()).to_le_bytes());
+    entry.extend_from_slice(&0u32.to_le_bytes());
+    entry.extend_from_slice(scratch_pad.unwrap_or(&[]));
+    entry.resize(SYMBOL_TABLE_ENTRY_SIZE, 0);
+    entry
+}
+
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#SuperblockVersion0
+fn superblock(end_of_file_address: u64, root_entry: &[u8]) -> Vec<u8> {
+    let mut bytes = b"\x89HDF\r\n\x1a\n".to_vec();
+    bytes.extend_from_slice(&[0, 0, 0, 0, 0, 8, 8, 0]);
+    bytes.extend_from_slice(&(GROUP_LEAF_NODE_K as u16).to_le_bytes());
+    bytes.extend_from_slice(&(GROUP_INTERNAL_NODE_K as u16).to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+    bytes.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+    bytes.extend_from_slice(&end_of_file_address.to_le_bytes());
+    bytes.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+    bytes.extend_from_slice(root_entry);
+    debug_assert_eq!(bytes.len() as u64, SUPERBLOCK_SIZE);
+    bytes
+}
+
+/// Returns the size of a message in a version 1 object header, which is padded to a multiple of eight bytes.
+fn message_size(data: &[u8]) -> Result<u16> {
+    let size = (data.len() + 7) / 8 * 8;
+    track_assert!(
+        size <= usize::from(u16::MAX),
+        ErrorKind::InvalidInput,
+        "Too large header message: {} bytes",
+        data.len()
+    );
+    Ok(size as u16)
+}
+
+/// Builds a version 1 object header containing the given messages (type and body).
+///
+/// https://support.hdfgroup.org/HDF5/doc/H5.format.html#V1ObjectHeaderPrefix
+fn object_header(messages: &[(u16, Vec<u8>)]) -> Result<Vec<u8>> {
+    track_assert!(
+        messages.len() <= usize::from(u16::MAX),
+        ErrorKind::InvalidInput,
+        "Too many header messages: {}",
+        messages.len()
+    );
+    let mut body = Vec::new();
+    for (kind, data) in messages {
+        let size = track!(message_size(data))?;
+        body.extend_from_slice(&kind.to_le_bytes());
+        body.extend_from_slice(&size.to_le_bytes());
+        body.extend_from_slice(&[0; 4]);
+        body.extend_from_slice(data);
+        body.resize(body.len() + usize::from(size) - data.len(), 0);
+    }
+    track_assert!(
+        body.len() <= u32::MAX as usize,
+        ErrorKind::InvalidInput,
+        "Too large object header: {} bytes",
+        body.len()
+    );
+
+    let mut bytes = vec![1, 0];
+    bytes.extend_from_slice(&(messages.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&body);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lowlevel::level0::Superblock;
+    use crate::lowlevel::level2::DatatypeMessage;
+    use crate::{DatatypeClass, Hdf5File, ReaderOptions};
+    use std::io::Cursor;
+    use trackable::result::TopLevelResult;
+
+    #[test]
+    fn float_datasets_round_trip() -> TopLevelResult {
+        let mut writer = Hdf5Writer::new(Vec::new());
+        track!(writer.add_dataset("b", &[3], &[1.5f32, -2.0, 0.25]))?;
+        track!(writer.add_dataset("a", &[2, 2], &[1.0f64, 2.0, 3.0, 4.0]))?;
+        track!(writer.add_dataset("scalar", &[], &[42.0f64]))?;
+        let bytes = track!(writer.finish())?;
+
+        let mut file = track!(Hdf5File::from_bytes(bytes))?;
+        let names = track!(file.member_names("/", crate::LinkOrder::Name))?;
+        assert_eq!(
+            names,
+            Some(vec!["a".to_owned(), "b".to_owned(), "scalar".to_owned()])
+        );
+
+        let mut dataset = track!(file.dataset("/a"))?.expect("no such dataset");
+        assert_eq!(track!(dataset.shape())?, [2, 2]);
+        let (items, _) = track!(track!(dataset.read())?.into_vec::<f64>())?;
+        assert_eq!(items, [1.0, 2.0, 3.0, 4.0]);
+
+        let mut dataset = track!(file.dataset("/b"))?.expect("no such dataset");
+        let (items, _) = track!(track!(dataset.read())?.into_vec::<f32>())?;
+        assert_eq!(items, [1.5, -2.0, 0.25]);
+
+        let mut dataset = track!(file.dataset("/scalar"))?.expect("no such dataset");
+        assert_eq!(track!(dataset.element_count())?, 1);
+        assert_eq!(track!(dataset.read_element(0))?, ScalarValue::F64(42.0));
+        Ok(())
+    }
+
+    #[test]
+    fn integer_datasets_round_trip() -> TopLevelResult {
+        let mut writer = Hdf5Writer::new(Vec::new());
+        track!(writer.add_dataset("i32", &[2, 2], &[1i32, -2, i32::MIN, i32::MAX]))?;
+        track!(writer.add_dataset("i16", &[3], &[i16::MIN, -1i16, i16::MAX]))?;
+        track!(writer.add_dataset("u16", &[2], &[0u16, u16::MAX]))?;
+        track!(writer.add_dataset("u32", &[2], &[0u32, u32::MAX]))?;
+        track!(writer.add_dataset("i64", &[2], &[i64::MIN, -1i64]))?;
+        track!(writer.add_dataset("u64", &[1], &[u64::MAX]))?;
+        let mut file = track!(Hdf5File::from_bytes(track!(writer.finish())?))?;
+
+        let mut dataset = track!(file.dataset("/i32"))?.expect("no such dataset");
+        let datatype = track!(dataset.dtype_message())?;
+        assert_eq!(datatype.size(), 4);
+        match datatype {
+            DatatypeMessage::FixedPoint(t) => assert!(t.is_signed()),
+            t => panic!("unexpected datatype: {:?}", t),
+        }
+        assert_eq!(track!(dataset.fill_value())?, Some(ScalarValue::I32(0)));
+        let (items, _) = track!(track!(dataset.read())?.into_vec::<i32>())?;
+        assert_eq!(items, [1, -2, i32::MIN, i32::MAX]);
+
+        let mut dataset = track!(file.dataset("/i16"))?.expect("no such dataset");
+        assert_eq!(track!(dataset.dtype_message())?.size(), 2);
+        assert_eq!(track!(dataset.fill_value())?, Some(ScalarValue::I16(0)));
+        let (items, _) = track!(track!(dataset.read())?.into_vec::<i16>())?;
+        assert_eq!(items, [i16::MIN, -1, i16::MAX]);
+
+        let mut dataset = track!(file.dataset("/u16"))?.expect("no such dataset");
+        assert_eq!(track!(dataset.fill_value())?, Some(ScalarValue::U16(0)));
+        let (items, _) = track!(track!(dataset.read())?.into_vec::<u16>())?;
+        assert_eq!(items, [0, u16::MAX]);
+
+        let mut dataset = track!(file.dataset("/u32"))?.expect("no such dataset");
+        assert_eq!(track!(dataset.fill_value())?, Some(ScalarValue::U32(0)));
+        let (items, _) = track!(track!(dataset.read())?.into_vec::<u32>())?;
+        assert_eq!(items, [0, u32::MAX]);
+
+        let mut dataset = track!(file.dataset("/i64"))?.expect("no such dataset");
+        let (items, _) = track!(track!(dataset.read())?.into_vec::<i64>())?;
+        assert_eq!(items, [i64::MIN, -1]);
+
+        let mut dataset = track!(file.dataset("/u64"))?.expect("no such dataset");
+        let (items, _) = track!(track!(dataset.read())?.into_vec::<u64>())?;
+        assert_eq!(items, [u64::MAX]);
+        Ok(())
+    }
+
+    #[test]
+    fn attributes_can_be_attached() -> TopLevelResult {
+        let mut writer = Hdf5Writer::new(Vec::new());
+        track!(writer.add_dataset("values", &[2], &[1.0f64, 2.0]))?;
+        track!(writer.attach_attribute("/", "title", ScalarValue::Str("fixture".to_owned())))?;
+        track!(writer.attach_attribute("/values", "units", ScalarValue::Str("m/s".to_owned())))?;
+        track!(writer.attach_attribute("/values", "scale", ScalarValue::F64(0.5)))?;
+        track!(writer.attach_attribute("/values", "valid", ScalarValue::Bool(true)))?;
+        let range = ScalarValue::Array(vec![ScalarValue::I32(-1), ScalarValue::I32(1)]);
+        track!(writer.attach_attribute("/values", "range", range))?;
+
+        assert!(writer
+            .attach_attribute("/values", "scale", ScalarValue::F64(1.0))
+            .is_err());
+        assert!(writer
+            .attach_attribute("/missing", "scale", ScalarValue::F64(1.0))
+            .is_err());
+        let mixed = ScalarValue::Array(vec![ScalarValue::I32(-1), ScalarValue::F64(1.0)]);
+        assert!(writer.attach_attribute("/values", "mixed", mixed).is_err());
+        let bytes = track!(writer.finish())?;
+
+        let mut file = track!(Hdf5File::from_bytes(bytes.clone()))?;
+        let dataset = track!(file.dataset("/values"))?.expect("no such dataset");
+        let (datatype, shape) = track!(dataset.attribute_info("units"))?;
+        assert_eq!(datatype.class(), DatatypeClass::String);
+        assert_eq!(datatype.size(), 4);
+        assert!(shape.is_empty());
+        let (datatype, _) = track!(dataset.attribute_info("scale"))?;
+        assert_eq!(datatype.class(), DatatypeClass::FloatingPoint);
+        let (datatype, _) = track!(dataset.attribute_info("valid"))?;
+        assert!(matches!(datatype, DatatypeMessage::Enumerated(ref t) if t.is_bool()));
+        let (datatype, shape) = track!(dataset.attribute_info("range"))?;
+        assert_eq!(datatype.class(), DatatypeClass::FixedPoint);
+        assert_eq!(shape, [2]);
+        assert_eq!(
+            track!(dataset.read_attribute("units"))?,
+            ScalarValue::Str("m/s".to_owned())
+        );
+        assert_eq!(
+            track!(dataset.read_attribute("scale"))?,
+            ScalarValue::F64(0.5)
+        );
+        assert_eq!(
+            track!(dataset.read_attribute("valid"))?,
+            ScalarValue::Bool(true)
+        );
+        assert_eq!(
+            track!(dataset.read_attribute("range"))?,
+            ScalarValue::Array(vec![ScalarValue::I32(-1), ScalarValue::I32(1)])
+        );
+
+        let mut reader = Cursor::new(&bytes);
+        let superblock = track!(Superblock::from_reader(&mut reader))?;
+        let root = track!(superblock.root_object_header(&mut reader, &ReaderOptions::new()))?;
+        let (datatype, _) = track!(root.attribute_info("title"))?;
+        assert_eq!(datatype.class(), DatatypeClass::String);
+        assert_eq!(
+            track!(root.read_attribute("title"))?,
+            ScalarValue::Str("fixture".to_owned())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn finish_is_required() -> TopLevelResult {
+        let mut buf = Vec::new();
+        let mut writer = Hdf5Writer::new(&mut buf);
+        track!(writer.add_dataset("a", &[2], &[1i32, 2]))?;
+        drop(writer);
+        assert!(Hdf5File::from_bytes(buf.clone()).is_err());
+
+        let mut writer = Hdf5Writer::new(&mut buf);
+        track!(writer.add_dataset("a", &[2], &[1i32, 2]))?;
+        track!(writer.close())?;
+        let superblock = track!(Superblock::from_reader(&buf[..]))?;
+        assert_eq!(superblock.end_of_file_address, buf.len() as u64);
+        let mut file = track!(Hdf5File::from_bytes(buf))?;
+        assert!(track!(file.dataset("/a"))?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn attributes_round_trip() -> TopLevelResult {
+        let values = vec![
+            ScalarValue::F32(1.5),
+            ScalarValue::F64(-0.25),
+            ScalarValue::I8(-8),
+            ScalarValue::I16(-16),
+            ScalarValue::I32(-32),
+            ScalarValue::I64(i64::MIN),
+            ScalarValue::U8(8),
+            ScalarValue::U16(16),
+            ScalarValue::U32(32),
+            ScalarValue::U64(u64::MAX),
+            ScalarValue::Bool(true),
+            ScalarValue::Str("µm".to_owned()),
+            ScalarValue::Str(String::new()),
+            ScalarValue::Array(vec![ScalarValue::U16(1), ScalarValue::U16(2)]),
+            ScalarValue::Array(vec![ScalarValue::Bool(false)]),
+        ];
+        let mut writer = Hdf5Writer::new(Vec::new());
+        track!(writer.add_dataset("values", &[1], &[0i32]))?;
+        for (i, value) in values.iter().enumerate() {
+            track!(writer.attach_attribute("/", &format!("a{}", i), value.clone()))?;
+            track!(writer.attach_attribute("/values", &format!("a{}", i), value.clone()))?;
+        }
+        let mut file = track!(Hdf5File::from_bytes(track!(writer.finish())?))?;
+
+        let root = track!(file.root())?;
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(track!(root.read_attribute(&format!("a{}", i)))?, *value);
+        }
+        let dataset = track!(file.dataset("/values"))?.expect("no such dataset");
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(track!(dataset.read_attribute(&format!("a{}", i)))?, *value);
+        }
+        assert!(dataset.read_attribute("missing").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn many_datasets_round_trip() -> TopLevelResult {
+        let mut writer = Hdf5Writer::new(Vec::new());
+        for i in 0..20 {
+            track!(writer.add_dataset(&format!("d{:02}", i), &[1], &[f64::from(i)]))?;
+        }
+        let mut file = track!(Hdf5File::from_bytes(track!(writer.finish())?))?;
+        for i in 0..20 {
+            let name = format!("/d{:02}", i);
+            let mut dataset = track!(file.dataset(&name))?.expect("no such dataset");
+            let (items, _) = track!(track!(dataset.read())?.into_vec::<f64>())?;
+            assert_eq!(items, [f64::from(i)]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_datasets_are_rejected() {
+        let mut writer = Hdf5Writer::new(Vec::new());
+        assert!(writer.add_dataset("a", &[3], &[1.0f64]).is_err());
+        assert!(writer.add_dataset("a/b", &[1], &[1.0f64]).is_err());
+        assert!(writer.add_dataset("a", &[1], &[1.0f64]).is_ok());
+        assert!(writer.add_dataset("a", &[1], &[1.0f64]).is_err());
+        assert!(writer.add_dataset("b", &[1; 33], &[1.0f64]).is_err());
+    }
+
+    #[test]
+    fn too_large_attributes_are_rejected() -> TopLevelResult {
+        let mut writer = Hdf5Writer::new(Vec::new());
+        track!(writer.add_dataset("a", &[1], &[1.0f64]))?;
+
+        let error = writer
+            .attach_attribute("/a", &"n".repeat(70000), ScalarValue::U8(0))
+            .unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+
+        let items = vec![ScalarValue::F64(0.0); 10000];
+        let error = writer
+            .attach_attribute("/a", "items", ScalarValue::Array(items))
+            .unwrap_err();
+        assert_eq!(*error.kind(), ErrorKind::InvalidInput);
+
+        let items = vec![ScalarValue::F64(0.0); 8000];
+        track!(writer.attach_attribute("/a", "items", ScalarValue::Array(items)))?;
+        let mut file = track!(Hdf5File::from_bytes(track!(writer.finish())?))?;
+        let dataset = track!(file.dataset("/a"))?.expect("no such dataset");
+        let (_, shape) = track!(dataset.attribute_info("items"))?;
+        assert_eq!(shape, [8000]);
+        Ok(())
+    }
+}